@@ -1,4 +1,5 @@
 use alloc::format;
+use core::hash::BuildHasherDefault;
 use core::hash::Hash;
 use core::hash::Hasher;
 use core::hint::black_box;
@@ -14,6 +15,7 @@ use criterion::criterion_main;
 use hashbrown::hash_table::Entry as HashbrownEntry;
 use hashbrown::hash_table::HashTable as HashbrownHashTable;
 use hop_hash::HashTable as HopHashTable;
+use hop_hash::hash_map::HashMap as HopHashMap;
 use rand::Rng;
 use rand::SeedableRng;
 use rand::TryRngCore;
@@ -632,6 +634,249 @@ fn bench_find_hit<TestItem: KeyValuePair, const MAX_SIZE: usize>(c: &mut Criteri
     group.finish();
 }
 
+fn bench_get_many<const MAX_SIZE: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_many");
+    group.plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic));
+
+    for size in SIZES[..=MAX_SIZE].iter() {
+        let mut map =
+            HopHashMap::<String, u64, BuildHasherDefault<SipHasher>>::with_capacity(*size);
+        let capacity = map.capacity();
+        let mut keys = (0..capacity as u64)
+            .map(|i| format!("key_{:016X}", i))
+            .collect::<Vec<_>>();
+        for (i, key) in keys.iter().enumerate() {
+            map.insert(key.clone(), i as u64);
+        }
+        keys.shuffle(&mut SmallRng::from_os_rng());
+        let key_refs = keys.iter().collect::<Vec<&String>>();
+
+        group.throughput(Throughput::Elements(capacity as u64));
+        group.bench_function(BenchmarkId::new("get", size), |b| {
+            b.iter(|| {
+                for key in key_refs.iter() {
+                    black_box(map.get(key));
+                }
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("get_many", size), |b| {
+            b.iter(|| black_box(map.get_many(&key_refs)))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_for_each<TestItem: KeyValuePair, const MAX_SIZE: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("for_each_{}", core::any::type_name::<TestItem>()));
+    group.plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic));
+
+    for size in SIZES[..=MAX_SIZE].iter() {
+        // Fill to a quarter of capacity so most tag groups are sparse.
+        let mut hop_table = HopHashTable::<TestItem>::with_capacity(*size);
+        let len = (hop_table.capacity() / 4).max(1);
+        for item in (0..len as u64).map(TestItem::new) {
+            let hash = item.hash_key();
+            match hop_table.entry(hash, |v| v.eq_key(&item), |v| v.hash_key()) {
+                hop_hash::hash_table::Entry::Vacant(entry) => {
+                    entry.insert(item);
+                }
+                hop_hash::hash_table::Entry::Occupied(_) => unreachable!(),
+            }
+        }
+
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_function(BenchmarkId::new("iter_for_each", size), |b| {
+            b.iter(|| {
+                hop_table.iter().for_each(|v| {
+                    black_box(v);
+                })
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("for_each", size), |b| {
+            b.iter(|| {
+                hop_table.for_each(|v| {
+                    black_box(v);
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_retain<TestItem: KeyValuePair, const MAX_SIZE: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("retain_{}", core::any::type_name::<TestItem>()));
+    group.plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic));
+
+    for size in SIZES[..=MAX_SIZE].iter() {
+        // Fill to 40% of capacity so the scan skips a mix of empty and occupied slots.
+        let mut hop_table = HopHashTable::<TestItem>::with_capacity(*size);
+        let len = (hop_table.capacity() * 2 / 5).max(1);
+        for item in (0..len as u64).map(TestItem::new) {
+            let hash = item.hash_key();
+            match hop_table.entry(hash, |v| v.eq_key(&item), |v| v.hash_key()) {
+                hop_hash::hash_table::Entry::Vacant(entry) => {
+                    entry.insert(item);
+                }
+                hop_hash::hash_table::Entry::Occupied(_) => unreachable!(),
+            }
+        }
+
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_function(BenchmarkId::new("retain_all", size), |b| {
+            b.iter(|| {
+                hop_table.retain(
+                    |v| {
+                        black_box(v);
+                        true
+                    },
+                    |v| v.hash_key(),
+                )
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_insert_all_unique(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_all_unique");
+    group.sample_size(20);
+
+    let size = 1 << 20;
+    let snapshot = (0..size as u64)
+        .map(SmallTestItem::new)
+        .map(|item| (item.hash_key(), item))
+        .collect::<Vec<_>>();
+
+    group.throughput(Throughput::Elements(size as u64));
+    group.bench_function(BenchmarkId::new("entry_loop", size), |b| {
+        b.iter(|| {
+            let mut table = HopHashTable::<SmallTestItem>::with_capacity(0);
+            for (hash, item) in snapshot.iter().cloned() {
+                match table.entry(hash, |v| v.eq_key(&item), |v| v.hash_key()) {
+                    hop_hash::hash_table::Entry::Vacant(entry) => {
+                        entry.insert(item);
+                    }
+                    hop_hash::hash_table::Entry::Occupied(_) => unreachable!(),
+                }
+            }
+            black_box(table)
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("insert_all_unique", size), |b| {
+        b.iter(|| {
+            let mut table = HopHashTable::<SmallTestItem>::with_capacity(0);
+            table.insert_all_unique(snapshot.iter().cloned(), |v| v.hash_key());
+            black_box(table)
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_extend_reserve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extend_reserve");
+
+    let size = 100_000;
+    let mut rng = SmallRng::from_os_rng();
+    let pairs = (0..size)
+        .map(|_| (rng.random::<u64>(), rng.random::<u64>()))
+        .collect::<Vec<(u64, u64)>>();
+
+    group.throughput(Throughput::Elements(size as u64));
+    group.bench_function(BenchmarkId::new("insert_loop", size), |b| {
+        b.iter(|| {
+            let mut map = HopHashMap::<u64, u64, BuildHasherDefault<SipHasher>>::default();
+            for &(k, v) in pairs.iter() {
+                map.insert(k, v);
+            }
+            black_box(map)
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("extend", size), |b| {
+        b.iter(|| {
+            let mut map = HopHashMap::<u64, u64, BuildHasherDefault<SipHasher>>::default();
+            map.extend(pairs.iter().copied());
+            black_box(map)
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_insert_reserved(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_reserved");
+
+    let size = 100_000;
+    let mut rng = SmallRng::from_os_rng();
+    let pairs = (0..size)
+        .map(|_| (rng.random::<u64>(), rng.random::<u64>()))
+        .collect::<Vec<(u64, u64)>>();
+
+    group.throughput(Throughput::Elements(size as u64));
+    group.bench_function(BenchmarkId::new("insert", size), |b| {
+        b.iter(|| {
+            let mut map = HopHashMap::<u64, u64, BuildHasherDefault<SipHasher>>::default();
+            map.reserve(pairs.len());
+            for &(k, v) in pairs.iter() {
+                map.insert(k, v);
+            }
+            black_box(map)
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("insert_reserved", size), |b| {
+        b.iter(|| {
+            let mut map = HopHashMap::<u64, u64, BuildHasherDefault<SipHasher>>::default();
+            map.reserve(pairs.len());
+            for &(k, v) in pairs.iter() {
+                map.insert_reserved(k, v);
+            }
+            black_box(map)
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_extend_from_slice<const MAX_SIZE: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extend_from_slice");
+    group.plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic));
+
+    let mut rng = SmallRng::from_os_rng();
+
+    for size in SIZES[..=MAX_SIZE].iter() {
+        let pairs = (0..*size)
+            .map(|_| (rng.random::<u32>(), rng.random::<u32>()))
+            .collect::<Vec<(u32, u32)>>();
+
+        group.throughput(Throughput::Elements(*size as u64));
+        group.bench_function(BenchmarkId::new("extend", size), |b| {
+            b.iter(|| {
+                let mut map = HopHashMap::<u32, u32, BuildHasherDefault<SipHasher>>::default();
+                map.extend(pairs.iter().copied());
+                black_box(map)
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("extend_from_slice", size), |b| {
+            b.iter(|| {
+                let mut map = HopHashMap::<u32, u32, BuildHasherDefault<SipHasher>>::default();
+                map.extend_from_slice(&pairs);
+                black_box(map)
+            })
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_find_miss<TestItem: KeyValuePair, const MAX_SIZE: usize>(c: &mut Criterion) {
     let mut group = c.benchmark_group(format!("find_miss_{}", core::any::type_name::<TestItem>()));
     group.plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic));
@@ -869,6 +1114,55 @@ fn bench_iteration_warm<TestItem: KeyValuePair, const MAX_SIZE: usize>(c: &mut C
     group.finish();
 }
 
+fn bench_iteration_prefetch_distance<TestItem: KeyValuePair, const MAX_SIZE: usize>(
+    c: &mut Criterion
+) {
+    fn iterate<TestItem, const DISTANCE: usize>(table: &HopHashTable<TestItem>) -> usize {
+        let mut count = 0;
+        for item in table.iter_with_prefetch_distance::<DISTANCE>() {
+            black_box(item);
+            count += 1;
+        }
+        count
+    }
+
+    let mut group = c.benchmark_group(format!(
+        "iteration_prefetch_distance_{}",
+        core::any::type_name::<TestItem>()
+    ));
+    group.plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic));
+
+    for size in SIZES[..=MAX_SIZE].iter() {
+        let mut hop_table = HopHashTable::<TestItem>::with_capacity(0);
+        let hop_capacity = HopHashTable::<TestItem>::with_capacity(*size).capacity();
+        for i in 0..hop_capacity as u64 {
+            let item = TestItem::new(i);
+            match hop_table.entry(item.hash_key(), |v| v.eq_key(&item), |v| v.hash_key()) {
+                hop_hash::hash_table::Entry::Vacant(entry) => {
+                    entry.insert(item);
+                }
+                hop_hash::hash_table::Entry::Occupied(_) => unreachable!(),
+            }
+        }
+
+        group.throughput(Throughput::Elements(hop_capacity as u64));
+        group.bench_function(BenchmarkId::new("distance_1", size), |b| {
+            b.iter(|| black_box(iterate::<TestItem, 1>(&hop_table)))
+        });
+        group.bench_function(BenchmarkId::new("distance_2", size), |b| {
+            b.iter(|| black_box(iterate::<TestItem, 2>(&hop_table)))
+        });
+        group.bench_function(BenchmarkId::new("distance_4", size), |b| {
+            b.iter(|| black_box(iterate::<TestItem, 4>(&hop_table)))
+        });
+        group.bench_function(BenchmarkId::new("distance_8", size), |b| {
+            b.iter(|| black_box(iterate::<TestItem, 8>(&hop_table)))
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_iteration_cold<TestItem: KeyValuePair, const MAX_SIZE: usize>(c: &mut Criterion) {
     let mut group = c.benchmark_group(format!(
         "iteration_cold_{}",
@@ -1030,6 +1324,73 @@ fn bench_drain<TestItem: KeyValuePair, const MAX_SIZE: usize>(c: &mut Criterion)
     group.finish();
 }
 
+fn bench_clear<TestItem: KeyValuePair, const MAX_SIZE: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("clear_{}", core::any::type_name::<TestItem>()));
+    group.plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic));
+
+    for size in SIZES[..=MAX_SIZE].iter() {
+        let mut hop_table = HopHashTable::<TestItem>::with_capacity(*size);
+        let hop_capacity = hop_table.capacity();
+        for item in (0..hop_capacity as u64).map(TestItem::new) {
+            let hash = item.hash_key();
+            match hop_table.entry(hash, |v| v.eq_key(&item), |v| v.hash_key()) {
+                hop_hash::hash_table::Entry::Vacant(entry) => {
+                    entry.insert(item);
+                }
+                hop_hash::hash_table::Entry::Occupied(_) => unreachable!(),
+            }
+        }
+
+        let mut hashbrown_table = HashbrownHashTable::<TestItem>::with_capacity(*size);
+        let hashbrown_capacity = hashbrown_table.capacity();
+        for item in (0..hashbrown_capacity as u64).map(TestItem::new) {
+            let hash = item.hash_key();
+            match hashbrown_table.entry(hash, |v| v.eq_key(&item), |v| v.hash_key()) {
+                HashbrownEntry::Vacant(entry) => {
+                    entry.insert(item);
+                }
+                HashbrownEntry::Occupied(_) => unreachable!(),
+            }
+        }
+
+        group.throughput(Throughput::Elements(hop_capacity as u64));
+        group.bench_function(BenchmarkId::new("hop_hash", size), |b| {
+            b.iter_batched(
+                || hop_table.clone(),
+                |mut table| {
+                    table.clear();
+                    black_box(table)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+
+        group.throughput(Throughput::Elements(hashbrown_capacity as u64));
+        group.bench_function(BenchmarkId::new("hashbrown", size), |b| {
+            b.iter_batched(
+                || hashbrown_table.clone(),
+                |mut table| {
+                    table.clear();
+                    black_box(table)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+
+        // Clearing an already-empty table should cost the same at every size.
+        hop_table.clear();
+        group.throughput(Throughput::Elements(1));
+        group.bench_function(BenchmarkId::new("hop_hash_empty", size), |b| {
+            b.iter(|| {
+                hop_table.clear();
+                black_box(&mut hop_table);
+            })
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_mixed_workload<TestItem: KeyValuePair, const MAX_SIZE: usize>(c: &mut Criterion) {
     let mut group = c.benchmark_group(format!(
         "mixed_workload_{}",
@@ -1673,6 +2034,20 @@ criterion_group!(
     bench_find_hit::<SmallTestItem, 8>,
     bench_find_hit::<TestItem, 8>,
     bench_find_hit::<LargeTestItem, 5>,
+    bench_get_many::<8>,
+    bench_extend_from_slice::<8>,
+    bench_extend_reserve,
+    bench_insert_reserved,
+    bench_insert_all_unique,
+    bench_for_each::<SmallTestItem, 8>,
+    bench_for_each::<TestItem, 8>,
+    bench_for_each::<LargeTestItem, 5>,
+    bench_retain::<SmallTestItem, 8>,
+    bench_retain::<TestItem, 8>,
+    bench_retain::<LargeTestItem, 5>,
+    bench_clear::<SmallTestItem, 8>,
+    bench_clear::<TestItem, 8>,
+    bench_clear::<LargeTestItem, 5>,
     bench_find_miss::<SmallTestItem, 8>,
     bench_find_miss::<TestItem, 8>,
     bench_find_miss::<LargeTestItem, 5>,
@@ -1682,6 +2057,7 @@ criterion_group!(
     bench_iteration_warm::<SmallTestItem, 8>,
     bench_iteration_warm::<TestItem, 8>,
     bench_iteration_warm::<LargeTestItem, 5>,
+    bench_iteration_prefetch_distance::<LargeTestItem, 5>,
     bench_iteration_cold::<SmallTestItem, 8>,
     bench_iteration_cold::<TestItem, 8>,
     bench_iteration_cold::<LargeTestItem, 5>,
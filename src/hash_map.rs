@@ -1,11 +1,27 @@
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
 use core::fmt::Debug;
 use core::hash::BuildHasher;
 use core::hash::Hash;
+use core::iter::FusedIterator;
 
+use crate::hash_table::CapacityInfo;
 use crate::hash_table::Entry as TableEntry;
+use crate::hash_table::EntryHandle;
 use crate::hash_table::HashTable;
 use crate::hash_table::TryEntryError;
+use crate::hash_table::TryReserveError;
+use crate::hash_table::extend_reserve_hint;
+use crate::hash_table::merge_reserve_hint;
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsEvent;
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsSink;
+
+/// How many keys ahead `get_many` prefetches. Far enough to cover a cache
+/// miss, close enough that the prefetched lines are still resident.
+const GET_MANY_PREFETCH_DISTANCE: usize = 8;
 
 /// A hash map implemented using the hopscotch HashTable as the underlying
 /// storage.
@@ -26,10 +42,19 @@ use crate::hash_table::TryEntryError;
 /// - **Lookup**: O(1) with a bounded probe distance of at most 16 buckets (8
 ///   for 8-way).
 /// - **Deletion**: O(1) with the same bounded probe distance as lookup.
+///
+/// # Cloning
+///
+/// Cloning copies the table layout as-is and clones every key and value into
+/// the same slot, so no key is rehashed and the clone iterates in the same
+/// order as the original. The cost is one allocation plus one `clone` of
+/// each `K` and `V`. Use [`try_clone`](Self::try_clone) to handle allocation
+/// failure instead of aborting.
 #[derive(Clone)]
 pub struct HashMap<K, V, S> {
     table: HashTable<(K, V)>,
     hash_builder: S,
+    auto_shrink: Option<f64>,
 }
 
 impl<K, V, S> PartialEq for HashMap<K, V, S>
@@ -42,6 +67,10 @@ where
         &self,
         other: &Self,
     ) -> bool {
+        // The length is the only thing that can be compared without probing.
+        // A cached content hash would go stale whenever a value is changed
+        // through `get_mut` or `iter_mut`, and maps built with different hasher
+        // seeds hash the same keys differently.
         if self.len() != other.len() {
             return false;
         }
@@ -124,6 +153,7 @@ where
     /// assert!(map.capacity() >= 100);
     /// # }
     /// ```
+    #[track_caller]
     pub fn with_capacity_and_hasher(
         capacity: usize,
         hash_builder: S,
@@ -131,9 +161,51 @@ where
         Self {
             table: HashTable::with_capacity(capacity),
             hash_builder,
+            auto_shrink: None,
+        }
+    }
+
+    /// Creates a new hash map with the specified capacity and hasher builder,
+    /// using as little memory as possible for small capacities.
+    ///
+    /// See [`HashTable::with_capacity_compact`] for when the compact layout
+    /// is used.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// use hop_hash::hash_map::HashMap;
+    ///
+    /// let map: HashMap<u64, u64, _> =
+    ///     HashMap::with_capacity_compact_and_hasher(8, RandomState::new());
+    /// assert!(map.capacity() >= 8);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn with_capacity_compact_and_hasher(
+        capacity: usize,
+        hash_builder: S,
+    ) -> Self {
+        Self {
+            table: HashTable::with_capacity_compact(capacity),
+            hash_builder,
+            auto_shrink: None,
         }
     }
 
+    /// Returns a reference to the map's hasher builder.
+    ///
+    /// Hashes computed with `map.hasher().hash_one(key)` can be passed to
+    /// methods that accept a precomputed hash, such as
+    /// [`remove_precomputed`](Self::remove_precomputed).
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
     /// Returns the number of elements in the map.
     ///
     /// # Examples
@@ -175,7 +247,9 @@ where
     /// Returns the current capacity of the map.
     ///
     /// The capacity represents the maximum number of elements the map can
-    /// hold before it needs to resize.
+    /// hold before it needs to resize. It is smaller than the number of
+    /// allocated slots, which is reported by
+    /// [`slot_capacity`](Self::slot_capacity).
     ///
     /// # Examples
     ///
@@ -192,6 +266,70 @@ where
         self.table.capacity()
     }
 
+    /// Returns the total number of slots allocated by the map.
+    ///
+    /// Slots are allocated in groups of 16, and the map resizes before all of
+    /// them are filled, so this is larger than [`capacity`](Self::capacity)
+    /// for any non-empty allocation. Use it to reason about memory use rather
+    /// than about when the next resize happens.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let map: HashMap<i32, String> = HashMap::with_capacity(100);
+    /// assert!(map.slot_capacity() > map.capacity());
+    /// # }
+    /// ```
+    pub fn slot_capacity(&self) -> usize {
+        self.table.slot_capacity()
+    }
+
+    /// Returns the map's capacity metrics in a single call.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let map: HashMap<i32, i32> = HashMap::with_capacity(100);
+    /// let info = map.capacity_info();
+    /// assert_eq!(info.live, map.capacity());
+    /// assert_eq!(info.slots, map.slot_capacity());
+    /// assert_eq!(info.load_factor, 0.0);
+    /// # }
+    /// ```
+    pub fn capacity_info(&self) -> CapacityInfo {
+        self.table.capacity_info()
+    }
+
+    /// Returns `true` if `additional` more elements fit in the map without a
+    /// resize.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let map: HashMap<i32, i32> = HashMap::with_capacity(100);
+    /// assert!(map.capacity_fits(100));
+    /// assert!(!map.capacity_fits(map.capacity() + 1));
+    /// # }
+    /// ```
+    pub fn capacity_fits(
+        &self,
+        additional: usize,
+    ) -> bool {
+        self.table.capacity_fits(additional)
+    }
+
     /// Removes all elements from the map.
     ///
     /// This operation preserves the map's allocated capacity.
@@ -214,6 +352,36 @@ where
         self.table.clear();
     }
 
+    /// Removes all elements from the map and shrinks its capacity down to
+    /// what `floor` elements need.
+    ///
+    /// This combines [`clear`](Self::clear) with a shrink, so a map that
+    /// briefly held many more key-value pairs than usual does not keep that
+    /// allocation. A `floor` of zero frees the allocation entirely. The
+    /// capacity never grows, so a map already smaller than `floor` needs
+    /// keeps its allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<u64, u64> = (0..100_000).map(|i| (i, i)).collect();
+    /// map.clear_to_capacity(16);
+    /// assert!(map.is_empty());
+    /// assert!(map.capacity() >= 16);
+    /// assert!(map.capacity() < 1000);
+    /// # }
+    /// ```
+    pub fn clear_to_capacity(
+        &mut self,
+        floor: usize,
+    ) {
+        self.table.clear_to_capacity(floor);
+    }
+
     /// Shrinks the capacity of the map as much as possible.
     ///
     /// This method will shrink the underlying storage to fit the current number
@@ -250,7 +418,187 @@ where
             .shrink_to_fit(|k| self.hash_builder.hash_one(&k.0));
     }
 
+    /// Registers `sink` to receive this map's operation events, or stops
+    /// reporting them with `None`.
+    ///
+    /// The map reports [`get`](Self::get), [`get_mut`](Self::get_mut),
+    /// [`get_key_value`](Self::get_key_value) and
+    /// [`contains_key`](Self::contains_key) as [`MetricsEvent::Get`], and
+    /// [`remove`](Self::remove), [`remove_entry`](Self::remove_entry) and
+    /// [`remove_if`](Self::remove_if) as [`MetricsEvent::Remove`]. Every
+    /// insertion reports [`MetricsEvent::Insert`], whether it goes through
+    /// [`insert`](Self::insert), [`try_insert`](Self::try_insert),
+    /// [`insert_unique_unchecked`](Self::insert_unique_unchecked), or an
+    /// [`entry`](Self::entry) that stores a value. Any operation that grows
+    /// the table also reports [`MetricsEvent::Resize`]. Other operations,
+    /// such as iteration, are not reported. Clones of the map report to the
+    /// same sink.
+    ///
+    /// Without a sink, each reported operation costs a single check of this
+    /// setting.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use core::sync::atomic::AtomicUsize;
+    /// use core::sync::atomic::Ordering;
+    ///
+    /// use hop_hash::HashMap;
+    /// use hop_hash::MetricsEvent;
+    /// use hop_hash::MetricsSink;
+    ///
+    /// struct InsertCounter(AtomicUsize);
+    ///
+    /// impl MetricsSink for InsertCounter {
+    ///     fn record(
+    ///         &self,
+    ///         event: MetricsEvent,
+    ///     ) {
+    ///         if event == MetricsEvent::Insert {
+    ///             self.0.fetch_add(1, Ordering::Relaxed);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// static INSERTS: InsertCounter = InsertCounter(AtomicUsize::new(0));
+    ///
+    /// let mut map: HashMap<i32, i32> = HashMap::new();
+    /// map.set_metrics_sink(Some(&INSERTS));
+    /// map.insert(1, 1);
+    /// map.insert(1, 2);
+    /// assert_eq!(INSERTS.0.load(Ordering::Relaxed), 2);
+    /// # }
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn set_metrics_sink(
+        &mut self,
+        sink: Option<&'static dyn MetricsSink>,
+    ) {
+        self.table.set_metrics_sink(sink);
+    }
+
+    /// Returns the sink registered with
+    /// [`set_metrics_sink`](Self::set_metrics_sink), if any.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_sink(&self) -> Option<&'static dyn MetricsSink> {
+        self.table.metrics_sink()
+    }
+
+    /// Sets the minimum load below which the map shrinks itself.
+    ///
+    /// With `Some(min_load)`, the map shrinks whenever a [`remove`],
+    /// [`remove_entry`], [`retain`], [`retain_mut`] or [`extract_if`] leaves
+    /// fewer than `min_load * capacity()` pairs in the map. `None`, the
+    /// default, never shrinks automatically.
+    ///
+    /// Unlike [`shrink_to_fit`], an automatic shrink leaves room for twice
+    /// the current length. Together with the cap on `min_load`, this keeps a
+    /// map that alternates inserts and removals around a shrink point from
+    /// reallocating on every operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_load` is not within `0.0..=0.25`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, i32> = HashMap::new();
+    /// map.set_auto_shrink(Some(0.25));
+    /// for i in 0..10_000 {
+    ///     map.insert(i, i);
+    /// }
+    /// let grown = map.capacity();
+    ///
+    /// map.retain(|&k, _| k < 100);
+    /// assert!(map.capacity() < grown);
+    /// # }
+    /// ```
+    ///
+    /// [`shrink_to_fit`]: Self::shrink_to_fit
+    /// [`remove`]: Self::remove
+    /// [`remove_entry`]: Self::remove_entry
+    /// [`retain`]: Self::retain
+    /// [`retain_mut`]: Self::retain_mut
+    /// [`extract_if`]: Self::extract_if
+    pub fn set_auto_shrink(
+        &mut self,
+        min_load: Option<f64>,
+    ) {
+        assert!(
+            min_load.is_none_or(|load| (0.0..=0.25).contains(&load)),
+            "auto-shrink load must be within 0.0..=0.25"
+        );
+        self.auto_shrink = min_load;
+    }
+
+    /// Returns the minimum load set by
+    /// [`set_auto_shrink`](Self::set_auto_shrink), if any.
+    pub fn auto_shrink(&self) -> Option<f64> {
+        self.auto_shrink
+    }
+
+    /// Shrinks the map if auto-shrinking is enabled and the load has dropped
+    /// below the configured minimum.
+    fn maybe_auto_shrink(&mut self) {
+        if let Some(min_load) = self.auto_shrink {
+            self.table
+                .shrink_if_below(min_load, |k| self.hash_builder.hash_one(&k.0));
+        }
+    }
+
+    /// Clones the map, returning an error instead of aborting if the
+    /// allocation fails.
+    ///
+    /// This performs the same layout-preserving clone as [`Clone::clone`]. If
+    /// the allocation fails, nothing is cloned and `self` is left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, String> = HashMap::new();
+    /// map.insert(1, "a".to_string());
+    ///
+    /// let cloned = map.try_clone().unwrap();
+    /// assert_eq!(map, cloned);
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::AllocError`] if the allocator fails to
+    /// provide memory for the new map.
+    pub fn try_clone(&self) -> Result<Self, TryReserveError>
+    where
+        K: Clone,
+        V: Clone,
+        S: Clone,
+    {
+        Ok(Self {
+            table: self.table.try_clone()?,
+            hash_builder: self.hash_builder.clone(),
+            auto_shrink: self.auto_shrink,
+        })
+    }
+
     /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics with "capacity overflow" if the required capacity cannot be
+    /// represented. Use [`try_reserve`](Self::try_reserve) to handle this
+    /// instead.
+    #[track_caller]
     pub fn reserve(
         &mut self,
         additional: usize,
@@ -259,6 +607,166 @@ where
             .reserve(additional, |k| self.hash_builder.hash_one(&k.0));
     }
 
+    /// Tries to reserve capacity for at least `additional` more elements.
+    ///
+    /// On error the map is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    /// use hop_hash::TryReserveError;
+    ///
+    /// let mut map: HashMap<i32, i32> = HashMap::new();
+    /// assert!(map.try_reserve(10).is_ok());
+    /// assert_eq!(
+    ///     map.try_reserve(usize::MAX),
+    ///     Err(TryReserveError::CapacityOverflow)
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::CapacityOverflow`] if the required capacity
+    /// cannot be represented, or [`TryReserveError::AllocError`] if the
+    /// allocator fails.
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), TryReserveError> {
+        self.table
+            .try_reserve(additional, |k| self.hash_builder.hash_one(&k.0))
+    }
+
+    /// Reserves capacity for at least `additional` more elements, returning
+    /// `true` if the map had to be resized.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, i32> = HashMap::new();
+    /// assert!(map.reserve_reporting(10));
+    /// assert!(!map.reserve_reporting(10));
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn reserve_reporting(
+        &mut self,
+        additional: usize,
+    ) -> bool {
+        self.table
+            .reserve_reporting(additional, |k| self.hash_builder.hash_one(&k.0))
+    }
+
+    /// Reserves capacity so that after `additional` more insertions the map is
+    /// loaded to at most `target_load`.
+    ///
+    /// Load is measured against [`capacity`](Self::capacity), as in
+    /// [`capacity_info`](Self::capacity_info). The map reserves room for
+    /// `(len + additional) / target_load` elements. Capacities are rounded up
+    /// to the next geometry, so the final load is usually somewhat below
+    /// `target_load`. A lower target trades memory for shorter probe
+    /// sequences, which suits long-lived, read-heavy maps.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<u32, u32> = HashMap::new();
+    /// map.reserve_at_load(1_000, 0.5);
+    /// assert!(map.capacity() >= 2_000);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target_load` is not within `(0.0, 1.0]`, or with "capacity
+    /// overflow" if the required capacity cannot be represented.
+    #[track_caller]
+    pub fn reserve_at_load(
+        &mut self,
+        additional: usize,
+        target_load: f64,
+    ) {
+        assert!(
+            target_load > 0.0 && target_load <= 1.0,
+            "target load must be within (0.0, 1.0]"
+        );
+
+        let planned = self.len().saturating_add(additional);
+        // Float-to-int casts saturate, so an unrepresentable capacity still reaches
+        // the overflow panic in `reserve`. `f64::ceil` needs `std`, so round up by
+        // hand.
+        let exact = planned as f64 / target_load;
+        let mut capacity = exact as usize;
+        if (capacity as f64) < exact {
+            capacity = capacity.saturating_add(1);
+        }
+        self.reserve(capacity.saturating_sub(self.len()));
+    }
+
+    /// Moves every key-value pair from `other` into this map.
+    ///
+    /// For keys present in both maps, `f(key, existing, incoming)` is called
+    /// to fold the incoming value into the existing one. All other pairs are
+    /// inserted as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut a: HashMap<&str, u32> = HashMap::new();
+    /// a.insert("apple", 1);
+    /// a.insert("pear", 2);
+    ///
+    /// let mut b: HashMap<&str, u32> = HashMap::new();
+    /// b.insert("apple", 3);
+    /// b.insert("plum", 4);
+    ///
+    /// a.merge_with(b, |_, existing, incoming| *existing += incoming);
+    /// assert_eq!(a.get(&"apple"), Some(&4));
+    /// assert_eq!(a.get(&"pear"), Some(&2));
+    /// assert_eq!(a.get(&"plum"), Some(&4));
+    /// # }
+    /// ```
+    pub fn merge_with(
+        &mut self,
+        other: HashMap<K, V, S>,
+        mut f: impl FnMut(&K, &mut V, V),
+    ) {
+        self.reserve(merge_reserve_hint(self.len(), other.len()));
+
+        for (key, value) in other {
+            let hash = self.hash_builder.hash_one(&key);
+            match self.table.entry(
+                hash,
+                |(k, _)| k == &key,
+                |kv| self.hash_builder.hash_one(&kv.0),
+            ) {
+                TableEntry::Occupied(mut entry) => {
+                    let (existing_key, existing) = entry.get_mut();
+                    f(existing_key, existing, value);
+                }
+                TableEntry::Vacant(entry) => {
+                    entry.insert((key, value));
+                }
+            }
+        }
+    }
+
     /// Retains only the elements specified by the predicate.
     ///
     /// In other words, remove all pairs `(k, v)` for which `f(&k, &v)` returns
@@ -289,6 +797,7 @@ where
     ) {
         self.table
             .retain(|(k, v)| f(k, v), |(k, _)| self.hash_builder.hash_one(k));
+        self.maybe_auto_shrink();
     }
 
     /// Retains only the elements specified by the predicate, with mutable
@@ -328,6 +837,46 @@ where
     ) {
         self.table
             .retain_mut(|(k, v)| f(k, v), |(k, _)| self.hash_builder.hash_one(k));
+        self.maybe_auto_shrink();
+    }
+
+    /// Retains only the elements specified by the predicate, passing each
+    /// key's hash to the predicate.
+    ///
+    /// Each key is hashed once, and that hash is given to `f` and reused to
+    /// remove the pair if `f` returns `false`. This avoids hashing twice when
+    /// the predicate depends on the hash, for example when splitting a map
+    /// into shards by hash bits.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, i32> = (0..100).map(|i| (i, i)).collect();
+    /// let mut moved = HashMap::new();
+    ///
+    /// map.retain_with_hash(|hash, &k, &mut v| {
+    ///     if hash % 2 == 0 {
+    ///         return true;
+    ///     }
+    ///     moved.insert(k, v);
+    ///     false
+    /// });
+    /// assert_eq!(map.len() + moved.len(), 100);
+    /// # }
+    /// ```
+    pub fn retain_with_hash(
+        &mut self,
+        mut f: impl FnMut(u64, &K, &mut V) -> bool,
+    ) {
+        self.table.retain_with_hash(
+            |hash, (k, v)| f(hash, k, v),
+            |(k, _)| self.hash_builder.hash_one(k),
+        );
+        self.maybe_auto_shrink();
     }
 
     /// Creates an iterator that removes and yields pairs from the map for which
@@ -366,6 +915,7 @@ where
                 Box::new(move |(k, v)| f(k, v)),
                 Box::new(|(k, _)| self.hash_builder.hash_one(k)),
             ),
+            auto_shrink: self.auto_shrink,
         }
     }
 
@@ -393,7 +943,11 @@ where
         key: K,
         value: V,
     ) -> Option<V> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Insert);
         let hash = self.hash_builder.hash_one(&key);
+        // `entry` resizes before the key is stored, so `rehash` only ever sees keys
+        // already in the table and the new key is hashed exactly once.
         match self.table.entry(
             hash,
             |(k, _)| k == &key,
@@ -410,30 +964,65 @@ where
         }
     }
 
-    /// Returns a reference to the value corresponding to the key.
+    /// Inserts a key-value pair into a map that already has room for it.
     ///
-    /// # Examples
+    /// This behaves like [`insert`](Self::insert), but skips the capacity
+    /// check `insert` performs before every lookup. It is meant for loops
+    /// that follow a [`reserve`](Self::reserve) covering every pair they
+    /// insert.
+    ///
+    /// # Contract
+    ///
+    /// The map must have spare capacity, i.e. `len() < capacity()`, even if
+    /// `key` is already present. Debug builds panic otherwise. Release builds
+    /// stay memory safe and still insert the pair, but the map may fill past
+    /// its target load factor until it next resizes.
+    ///
+    /// # Examples
     ///
     /// ```rust
     /// # #[cfg(any(feature = "std", feature = "foldhash"))]
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let mut map: HashMap<i32, &str> = HashMap::new();
-    /// map.insert(1, "a");
-    /// assert_eq!(map.get(&1), Some(&"a"));
-    /// assert_eq!(map.get(&2), None);
+    /// let mut map: HashMap<u32, u32> = HashMap::new();
+    /// map.reserve(100);
+    /// for i in 0..100 {
+    ///     map.insert_reserved(i, i * 2);
+    /// }
+    /// assert_eq!(map.len(), 100);
+    /// assert_eq!(map.get(&7), Some(&14));
     /// # }
     /// ```
-    pub fn get(
-        &self,
-        key: &K,
-    ) -> Option<&V> {
-        let hash = self.hash_builder.hash_one(key);
-        self.table.find(hash, |(k, _)| k == key).map(|(_, v)| v)
+    #[track_caller]
+    pub fn insert_reserved(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Option<V> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Insert);
+        let hash = self.hash_builder.hash_one(&key);
+        match self.table.entry_reserved(
+            hash,
+            |(k, _)| k == &key,
+            |kv| self.hash_builder.hash_one(&kv.0),
+        ) {
+            TableEntry::Occupied(mut entry) => {
+                let old_value = core::mem::replace(&mut entry.get_mut().1, value);
+                Some(old_value)
+            }
+            TableEntry::Vacant(entry) => {
+                entry.insert((key, value));
+                None
+            }
+        }
     }
 
-    /// Returns the key-value pair corresponding to the supplied key.
+    /// Inserts every key-value pair from a slice of `Copy` pairs.
+    ///
+    /// This reserves once up front for `pairs.len()` elements. Later pairs
+    /// overwrite the values of earlier pairs with the same key.
     ///
     /// # Examples
     ///
@@ -442,23 +1031,32 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let mut map: HashMap<i32, &str> = HashMap::new();
-    /// map.insert(1, "a");
-    /// assert_eq!(map.get_key_value(&1), Some((&1, &"a")));
-    /// assert_eq!(map.get_key_value(&2), None);
+    /// let mut map: HashMap<u32, u32> = HashMap::new();
+    /// map.extend_from_slice(&[(1, 10), (2, 20), (1, 11)]);
+    /// assert_eq!(map.len(), 2);
+    /// assert_eq!(map.get(&1), Some(&11));
     /// # }
     /// ```
-    pub fn get_key_value(
-        &self,
-        key: &K,
-    ) -> Option<(&K, &V)> {
-        let hash = self.hash_builder.hash_one(key);
-        self.table
-            .find(hash, |(k, _)| k == key)
-            .map(|(k, v)| (k, v))
+    pub fn extend_from_slice(
+        &mut self,
+        pairs: &[(K, V)],
+    ) where
+        K: Copy,
+        V: Copy,
+    {
+        self.reserve(pairs.len());
+        for &(key, value) in pairs {
+            self.insert(key, value);
+        }
     }
 
-    /// Returns a mutable reference to the value corresponding to the key.
+    /// Inserts every key-value pair from an iterator like
+    /// [`extend`](Extend::extend), and returns how many of them overwrote the
+    /// value of a key already in the map.
+    ///
+    /// Pairs that added a new key are not counted, so the number of new keys
+    /// is the number of pairs minus the returned count. A key repeated within
+    /// `iter` counts as an overwrite on each repeat.
     ///
     /// # Examples
     ///
@@ -467,23 +1065,29 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let mut map: HashMap<i32, &str> = HashMap::new();
-    /// map.insert(1, "a");
-    /// if let Some(x) = map.get_mut(&1) {
-    ///     *x = "b";
-    /// }
-    /// assert_eq!(map.get(&1), Some(&"b"));
+    /// let mut map: HashMap<u32, u32> = (0..4).map(|i| (i, i)).collect();
+    /// let overwritten = map.extend_reporting((2..6).map(|i| (i, i * 10)));
+    /// assert_eq!(overwritten, 2);
+    /// assert_eq!(map.len(), 6);
+    /// assert_eq!(map.get(&3), Some(&30));
     /// # }
     /// ```
-    pub fn get_mut(
+    pub fn extend_reporting<I: IntoIterator<Item = (K, V)>>(
         &mut self,
-        key: &K,
-    ) -> Option<&mut V> {
-        let hash = self.hash_builder.hash_one(key);
-        self.table.find_mut(hash, |(k, _)| k == key).map(|(_, v)| v)
+        iter: I,
+    ) -> usize {
+        let iter = iter.into_iter();
+        self.reserve(extend_reserve_hint(iter.size_hint()));
+        let mut overwritten = 0;
+        for (k, v) in iter {
+            if self.insert(k, v).is_some() {
+                overwritten += 1;
+            }
+        }
+        overwritten
     }
 
-    /// Returns `true` if the map contains a value for the specified key.
+    /// Returns a reference to the value corresponding to the key.
     ///
     /// # Examples
     ///
@@ -494,19 +1098,26 @@ where
     ///
     /// let mut map: HashMap<i32, &str> = HashMap::new();
     /// map.insert(1, "a");
-    /// assert!(map.contains_key(&1));
-    /// assert!(!map.contains_key(&2));
+    /// assert_eq!(map.get(&1), Some(&"a"));
+    /// assert_eq!(map.get(&2), None);
     /// # }
     /// ```
-    pub fn contains_key(
+    pub fn get(
         &self,
         key: &K,
-    ) -> bool {
-        self.get(key).is_some()
+    ) -> Option<&V> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Get);
+        let hash = self.hash_builder.hash_one(key);
+        self.table.find(hash, |(k, _)| k == key).map(|(_, v)| v)
     }
 
-    /// Removes a key from the map, returning the value at the key if the key
-    /// was previously in the map.
+    /// Returns references to the values corresponding to each of the keys.
+    ///
+    /// The result has one slot per key, in the same order as `keys`.
+    /// Duplicate keys are allowed. Lookups are software-prefetched a few
+    /// keys ahead, which makes this faster than calling [`get`] in a loop
+    /// for large batches against large maps.
     ///
     /// # Examples
     ///
@@ -517,20 +1128,41 @@ where
     ///
     /// let mut map: HashMap<i32, &str> = HashMap::new();
     /// map.insert(1, "a");
-    /// assert_eq!(map.remove(&1), Some("a"));
-    /// assert_eq!(map.remove(&1), None);
+    /// map.insert(2, "b");
+    /// assert_eq!(
+    ///     map.get_many(&[&1, &3, &2, &1]),
+    ///     vec![Some(&"a"), None, Some(&"b"), Some(&"a")]
+    /// );
     /// # }
     /// ```
-    pub fn remove(
-        &mut self,
-        key: &K,
-    ) -> Option<V> {
-        let hash = self.hash_builder.hash_one(key);
-        self.table.remove(hash, |(k, _)| k == key).map(|(_, v)| v)
+    ///
+    /// [`get`]: HashMap::get
+    pub fn get_many(
+        &self,
+        keys: &[&K],
+    ) -> Vec<Option<&V>> {
+        let hashes: Vec<u64> = keys
+            .iter()
+            .map(|key| self.hash_builder.hash_one(key))
+            .collect();
+
+        let mut values = Vec::with_capacity(keys.len());
+        for (i, (key, &hash)) in keys.iter().zip(hashes.iter()).enumerate() {
+            if let Some(&ahead) = hashes.get(i + GET_MANY_PREFETCH_DISTANCE) {
+                self.table.prefetch_hash(ahead);
+            }
+            values.push(self.table.find(hash, |(k, _)| k == *key).map(|(_, v)| v));
+        }
+        values
     }
 
-    /// Removes a key from the map, returning the stored key and value if the
-    /// key was previously in the map.
+    /// Calls `f` with each key and a mutable reference to its value, or
+    /// `None` if the key is not present.
+    ///
+    /// Keys are visited in the order given, and duplicates are visited once
+    /// per occurrence. Like [`get_many`], lookups are prefetched a few keys
+    /// ahead. Only one value is borrowed at a time, so keys do not need to be
+    /// distinct.
     ///
     /// # Examples
     ///
@@ -539,22 +1171,46 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let mut map: HashMap<i32, &str> = HashMap::new();
-    /// map.insert(1, "a");
-    /// assert_eq!(map.remove_entry(&1), Some((1, "a")));
-    /// assert_eq!(map.remove_entry(&1), None);
+    /// let mut map: HashMap<&str, i32> = HashMap::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let mut missing = vec![];
+    /// map.for_each_mut_by_keys(&[&"a", &"c", &"a"], |key, value| match value {
+    ///     Some(value) => *value += 10,
+    ///     None => missing.push(*key),
+    /// });
+    /// assert_eq!(map.get(&"a"), Some(&21));
+    /// assert_eq!(map.get(&"b"), Some(&2));
+    /// assert_eq!(missing, ["c"]);
     /// # }
     /// ```
-    pub fn remove_entry(
+    ///
+    /// [`get_many`]: HashMap::get_many
+    pub fn for_each_mut_by_keys(
         &mut self,
-        key: &K,
-    ) -> Option<(K, V)> {
-        let hash = self.hash_builder.hash_one(key);
-        self.table.remove(hash, |(k, _)| k == key)
+        keys: &[&K],
+        mut f: impl FnMut(&K, Option<&mut V>),
+    ) {
+        let hashes: Vec<u64> = keys
+            .iter()
+            .map(|key| self.hash_builder.hash_one(key))
+            .collect();
+
+        for (i, (key, &hash)) in keys.iter().zip(hashes.iter()).enumerate() {
+            if let Some(&ahead) = hashes.get(i + GET_MANY_PREFETCH_DISTANCE) {
+                self.table.prefetch_hash(ahead);
+            }
+            f(
+                key,
+                self.table
+                    .find_mut(hash, |(k, _)| k == *key)
+                    .map(|(_, v)| v),
+            );
+        }
     }
 
-    /// Gets the given key's corresponding entry in the map for in-place
-    /// manipulation.
+    /// Returns the key-value pair corresponding to the supplied key.
     ///
     /// # Examples
     ///
@@ -564,34 +1220,28 @@ where
     /// use hop_hash::HashMap;
     ///
     /// let mut map: HashMap<i32, &str> = HashMap::new();
-    ///
-    /// map.entry(1).or_insert("a");
-    /// map.entry(2).or_insert("b");
-    ///
-    /// assert_eq!(map.get(&1), Some(&"a"));
-    /// assert_eq!(map.get(&2), Some(&"b"));
+    /// map.insert(1, "a");
+    /// assert_eq!(map.get_key_value(&1), Some((&1, &"a")));
+    /// assert_eq!(map.get_key_value(&2), None);
     /// # }
     /// ```
-    pub fn entry(
-        &mut self,
-        key: K,
-    ) -> Entry<'_, K, V> {
-        let hash = self.hash_builder.hash_one(&key);
-        match self.table.entry(
-            hash,
-            |(k, _)| k == &key,
-            |kv| self.hash_builder.hash_one(&kv.0),
-        ) {
-            TableEntry::Occupied(entry) => Entry::Occupied(OccupiedEntry { entry }),
-            TableEntry::Vacant(entry) => Entry::Vacant(VacantEntry { entry, key }),
-        }
+    pub fn get_key_value(
+        &self,
+        key: &K,
+    ) -> Option<(&K, &V)> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Get);
+        let hash = self.hash_builder.hash_one(key);
+        self.table
+            .find(hash, |(k, _)| k == key)
+            .map(|(k, v)| (k, v))
     }
 
-    /// Tries to get the given key's corresponding entry in the map for in-place
-    /// manipulation, without resizing the table.
+    /// Returns the stored key and a mutable reference to its value, inserting
+    /// `f()` first if the key is not present.
     ///
-    /// If the table is full or no free slot can be found, returns an error with
-    /// the key.
+    /// If an equal key is already in the map, the returned key is the one
+    /// stored in the map and `key` is dropped.
     ///
     /// # Examples
     ///
@@ -600,35 +1250,37 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let mut map: HashMap<i32, &str> = HashMap::with_capacity(10);
+    /// let mut map: HashMap<String, u32> = HashMap::new();
+    /// let (key, count) = map.get_or_insert_kv("a".to_string(), || 0);
+    /// *count += 1;
+    /// assert_eq!(key, "a");
     ///
-    /// match map.try_entry(1) {
-    ///     Ok(entry) => {
-    ///         entry.or_insert("a");
-    ///     }
-    ///     Err((key, _)) => println!("Failed to get entry for key: {}", key),
-    /// }
+    /// let (_, count) = map.get_or_insert_kv("a".to_string(), || 0);
+    /// assert_eq!(*count, 1);
     /// # }
     /// ```
-    pub fn try_entry(
+    pub fn get_or_insert_kv(
         &mut self,
         key: K,
-    ) -> Result<Entry<'_, K, V>, (K, TryEntryError)> {
+        f: impl FnOnce() -> V,
+    ) -> (&K, &mut V) {
         let hash = self.hash_builder.hash_one(&key);
-        match self.table.try_entry(hash, |(k, _)| k == &key) {
-            Ok(TableEntry::Occupied(entry)) => Ok(Entry::Occupied(OccupiedEntry { entry })),
-            Ok(TableEntry::Vacant(entry)) => Ok(Entry::Vacant(VacantEntry { entry, key })),
-            Err(e) => Err((key, e)),
-        }
+        let (k, v) = match self.table.entry(
+            hash,
+            |(k, _)| k == &key,
+            |kv| self.hash_builder.hash_one(&kv.0),
+        ) {
+            TableEntry::Occupied(entry) => entry.into_mut(),
+            TableEntry::Vacant(entry) => {
+                #[cfg(feature = "metrics")]
+                crate::metrics::record(entry.metrics_sink(), MetricsEvent::Insert);
+                entry.insert((key, f()))
+            }
+        };
+        (k, v)
     }
 
-    /// Tries to insert a key-value pair into the map without resizing.
-    ///
-    /// If the map did not have this key present, `Ok(None)` is returned.
-    /// If the map did have this key present, the value is updated, and the old
-    /// value is returned as `Ok(Some(old_value))`.
-    /// If the table is full or no free slot can be found, returns an error with
-    /// the key and value.
+    /// Returns a mutable reference to the value corresponding to the key.
     ///
     /// # Examples
     ///
@@ -637,35 +1289,28 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let mut map: HashMap<i32, &str> = HashMap::with_capacity(10);
-    /// match map.try_insert(37, "a") {
-    ///     Ok(None) => println!("Inserted successfully"),
-    ///     Ok(Some(old)) => println!("Updated, old value: {}", old),
-    ///     Err((k, v, _)) => println!("Failed to insert: {} -> {}", k, v),
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// if let Some(x) = map.get_mut(&1) {
+    ///     *x = "b";
     /// }
+    /// assert_eq!(map.get(&1), Some(&"b"));
     /// # }
     /// ```
-    pub fn try_insert(
+    pub fn get_mut(
         &mut self,
-        key: K,
-        value: V,
-    ) -> Result<Option<V>, (K, V, TryEntryError)> {
-        match self.try_entry(key) {
-            Ok(Entry::Occupied(mut entry)) => {
-                let old_value = entry.insert(value);
-                Ok(Some(old_value))
-            }
-            Ok(Entry::Vacant(entry)) => {
-                entry.insert(value);
-                Ok(None)
-            }
-            Err((key, e)) => Err((key, value, e)),
-        }
+        key: &K,
+    ) -> Option<&mut V> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Get);
+        let hash = self.hash_builder.hash_one(key);
+        self.table.find_mut(hash, |(k, _)| k == key).map(|(_, v)| v)
     }
 
-    /// Returns an iterator over the key-value pairs of the map.
+    /// Replaces the value for `key` if it is present, returning the old value.
     ///
-    /// The iterator yields `(&K, &V)` pairs in an arbitrary order.
+    /// Unlike [`insert`](Self::insert), this never adds a new entry: if the key
+    /// is absent, `value` is dropped and `None` is returned.
     ///
     /// # Examples
     ///
@@ -676,23 +1321,30 @@ where
     ///
     /// let mut map: HashMap<i32, &str> = HashMap::new();
     /// map.insert(1, "a");
-    /// map.insert(2, "b");
-    ///
-    /// for (key, value) in map.iter() {
-    ///     println!("Key: {}, Value: {}", key, value);
-    /// }
+    /// assert_eq!(map.replace_value(&1, "b"), Some("a"));
+    /// assert_eq!(map.replace_value(&2, "c"), None);
+    /// assert_eq!(map.len(), 1);
     /// # }
     /// ```
-    pub fn iter(&self) -> Iter<'_, K, V> {
-        Iter {
-            inner: self.table.iter(),
-        }
+    pub fn replace_value(
+        &mut self,
+        key: &K,
+        value: V,
+    ) -> Option<V> {
+        self.get_mut(key)
+            .map(|existing| core::mem::replace(existing, value))
     }
 
-    /// Returns an iterator over the key-value pairs of the map with mutable
-    /// references to the values.
+    /// Computes a new value for `key` from its current value, if any.
     ///
-    /// The iterator yields `(&K, &mut V)` pairs in an arbitrary order.
+    /// `f` receives the key and the value currently stored for it, moved out
+    /// of the map. Returning `Some` stores the new value, and returning
+    /// `None` leaves the key absent. This expresses insert, update and remove
+    /// with a single key lookup. Returns a reference to the stored value, or
+    /// `None` if the key ends up absent.
+    ///
+    /// If the key is present, `f` receives the key stored in the map and
+    /// `key` is dropped. If `f` panics, a present key is removed from the map.
     ///
     /// # Examples
     ///
@@ -701,25 +1353,45 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let mut map: HashMap<i32, String> = HashMap::new();
-    /// map.insert(1, "a".to_string());
-    /// map.insert(2, "b".to_string());
-    ///
-    /// for (_key, value) in map.iter_mut() {
-    ///     value.make_ascii_uppercase();
-    /// }
+    /// let mut counts: HashMap<&str, u32> = HashMap::new();
+    /// let bump = |_: &&str, count: Option<u32>| Some(count.unwrap_or(0) + 1);
+    /// counts.compute("a", bump);
+    /// assert_eq!(counts.compute("a", bump), Some(&mut 2));
     ///
-    /// assert_eq!(map.get(&1), Some(&"A".to_string()));
-    /// assert_eq!(map.get(&2), Some(&"B".to_string()));
+    /// // Returning `None` removes the key.
+    /// assert_eq!(counts.compute("a", |_, _| None), None);
+    /// assert!(counts.is_empty());
     /// # }
     /// ```
-    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
-        IterMut {
-            inner: self.table.iter_mut(),
+    pub fn compute(
+        &mut self,
+        key: K,
+        f: impl FnOnce(&K, Option<V>) -> Option<V>,
+    ) -> Option<&mut V> {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.table.remove(hash, |(k, _)| k == &key) {
+            Some((key, old_value)) => {
+                let new_value = f(&key, Some(old_value));
+                self.store_computed(hash, key, new_value)
+            }
+            None => {
+                let value = f(&key, None)?;
+                // The lookup above already ruled the key out, so it is placed
+                // without comparing it to anything.
+                Some(&mut self.insert_computed(hash, key, value).1)
+            }
         }
     }
 
-    /// Returns an iterator over the keys of the map.
+    /// Computes a new value for `key` from its current value, if it is
+    /// present.
+    ///
+    /// This is [`compute`](Self::compute) for keys that may only be updated
+    /// or removed: if `key` is absent, `f` is not called and `None` is
+    /// returned. Otherwise `f` receives the stored key and value, and its
+    /// result replaces the value (`Some`) or removes the key (`None`).
+    ///
+    /// If `f` panics, the key is removed from the map.
     ///
     /// # Examples
     ///
@@ -728,19 +1400,59 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let mut map: HashMap<i32, &str> = HashMap::new();
-    /// map.insert(1, "a");
-    /// map.insert(2, "b");
-    ///
-    /// let keys: Vec<_> = map.keys().collect();
-    /// assert_eq!(keys.len(), 2);
+    /// let mut stock: HashMap<&str, u32> = HashMap::new();
+    /// stock.insert("apples", 1);
+    /// let take_one = |_: &&str, n: u32| n.checked_sub(1).filter(|&n| n > 0);
+    /// assert_eq!(stock.compute_if_present(&"pears", take_one), None);
+    /// assert_eq!(stock.compute_if_present(&"apples", take_one), None);
+    /// assert!(stock.is_empty());
     /// # }
     /// ```
-    pub fn keys(&self) -> Keys<'_, K, V> {
-        Keys { inner: self.iter() }
+    pub fn compute_if_present(
+        &mut self,
+        key: &K,
+        f: impl FnOnce(&K, V) -> Option<V>,
+    ) -> Option<&mut V> {
+        let hash = self.hash_builder.hash_one(key);
+        let (key, old_value) = self.table.remove(hash, |(k, _)| k == key)?;
+        let new_value = f(&key, old_value);
+        self.store_computed(hash, key, new_value)
     }
 
-    /// Returns an iterator over the values of the map.
+    /// Puts a removed key back with its computed value, or finishes its
+    /// removal if there is none.
+    fn store_computed(
+        &mut self,
+        hash: u64,
+        key: K,
+        value: Option<V>,
+    ) -> Option<&mut V> {
+        match value {
+            // The key's slot was just freed, so this never resizes.
+            Some(value) => Some(&mut self.insert_computed(hash, key, value).1),
+            None => {
+                self.maybe_auto_shrink();
+                None
+            }
+        }
+    }
+
+    /// Places a key known to be absent, reusing its hash.
+    fn insert_computed(
+        &mut self,
+        hash: u64,
+        key: K,
+        value: V,
+    ) -> &mut (K, V) {
+        self.table.insert_unique_unchecked(
+            hash,
+            (key, value),
+            |a, b| a.0 == b.0,
+            |kv| self.hash_builder.hash_one(&kv.0),
+        )
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
     ///
     /// # Examples
     ///
@@ -751,17 +1463,28 @@ where
     ///
     /// let mut map: HashMap<i32, &str> = HashMap::new();
     /// map.insert(1, "a");
-    /// map.insert(2, "b");
-    ///
-    /// let values: Vec<_> = map.values().collect();
-    /// assert_eq!(values.len(), 2);
+    /// assert!(map.contains_key(&1));
+    /// assert!(!map.contains_key(&2));
     /// # }
     /// ```
-    pub fn values(&self) -> Values<'_, K, V> {
-        Values { inner: self.iter() }
+    pub fn contains_key(
+        &self,
+        key: &K,
+    ) -> bool {
+        self.get(key).is_some()
     }
 
-    /// Returns an iterator over mutable references to the values of the map.
+    /// Inserts a key-value pair without checking whether the key is already
+    /// present, returning references to the stored key and value.
+    ///
+    /// This skips the lookup that [`insert`](Self::insert) performs, which is
+    /// useful when building a map from keys that are known to be distinct.
+    /// The caller must ensure the key is not already in the map. Violating
+    /// this is not undefined behavior, but the map will hold two entries for
+    /// the key and lookups may return either one.
+    ///
+    /// With the `debug-checks` feature enabled, debug builds look the key up
+    /// first and panic if it is already present.
     ///
     /// # Examples
     ///
@@ -770,32 +1493,56 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let mut map: HashMap<i32, String> = HashMap::new();
-    /// map.insert(1, "a".to_string());
-    /// map.insert(2, "b".to_string());
-    ///
-    /// for value in map.values_mut() {
-    ///     value.make_ascii_uppercase();
-    /// }
-    ///
-    /// assert_eq!(map.get(&1), Some(&"A".to_string()));
-    /// assert_eq!(map.get(&2), Some(&"B".to_string()));
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// let (key, value) = map.insert_unique_unchecked(1, "a");
+    /// *value = "b";
+    /// assert_eq!(*key, 1);
+    /// assert_eq!(map.get(&1), Some(&"b"));
     /// # }
     /// ```
-    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
-        ValuesMut {
-            inner: self.iter_mut(),
-        }
+    #[track_caller]
+    pub fn insert_unique_unchecked(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> (&K, &mut V) {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Insert);
+        let hash = self.hash_builder.hash_one(&key);
+        let (k, v) = self.table.insert_unique_unchecked(
+            hash,
+            (key, value),
+            |a, b| a.0 == b.0,
+            |kv| self.hash_builder.hash_one(&kv.0),
+        );
+        (k, v)
     }
 
-    /// Returns an iterator that removes and yields all key-value pairs from the
-    /// map.
+    /// Removes a key from the map, returning the value at the key if the key
+    /// was previously in the map.
     ///
-    /// After calling `drain()`, the map will be empty.
+    /// # Examples
     ///
-    /// Calling `mem::forget` on the returned iterator will leak all key-value
-    /// pairs in the map that have not yet been yielded. This can cause memory
-    /// leaks.
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.remove(&1), Some("a"));
+    /// assert_eq!(map.remove(&1), None);
+    /// # }
+    /// ```
+    pub fn remove(
+        &mut self,
+        key: &K,
+    ) -> Option<V> {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    /// Removes a key from the map, returning the stored key and value if the
+    /// key was previously in the map.
     ///
     /// # Examples
     ///
@@ -806,26 +1553,30 @@ where
     ///
     /// let mut map: HashMap<i32, &str> = HashMap::new();
     /// map.insert(1, "a");
-    /// map.insert(2, "b");
-    ///
-    /// let pairs: Vec<_> = map.drain().collect();
-    /// assert!(map.is_empty());
-    /// assert_eq!(pairs.len(), 2);
+    /// assert_eq!(map.remove_entry(&1), Some((1, "a")));
+    /// assert_eq!(map.remove_entry(&1), None);
     /// # }
     /// ```
-    pub fn drain(&mut self) -> Drain<'_, K, V> {
-        Drain {
-            inner: self.table.drain(),
+    pub fn remove_entry(
+        &mut self,
+        key: &K,
+    ) -> Option<(K, V)> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Remove);
+        let hash = self.hash_builder.hash_one(key);
+        let removed = self.table.remove(hash, |(k, _)| k == key);
+        if removed.is_some() {
+            self.maybe_auto_shrink();
         }
+        removed
     }
-}
 
-impl<K, V, S> HashMap<K, V, S>
-where
-    K: Hash + Eq,
-    S: BuildHasher + Default,
-{
-    /// Creates a new hash map using the default hasher builder.
+    /// Removes a key from the map if its value satisfies `pred`, returning
+    /// the removed value.
+    ///
+    /// The key is looked up once. If it is absent, `pred` is not called and
+    /// `None` is returned. If `pred` returns `false`, the pair is left in
+    /// place and `None` is returned.
     ///
     /// # Examples
     ///
@@ -834,16 +1585,41 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let map: HashMap<i32, String> = HashMap::new();
-    /// assert!(map.is_empty());
+    /// let mut map: HashMap<&str, u32> = HashMap::new();
+    /// map.insert("a", 0);
+    /// map.insert("b", 3);
+    /// assert_eq!(map.remove_if(&"a", |&count| count == 0), Some(0));
+    /// assert_eq!(map.remove_if(&"b", |&count| count == 0), None);
+    /// assert_eq!(map.len(), 1);
     /// # }
     /// ```
-    pub fn new() -> Self {
-        Self::with_hasher(S::default())
+    pub fn remove_if(
+        &mut self,
+        key: &K,
+        pred: impl FnOnce(&V) -> bool,
+    ) -> Option<V> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Remove);
+        let hash = self.hash_builder.hash_one(key);
+        // Keys are unique, so the matching pair is the only one `pred` is asked
+        // about. Taking it out of the cell lets a `FnOnce` serve as part of the
+        // table's `Fn` predicate.
+        let pred = core::cell::Cell::new(Some(pred));
+        let removed = self.table.remove(hash, |(k, v)| {
+            k == key && pred.take().is_some_and(|pred| pred(v))
+        });
+        if removed.is_some() {
+            self.maybe_auto_shrink();
+        }
+        removed.map(|(_, v)| v)
     }
 
-    /// Creates a new hash map with the specified capacity using the default
-    /// hasher builder.
+    /// Removes the pair that `handle` refers to, without looking its key up.
+    ///
+    /// Handles come from [`Entry::or_insert_handle`] or
+    /// [`OccupiedEntry::handle`] and stay valid until the map is next
+    /// modified. Returns `None` if the map was modified after the handle was
+    /// created, or if the handle came from another map.
     ///
     /// # Examples
     ///
@@ -852,453 +1628,2293 @@ where
     /// # {
     /// use hop_hash::HashMap;
     ///
-    /// let map: HashMap<i32, String> = HashMap::with_capacity(100);
-    /// assert!(map.capacity() >= 100);
+    /// let mut map: HashMap<&str, u32> = HashMap::new();
+    /// let (_, handle) = map.entry("a").or_insert_handle(1);
+    /// assert_eq!(map.remove_by_handle(handle), Some(("a", 1)));
+    /// assert!(map.is_empty());
     /// # }
     /// ```
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self::with_capacity_and_hasher(capacity, S::default())
-    }
-}
-
-impl<K, V, S> Default for HashMap<K, V, S>
-where
-    K: Hash + Eq,
-    S: BuildHasher + Default,
-{
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-/// A view into a single entry in the map, which may either be vacant or
-/// occupied.
-///
-/// This enum is constructed from the [`entry`] method on [`HashMap`].
-///
-/// [`entry`]: HashMap::entry
-pub enum Entry<'a, K, V> {
-    /// A vacant entry.
-    Vacant(VacantEntry<'a, K, V>),
-    /// An occupied entry.
-    Occupied(OccupiedEntry<'a, K, V>),
-}
-
-impl<'a, K, V> Entry<'a, K, V> {
-    /// Inserts a default value if the entry is vacant and returns a mutable
-    /// reference.
-    pub fn or_insert(
-        self,
-        default: V,
-    ) -> &'a mut V {
-        match self {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => entry.insert(default),
+    pub fn remove_by_handle(
+        &mut self,
+        handle: EntryHandle,
+    ) -> Option<(K, V)> {
+        let removed = self.table.remove_by_handle(handle);
+        if removed.is_some() {
+            self.maybe_auto_shrink();
         }
+        removed
     }
 
-    /// Inserts a value computed from a closure if the entry is vacant and
-    /// returns a mutable reference.
-    pub fn or_insert_with<F>(
-        self,
-        default: F,
-    ) -> &'a mut V
-    where
-        F: FnOnce() -> V,
-    {
-        match self {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => entry.insert(default()),
+    /// Removes a key from the map using a hash the caller already computed,
+    /// returning the value at the key if it was present.
+    ///
+    /// `hash` must be the map's hash of `key`, i.e.
+    /// `map.hasher().hash_one(key)`. This is checked in debug builds. In
+    /// release builds a wrong hash is a logic error that corrupts the map: if
+    /// the key happens to be found through the wrong hash, it is removed from
+    /// the bookkeeping of the wrong bucket. Later lookups, inserts and
+    /// removals on the map may then miss keys, misbehave or panic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use std::hash::BuildHasher;
+    ///
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// let hash = map.hasher().hash_one(&1);
+    /// assert_eq!(map.get(&1), Some(&"a"));
+    /// assert_eq!(map.remove_precomputed(&1, hash), Some("a"));
+    /// # }
+    /// ```
+    pub fn remove_precomputed(
+        &mut self,
+        key: &K,
+        hash: u64,
+    ) -> Option<V> {
+        debug_assert_eq!(
+            hash,
+            self.hash_builder.hash_one(key),
+            "remove_precomputed: hash does not match key"
+        );
+
+        let removed = self.table.remove(hash, |(k, _)| k == key);
+        if removed.is_some() {
+            self.maybe_auto_shrink();
         }
+        removed.map(|(_, v)| v)
     }
 
-    /// Provides in-place mutable access to an occupied entry before any
-    /// potential inserts.
-    pub fn and_modify<F>(
-        self,
-        f: F,
-    ) -> Self
+    /// Removes every key in `keys` that is present in the map, returning the
+    /// removed pairs.
+    ///
+    /// Absent keys are skipped. Each key is hashed once, and removals are
+    /// software-prefetched a few keys ahead like [`get_many`]. If
+    /// auto-shrinking is enabled, the map is checked once after all removals
+    /// rather than after each one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// map.insert(3, "c");
+    ///
+    /// let removed = map.remove_many(&[1, 4, 3]);
+    /// assert_eq!(removed, vec![(1, "a"), (3, "c")]);
+    /// assert_eq!(map.len(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`get_many`]: HashMap::get_many
+    pub fn remove_many<'a>(
+        &mut self,
+        keys: impl IntoIterator<Item = &'a K>,
+    ) -> Vec<(K, V)>
     where
-        F: FnOnce(&mut V),
+        K: 'a,
     {
-        match self {
-            Entry::Occupied(mut entry) => {
-                f(entry.get_mut());
-                Entry::Occupied(entry)
+        let keys: Vec<(&K, u64)> = keys
+            .into_iter()
+            .map(|key| (key, self.hash_builder.hash_one(key)))
+            .collect();
+
+        let mut removed = Vec::new();
+        for (i, &(key, hash)) in keys.iter().enumerate() {
+            if let Some(&(_, ahead)) = keys.get(i + GET_MANY_PREFETCH_DISTANCE) {
+                self.table.prefetch_hash(ahead);
             }
-            Entry::Vacant(entry) => Entry::Vacant(entry),
+            removed.extend(self.table.remove(hash, |(k, _)| k == key));
         }
-    }
 
-    /// Returns a reference to this entry's key.
-    pub fn key(&self) -> &K {
-        match self {
-            Entry::Occupied(entry) => entry.key(),
-            Entry::Vacant(entry) => entry.key(),
+        if !removed.is_empty() {
+            self.maybe_auto_shrink();
         }
+        removed
     }
-}
-
-impl<'a, K, V> Entry<'a, K, V>
-where
-    V: Default,
-{
-    /// Inserts the default value if the entry is vacant and returns a mutable
-    /// reference.
-    pub fn or_default(self) -> &'a mut V {
-        self.or_insert_with(Default::default)
-    }
-}
 
-/// A view into a vacant entry in the map.
-pub struct VacantEntry<'a, K, V> {
-    entry: crate::hash_table::VacantEntry<'a, (K, V)>,
-    key: K,
-}
-
-impl<'a, K, V> VacantEntry<'a, K, V> {
-    /// Gets a reference to the key that would be used when inserting a value.
+    /// Gets the given key's corresponding entry in the map for in-place
+    /// manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    ///
+    /// map.entry(1).or_insert("a");
+    /// map.entry(2).or_insert("b");
+    ///
+    /// assert_eq!(map.get(&1), Some(&"a"));
+    /// assert_eq!(map.get(&2), Some(&"b"));
+    /// # }
+    /// ```
+    pub fn entry(
+        &mut self,
+        key: K,
+    ) -> Entry<'_, K, V> {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.table.entry(
+            hash,
+            |(k, _)| k == &key,
+            |kv| self.hash_builder.hash_one(&kv.0),
+        ) {
+            TableEntry::Occupied(entry) => Entry::Occupied(OccupiedEntry { entry }),
+            TableEntry::Vacant(entry) => Entry::Vacant(VacantEntry { entry, key }),
+        }
+    }
+
+    /// Tries to get the given key's corresponding entry in the map for in-place
+    /// manipulation, without resizing the table.
+    ///
+    /// If the table is full or no free slot can be found, returns an error with
+    /// the key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::with_capacity(10);
+    ///
+    /// match map.try_entry(1) {
+    ///     Ok(entry) => {
+    ///         entry.or_insert("a");
+    ///     }
+    ///     Err((key, _)) => println!("Failed to get entry for key: {}", key),
+    /// }
+    /// # }
+    /// ```
+    pub fn try_entry(
+        &mut self,
+        key: K,
+    ) -> Result<Entry<'_, K, V>, (K, TryEntryError)> {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.table.try_entry(hash, |(k, _)| k == &key) {
+            Ok(TableEntry::Occupied(entry)) => Ok(Entry::Occupied(OccupiedEntry { entry })),
+            Ok(TableEntry::Vacant(entry)) => Ok(Entry::Vacant(VacantEntry { entry, key })),
+            Err(e) => Err((key, e)),
+        }
+    }
+
+    /// Tries to insert a key-value pair into the map without resizing.
+    ///
+    /// If the map did not have this key present, `Ok(None)` is returned.
+    /// If the map did have this key present, the value is updated, and the old
+    /// value is returned as `Ok(Some(old_value))`.
+    /// If the table is full or no free slot can be found, returns an error with
+    /// the key and value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::with_capacity(10);
+    /// match map.try_insert(37, "a") {
+    ///     Ok(None) => println!("Inserted successfully"),
+    ///     Ok(Some(old)) => println!("Updated, old value: {}", old),
+    ///     Err((k, v, _)) => println!("Failed to insert: {} -> {}", k, v),
+    /// }
+    /// # }
+    /// ```
+    pub fn try_insert(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Result<Option<V>, (K, V, TryEntryError)> {
+        match self.try_entry(key) {
+            Ok(Entry::Occupied(mut entry)) => {
+                let old_value = entry.insert(value);
+                Ok(Some(old_value))
+            }
+            Ok(Entry::Vacant(entry)) => {
+                entry.insert(value);
+                Ok(None)
+            }
+            Err((key, e)) => Err((key, value, e)),
+        }
+    }
+
+    /// Returns an iterator over the key-value pairs of the map.
+    ///
+    /// The iterator yields `(&K, &V)` pairs in an arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    ///
+    /// for (key, value) in map.iter() {
+    ///     println!("Key: {}, Value: {}", key, value);
+    /// }
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.table.iter(),
+        }
+    }
+
+    /// Returns an iterator over the key-value pairs of the map with mutable
+    /// references to the values.
+    ///
+    /// The iterator yields `(&K, &mut V)` pairs in an arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, String> = HashMap::new();
+    /// map.insert(1, "a".to_string());
+    /// map.insert(2, "b".to_string());
+    ///
+    /// for (_key, value) in map.iter_mut() {
+    ///     value.make_ascii_uppercase();
+    /// }
+    ///
+    /// assert_eq!(map.get(&1), Some(&"A".to_string()));
+    /// assert_eq!(map.get(&2), Some(&"B".to_string()));
+    /// # }
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.table.iter_mut(),
+        }
+    }
+
+    /// Returns a parallel iterator over mutable references to the values of
+    /// the map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut map: HashMap<i32, i32> = (0..100).map(|i| (i, i)).collect();
+    /// map.par_values_mut().for_each(|v| *v *= 2);
+    /// assert_eq!(map.get(&21), Some(&42));
+    /// # }
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_values_mut(&mut self) -> impl rayon::iter::ParallelIterator<Item = &mut V> + '_
+    where
+        K: Send,
+        V: Send,
+    {
+        use rayon::iter::ParallelIterator;
+
+        self.table.par_iter_mut().map(|(_, v)| v)
+    }
+
+    /// Returns an iterator over the keys of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    ///
+    /// let keys: Vec<_> = map.keys().collect();
+    /// assert_eq!(keys.len(), 2);
+    /// # }
+    /// ```
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    /// Returns an iterator over the values of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    ///
+    /// let values: Vec<_> = map.values().collect();
+    /// assert_eq!(values.len(), 2);
+    /// # }
+    /// ```
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// Returns an iterator over mutable references to the values of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, String> = HashMap::new();
+    /// map.insert(1, "a".to_string());
+    /// map.insert(2, "b".to_string());
+    ///
+    /// for value in map.values_mut() {
+    ///     value.make_ascii_uppercase();
+    /// }
+    ///
+    /// assert_eq!(map.get(&1), Some(&"A".to_string()));
+    /// assert_eq!(map.get(&2), Some(&"B".to_string()));
+    /// # }
+    /// ```
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// Calls `f` on every key and a mutable reference to its value.
+    ///
+    /// Values are updated where they are stored: this never resizes,
+    /// reallocates, or moves entries, so the capacity and iteration order are
+    /// unchanged afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, i32> = HashMap::new();
+    /// map.insert(1, 10);
+    /// map.insert(2, 20);
+    ///
+    /// map.map_values_in_place(|k, v| *v += k);
+    /// assert_eq!(map.get(&1), Some(&11));
+    /// assert_eq!(map.get(&2), Some(&22));
+    /// # }
+    /// ```
+    pub fn map_values_in_place(
+        &mut self,
+        mut f: impl FnMut(&K, &mut V),
+    ) {
+        self.table.for_each_mut(|(k, v)| f(k, v));
+    }
+
+    /// Returns an iterator that removes and yields all key-value pairs from the
+    /// map.
+    ///
+    /// After calling `drain()`, the map will be empty.
+    ///
+    /// Calling `mem::forget` on the returned iterator will leak all key-value
+    /// pairs in the map that have not yet been yielded. This can cause memory
+    /// leaks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    ///
+    /// let pairs: Vec<_> = map.drain().collect();
+    /// assert!(map.is_empty());
+    /// assert_eq!(pairs.len(), 2);
+    /// # }
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        Drain {
+            inner: self.table.drain(),
+        }
+    }
+
+    /// Consumes the map, returning an iterator over its keys.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    ///
+    /// let mut keys: Vec<_> = map.into_keys().collect();
+    /// keys.sort();
+    /// assert_eq!(keys, [1, 2]);
+    /// # }
+    /// ```
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys {
+            inner: self.into_iter(),
+        }
+    }
+
+    /// Consumes the map, returning an iterator over its values.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<i32, &str> = HashMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    ///
+    /// let mut values: Vec<_> = map.into_values().collect();
+    /// values.sort();
+    /// assert_eq!(values, ["a", "b"]);
+    /// # }
+    /// ```
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues {
+            inner: self.into_iter(),
+        }
+    }
+
+    /// Returns references to every key-value pair, sorted by key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let map: HashMap<i32, &str> = [(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+    /// assert_eq!(map.to_sorted_vec(), [(&1, &"a"), (&2, &"b"), (&3, &"c")]);
+    /// # }
+    /// ```
+    pub fn to_sorted_vec(&self) -> Vec<(&K, &V)>
+    where
+        K: Ord,
+    {
+        let mut pairs: Vec<_> = self.iter().collect();
+        pairs.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        pairs
+    }
+
+    /// Consumes the map, moving every pair into a [`BTreeMap`] ordered by
+    /// key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let map: HashMap<i32, &str> = [(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+    /// let sorted = map.into_btree_map();
+    /// assert!(sorted.keys().copied().eq([1, 2, 3]));
+    /// # }
+    /// ```
+    pub fn into_btree_map(self) -> BTreeMap<K, V>
+    where
+        K: Ord,
+    {
+        self.into_iter().collect()
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Creates a new hash map using the default hasher builder.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let map: HashMap<i32, String> = HashMap::new();
+    /// assert!(map.is_empty());
+    /// # }
+    /// ```
+    pub fn new() -> Self {
+        Self::with_hasher(S::default())
+    }
+
+    /// Creates a new hash map with the specified capacity using the default
+    /// hasher builder.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let map: HashMap<i32, String> = HashMap::with_capacity(100);
+    /// assert!(map.capacity() >= 100);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::default())
+    }
+
+    /// Creates a new hash map with the specified capacity using the default
+    /// hasher builder, using as little memory as possible for small
+    /// capacities.
+    ///
+    /// Capacities that fit in a single bucket (14 entries at the default
+    /// density) allocate one bucket with no padding; larger ones behave like
+    /// [`with_capacity`](Self::with_capacity). See
+    /// [`HashTable::with_capacity_compact`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let map: HashMap<u64, u64> = HashMap::with_capacity_compact(8);
+    /// assert!(map.capacity_info().allocation_bytes < 1024);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn with_capacity_compact(capacity: usize) -> Self {
+        Self::with_capacity_compact_and_hasher(capacity, S::default())
+    }
+
+    /// Creates a map from an iterator of pairs, keeping the first value seen
+    /// for each key.
+    ///
+    /// This is the counterpart to [`FromIterator`], where a later pair with
+    /// the same key replaces the earlier one. Here later duplicates are
+    /// dropped instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let pairs = [("a", 1), ("b", 2), ("a", 3)];
+    ///
+    /// let first: HashMap<&str, i32> = HashMap::from_iter_first_wins(pairs);
+    /// assert_eq!(first.get(&"a"), Some(&1));
+    ///
+    /// let last: HashMap<&str, i32> = pairs.into_iter().collect();
+    /// assert_eq!(last.get(&"a"), Some(&3));
+    /// # }
+    /// ```
+    pub fn from_iter_first_wins<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let iter = iter.into_iter();
+        let mut map = Self::with_capacity(extend_reserve_hint(iter.size_hint()));
+        for (key, value) in iter {
+            map.entry(key).or_insert(value);
+        }
+        map
+    }
+}
+
+#[cfg(any(feature = "std", feature = "foldhash"))]
+impl<K, V> HashMap<K, V, crate::FixedState>
+where
+    K: Hash + Eq,
+{
+    /// Creates a new hash map with a deterministic hasher.
+    ///
+    /// Maps created this way iterate in the same order whenever they receive
+    /// the same sequence of operations, which makes them suitable for
+    /// snapshot tests. See [`FixedState`](crate::FixedState) for why they
+    /// should not hold untrusted keys.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::hash_map::HashMap;
+    ///
+    /// let mut a = HashMap::fixed();
+    /// let mut b = HashMap::fixed();
+    /// for i in 0..100 {
+    ///     a.insert(i, i);
+    ///     b.insert(i, i);
+    /// }
+    /// assert!(a.iter().eq(b.iter()));
+    /// # }
+    /// ```
+    pub fn fixed() -> Self {
+        Self::with_hasher(crate::FixedState::default())
+    }
+}
+
+impl<K, V, S> Default for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, S> HashMap<K, Vec<V>, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Appends `item` to the `Vec` stored under `key`, creating an empty
+    /// `Vec` first if the key is absent.
+    ///
+    /// This resolves the key with a single lookup, which makes building a
+    /// multimap as cheap as a plain insert.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut map: HashMap<&str, Vec<i32>> = HashMap::new();
+    /// map.push("odd", 1);
+    /// map.push("even", 2);
+    /// map.push("odd", 3);
+    ///
+    /// assert_eq!(map.get(&"odd"), Some(&vec![1, 3]));
+    /// assert_eq!(map.get(&"even"), Some(&vec![2]));
+    /// # }
+    /// ```
+    pub fn push(
+        &mut self,
+        key: K,
+        item: V,
+    ) {
+        self.entry(key).or_default().push(item);
+    }
+}
+
+impl<K, V, S, T> HashMap<K, crate::hash_set::HashSet<V, T>, S>
+where
+    K: Hash + Eq,
+    V: Hash + Eq,
+    S: BuildHasher,
+    T: BuildHasher + Default,
+{
+    /// Inserts `item` into the set stored under `key`, creating an empty set
+    /// first if the key is absent.
+    ///
+    /// Returns whether `item` was newly added to that set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut map: HashMap<&str, HashSet<i32>> = HashMap::new();
+    /// assert!(map.insert_to_set("a", 1));
+    /// assert!(!map.insert_to_set("a", 1));
+    /// assert!(map.insert_to_set("a", 2));
+    /// assert_eq!(map.get(&"a").unwrap().len(), 2);
+    /// # }
+    /// ```
+    pub fn insert_to_set(
+        &mut self,
+        key: K,
+        item: V,
+    ) -> bool {
+        self.entry(key).or_default().insert(item)
+    }
+}
+
+/// A view into a single entry in the map, which may either be vacant or
+/// occupied.
+///
+/// This enum is constructed from the [`entry`] method on [`HashMap`].
+///
+/// [`entry`]: HashMap::entry
+pub enum Entry<'a, K, V> {
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, K, V>),
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V>),
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    /// Inserts a default value if the entry is vacant and returns a mutable
+    /// reference.
+    pub fn or_insert(
+        self,
+        default: V,
+    ) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Inserts a default value if the entry is vacant, and returns a mutable
+    /// reference along with whether the value was just inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut counts: HashMap<&str, u32> = HashMap::new();
+    /// let mut first_seen = Vec::new();
+    /// for word in ["a", "b", "a"] {
+    ///     let (count, inserted) = counts.entry(word).or_insert_reporting(0);
+    ///     *count += 1;
+    ///     if inserted {
+    ///         first_seen.push(word);
+    ///     }
+    /// }
+    /// assert_eq!(first_seen, ["a", "b"]);
+    /// assert_eq!(counts.get(&"a"), Some(&2));
+    /// # }
+    /// ```
+    pub fn or_insert_reporting(
+        self,
+        default: V,
+    ) -> (&'a mut V, bool) {
+        match self {
+            Entry::Occupied(entry) => (entry.into_mut(), false),
+            Entry::Vacant(entry) => (entry.insert(default), true),
+        }
+    }
+
+    /// Inserts a default value if the entry is vacant, and returns a mutable
+    /// reference along with a handle for removing the pair later with
+    /// [`HashMap::remove_by_handle`].
+    ///
+    /// The handle is valid until the map is next modified.
+    pub fn or_insert_handle(
+        self,
+        default: V,
+    ) -> (&'a mut V, EntryHandle) {
+        match self {
+            Entry::Occupied(entry) => {
+                let handle = entry.handle();
+                (entry.into_mut(), handle)
+            }
+            Entry::Vacant(entry) => {
+                #[cfg(feature = "metrics")]
+                crate::metrics::record(entry.entry.metrics_sink(), MetricsEvent::Insert);
+                let (pair, handle) = entry.entry.insert_with_handle((entry.key, default));
+                (&mut pair.1, handle)
+            }
+        }
+    }
+
+    /// Inserts a value computed from a closure if the entry is vacant and
+    /// returns a mutable reference.
+    pub fn or_insert_with<F>(
+        self,
+        default: F,
+    ) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts.
+    pub fn and_modify<F>(
+        self,
+        f: F,
+    ) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+
+    /// Applies `modify` to the value if the entry is occupied, or inserts
+    /// `default` if it is vacant, and returns a mutable reference to the
+    /// value.
+    ///
+    /// This is the same as `entry.and_modify(modify).or_insert(default)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashMap;
+    ///
+    /// let mut counts: HashMap<&str, u32> = HashMap::new();
+    /// for word in ["a", "b", "a"] {
+    ///     counts.entry(word).and_modify_or_insert(|c| *c += 1, 1);
+    /// }
+    /// assert_eq!(counts.get(&"a"), Some(&2));
+    /// assert_eq!(counts.get(&"b"), Some(&1));
+    /// # }
+    /// ```
+    pub fn and_modify_or_insert<F>(
+        self,
+        modify: F,
+        default: V,
+    ) -> &'a mut V
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Entry::Occupied(entry) => {
+                let value = entry.into_mut();
+                modify(value);
+                value
+            }
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Applies `modify` to the value if the entry is occupied, or inserts the
+    /// result of `default` if it is vacant, and returns a mutable reference
+    /// to the value.
+    ///
+    /// `default` is only called if the entry is vacant.
+    pub fn and_modify_or_insert_with<F, D>(
+        self,
+        modify: F,
+        default: D,
+    ) -> &'a mut V
+    where
+        F: FnOnce(&mut V),
+        D: FnOnce() -> V,
+    {
+        match self {
+            Entry::Occupied(entry) => {
+                let value = entry.into_mut();
+                modify(value);
+                value
+            }
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    V: Default,
+{
+    /// Inserts the default value if the entry is vacant and returns a mutable
+    /// reference.
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(Default::default)
+    }
+}
+
+/// A view into a vacant entry in the map.
+pub struct VacantEntry<'a, K, V> {
+    entry: crate::hash_table::VacantEntry<'a, (K, V)>,
+    key: K,
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    /// Gets a reference to the key that would be used when inserting a value.
     pub fn key(&self) -> &K {
         &self.key
     }
 
-    /// Take ownership of the key.
-    pub fn into_key(self) -> K {
-        self.key
-    }
+    /// Take ownership of the key.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the value into the map and returns a mutable reference to it.
+    pub fn insert(
+        self,
+        value: V,
+    ) -> &'a mut V {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.entry.metrics_sink(), MetricsEvent::Insert);
+        &mut self.entry.insert((self.key, value)).1
+    }
+}
+
+/// A view into an occupied entry in the map.
+pub struct OccupiedEntry<'a, K, V> {
+    entry: crate::hash_table::OccupiedEntry<'a, (K, V)>,
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    /// Gets a reference to the key in the entry.
+    pub fn key(&self) -> &K {
+        &self.entry.get().0
+    }
+
+    /// Gets a reference to the value in the entry.
+    pub fn get(&self) -> &V {
+        &self.entry.get().1
+    }
+
+    /// Gets a mutable reference to the value in the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.entry.get_mut().1
+    }
+
+    /// Converts the entry into a mutable reference to the value.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.entry.into_mut().1
+    }
+
+    /// Returns a handle for removing this pair later with
+    /// [`HashMap::remove_by_handle`].
+    ///
+    /// The handle is valid until the map is next modified.
+    pub fn handle(&self) -> EntryHandle {
+        self.entry.handle()
+    }
+
+    /// Inserts a value into the entry and returns the old value.
+    pub fn insert(
+        &mut self,
+        value: V,
+    ) -> V {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.entry.metrics_sink(), MetricsEvent::Insert);
+        core::mem::replace(&mut self.entry.get_mut().1, value)
+    }
+
+    /// Removes the entry from the map and returns the value.
+    pub fn remove(self) -> V {
+        self.entry.remove().1
+    }
+
+    /// Removes the entry from the map and returns the key and value.
+    pub fn remove_entry(self) -> (K, V) {
+        self.entry.remove()
+    }
+
+    /// Removes the entry from the map and returns the stored key, dropping
+    /// the value.
+    ///
+    /// Use [`remove_entry`](Self::remove_entry) to keep the value as well.
+    pub fn into_key(self) -> K {
+        self.entry.remove().0
+    }
+
+    /// Replaces the stored key with `key`, keeping the value, and returns the
+    /// old key.
+    ///
+    /// This is useful when keys carry data that does not take part in
+    /// equality. `key` must be equal to the stored key, or lookups will no
+    /// longer find the entry; this is checked in debug builds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use std::rc::Rc;
+    ///
+    /// use hop_hash::HashMap;
+    /// use hop_hash::hash_map::Entry;
+    ///
+    /// let first: Rc<str> = Rc::from("key");
+    /// let mut map: HashMap<Rc<str>, i32> = HashMap::new();
+    /// map.insert(first.clone(), 1);
+    ///
+    /// let second: Rc<str> = Rc::from("key");
+    /// if let Entry::Occupied(mut entry) = map.entry(second.clone()) {
+    ///     let old = entry.replace_key(second.clone());
+    ///     assert!(Rc::ptr_eq(&old, &first));
+    /// }
+    /// assert!(Rc::ptr_eq(map.get_key_value(&second).unwrap().0, &second));
+    /// # }
+    /// ```
+    pub fn replace_key(
+        &mut self,
+        key: K,
+    ) -> K
+    where
+        K: Eq,
+    {
+        let stored = &mut self.entry.get_mut().0;
+        debug_assert!(
+            *stored == key,
+            "replace_key: new key is not equal to the stored key"
+        );
+        core::mem::replace(stored, key)
+    }
+}
+
+/// An iterator over the key-value pairs of a `HashMap`.
+pub struct Iter<'a, K, V> {
+    inner: crate::hash_table::Iter<'a, (K, V)>,
+}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// A mutable iterator over the key-value pairs of a `HashMap`.
+pub struct IterMut<'a, K, V> {
+    inner: crate::hash_table::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// An iterator over the keys of a `HashMap`.
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<K, V> Clone for Keys<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// An iterator over the values of a `HashMap`.
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<K, V> Clone for Values<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// A mutable iterator over the values of a `HashMap`.
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// A draining iterator over the key-value pairs of a `HashMap`.
+pub struct Drain<'a, K, V> {
+    inner: crate::hash_table::Drain<'a, (K, V)>,
+}
+
+/// A consuming iterator over the key-value pairs of a `HashMap`.
+pub struct IntoIter<K, V> {
+    inner: crate::hash_table::IntoIter<(K, V)>,
+}
+
+impl<K, V> Iterator for Drain<'_, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<K, V> FusedIterator for Drain<'_, K, V> {}
+
+impl<K, V> Drop for Drain<'_, K, V> {
+    fn drop(&mut self) {
+        for _ in self {}
+    }
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// A consuming iterator over the keys of a `HashMap`.
+pub struct IntoKeys<K, V> {
+    inner: IntoIter<K, V>,
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+
+impl<K, V> DoubleEndedIterator for IntoKeys<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// A consuming iterator over the values of a `HashMap`.
+pub struct IntoValues<K, V> {
+    inner: IntoIter<K, V>,
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> FusedIterator for IntoValues<K, V> {}
+
+impl<K, V> DoubleEndedIterator for IntoValues<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K, V, S> IntoIterator for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type IntoIter = IntoIter<K, V>;
+    type Item = (K, V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.table.into_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type IntoIter = Iter<'a, K, V>;
+    type Item = (&'a K, &'a V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type IntoIter = IterMut<'a, K, V>;
+    type Item = (&'a K, &'a mut V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = HashMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn extend<T: IntoIterator<Item = (K, V)>>(
+        &mut self,
+        iter: T,
+    ) {
+        let iter = iter.into_iter();
+        self.reserve(extend_reserve_hint(iter.size_hint()));
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+/// An iterator that removes and yields all values from the set that satisfy
+/// a given predicate.
+pub struct ExtractIf<'a, K, V> {
+    #[allow(clippy::type_complexity)]
+    inner: crate::hash_table::ExtractIf<
+        'a,
+        (K, V),
+        Box<dyn FnMut(&mut (K, V)) -> bool + 'a>,
+        Box<dyn Fn(&(K, V)) -> u64 + 'a>,
+    >,
+    auto_shrink: Option<f64>,
+}
+
+impl<K, V> Drop for ExtractIf<'_, K, V> {
+    fn drop(&mut self) {
+        if let Some(min_load) = self.auto_shrink {
+            self.inner.shrink_if_below(min_load);
+        }
+    }
+}
+
+impl<K, V> Iterator for ExtractIf<'_, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<K, V> FusedIterator for ExtractIf<'_, K, V> {}
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::hash::BuildHasher;
+
+    use rand::TryRngCore;
+    use rand::rngs::OsRng;
+    use siphasher::sip::SipHasher;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct SipHashBuilder {
+        k1: u64,
+        k2: u64,
+    }
+
+    impl BuildHasher for SipHashBuilder {
+        type Hasher = SipHasher;
+
+        fn build_hasher(&self) -> Self::Hasher {
+            SipHasher::new_with_keys(self.k1, self.k2)
+        }
+    }
+
+    impl Default for SipHashBuilder {
+        fn default() -> Self {
+            let mut rng = OsRng;
+            Self {
+                k1: rng.try_next_u64().unwrap_or(0),
+                k2: rng.try_next_u64().unwrap_or(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_new_and_with_hasher() {
+        let map: HashMap<i32, String, SipHashBuilder> = HashMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.len(), 0);
+
+        let map2 = HashMap::<i32, String, _>::with_hasher(SipHashBuilder::default());
+        assert!(map2.is_empty());
+        assert_eq!(map2.len(), 0);
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let map: HashMap<i32, String, SipHashBuilder> = HashMap::with_capacity(100);
+        assert!(map.capacity() >= 100);
+        assert!(map.is_empty());
+
+        let map2 =
+            HashMap::<i32, String, _>::with_capacity_and_hasher(200, SipHashBuilder::default());
+        assert!(map2.capacity() >= 200);
+        assert!(map2.is_empty());
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+
+        assert_eq!(map.insert(1, "hello".to_string()), None);
+        assert_eq!(map.len(), 1);
+        assert!(!map.is_empty());
+
+        assert_eq!(map.get(&1), Some(&"hello".to_string()));
+        assert_eq!(map.get(&2), None);
+
+        assert_eq!(
+            map.insert(1, "world".to_string()),
+            Some("hello".to_string())
+        );
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&1), Some(&"world".to_string()));
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.insert(1, "hello".to_string());
+
+        if let Some(value) = map.get_mut(&1) {
+            value.push_str(" world");
+        }
+
+        assert_eq!(map.get(&1), Some(&"hello world".to_string()));
+        assert_eq!(map.get_mut(&2), None);
+    }
+
+    #[test]
+    fn test_insert_hashes_key_once_across_resize() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct CountedKey {
+            id: u32,
+            hashes: Rc<Cell<usize>>,
+        }
+
+        impl Hash for CountedKey {
+            fn hash<H: core::hash::Hasher>(
+                &self,
+                state: &mut H,
+            ) {
+                self.hashes.set(self.hashes.get() + 1);
+                self.id.hash(state);
+            }
+        }
+
+        impl PartialEq for CountedKey {
+            fn eq(
+                &self,
+                other: &Self,
+            ) -> bool {
+                self.id == other.id
+            }
+        }
+
+        impl Eq for CountedKey {}
+
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        let mut resizes = 0;
+        for id in 0..2000u32 {
+            let hashes = Rc::new(Cell::new(0));
+            let capacity = map.capacity();
+            map.insert(
+                CountedKey {
+                    id,
+                    hashes: hashes.clone(),
+                },
+                id,
+            );
+            if map.capacity() != capacity {
+                resizes += 1;
+            }
+            // Any resize happens before the new key is stored, so only existing
+            // keys are rehashed.
+            assert_eq!(hashes.get(), 1, "key {} hashed more than once", id);
+        }
+        assert!(resizes > 1);
+
+        let hashes = Rc::new(Cell::new(0));
+        map.entry(CountedKey {
+            id: 5000,
+            hashes: hashes.clone(),
+        })
+        .and_modify(|v| *v += 1)
+        .or_insert(0);
+        assert_eq!(hashes.get(), 1);
+
+        // The same holds for occupied and vacant entries through the other
+        // insertion helpers, as long as nothing resizes.
+        map.reserve(10);
+        let capacity = map.capacity();
+        for id in [0, 1999, 5000, 5001, 5002] {
+            let hashes = Rc::new(Cell::new(0));
+            let key = CountedKey {
+                id,
+                hashes: hashes.clone(),
+            };
+            if id % 2 == 0 {
+                *map.entry(key).or_insert_with(|| 7) += 1;
+            } else {
+                *map.entry(key).or_default() += 1;
+            }
+            assert_eq!(hashes.get(), 1, "key {} hashed more than once", id);
+        }
+        assert_eq!(map.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_extend_reserves_from_size_hint() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.extend((0..10_000u32).map(|i| (i, i)));
+        let preallocated: HashMap<u32, u32, SipHashBuilder> = HashMap::with_capacity(10_000);
+        assert_eq!(map.len(), 10_000);
+        assert_eq!(map.capacity(), preallocated.capacity());
+
+        let collected: HashMap<u32, u32, SipHashBuilder> = (0..10_000u32).map(|i| (i, i)).collect();
+        assert_eq!(collected.capacity(), preallocated.capacity());
+
+        // A sparse filter only reserves for its lower bound.
+        let mut filtered = HashMap::with_hasher(SipHashBuilder::default());
+        filtered.extend((0..10_000u32).filter(|i| i % 1000 == 0).map(|i| (i, i)));
+        assert_eq!(filtered.len(), 10);
+        assert!(filtered.capacity() < preallocated.capacity());
+    }
+
+    #[test]
+    fn test_into_iter_double_ended() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..100u32 {
+            map.insert(i, i * 10);
+        }
+
+        let mut iter = map.clone().into_iter();
+        assert_eq!(iter.len(), 100);
+        let mut seen = Vec::new();
+        while let Some(front) = iter.next() {
+            seen.push(front);
+            if let Some(back) = iter.next_back() {
+                seen.push(back);
+            }
+        }
+        assert_eq!(iter.len(), 0);
+        seen.sort();
+        assert_eq!(seen, (0..100).map(|i| (i, i * 10)).collect::<Vec<_>>());
+
+        let forward: Vec<_> = map.clone().into_iter().collect();
+        let mut backward: Vec<_> = map.clone().into_iter().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        let keys = map.clone().into_keys();
+        assert_eq!(keys.len(), 100);
+        let mut keys: Vec<_> = keys.rev().collect();
+        keys.sort();
+        assert_eq!(keys, (0..100).collect::<Vec<_>>());
+
+        let mut values: Vec<_> = map.into_values().rev().collect();
+        values.sort();
+        assert_eq!(values, (0..100).map(|i| i * 10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_slot_capacity_exceeds_capacity() {
+        let empty: HashMap<u64, u64, SipHashBuilder> = HashMap::new();
+        assert_eq!(empty.slot_capacity(), 0);
+        assert_eq!(empty.capacity(), 0);
+
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..1000u64 {
+            map.insert(i, i);
+            assert!(map.slot_capacity() > map.capacity());
+        }
+    }
+
+    #[test]
+    fn test_merge_with_sums_counts() {
+        let builder = SipHashBuilder::default();
+        let mut left = HashMap::with_hasher(builder.clone());
+        for word in "the quick brown fox jumps over the lazy dog".split(' ') {
+            *left.entry(word).or_insert(0) += 1;
+        }
+        let mut right = HashMap::with_hasher(builder);
+        for word in "the dog barks at the fox".split(' ') {
+            *right.entry(word).or_insert(0) += 1;
+        }
 
-    /// Inserts the value into the map and returns a mutable reference to it.
-    pub fn insert(
-        self,
-        value: V,
-    ) -> &'a mut V {
-        &mut self.entry.insert((self.key, value)).1
+        left.merge_with(right, |_, existing, incoming| *existing += incoming);
+
+        assert_eq!(left.len(), 10);
+        assert_eq!(left.get(&"the"), Some(&4));
+        assert_eq!(left.get(&"fox"), Some(&2));
+        assert_eq!(left.get(&"dog"), Some(&2));
+        assert_eq!(left.get(&"quick"), Some(&1));
+        assert_eq!(left.get(&"barks"), Some(&1));
+        assert_eq!(left.get(&"at"), Some(&1));
     }
-}
 
-/// A view into an occupied entry in the map.
-pub struct OccupiedEntry<'a, K, V> {
-    entry: crate::hash_table::OccupiedEntry<'a, (K, V)>,
-}
+    #[test]
+    fn test_extend_from_slice() {
+        let pairs: Vec<(u32, u32)> = (0..1000).map(|i| (i % 700, i)).collect();
 
-impl<'a, K, V> OccupiedEntry<'a, K, V> {
-    /// Gets a reference to the key in the entry.
-    pub fn key(&self) -> &K {
-        &self.entry.get().0
-    }
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.extend_from_slice(&pairs);
 
-    /// Gets a reference to the value in the entry.
-    pub fn get(&self) -> &V {
-        &self.entry.get().1
-    }
+        let mut expected = HashMap::with_hasher(SipHashBuilder::default());
+        expected.extend(pairs.iter().copied());
 
-    /// Gets a mutable reference to the value in the entry.
-    pub fn get_mut(&mut self) -> &mut V {
-        &mut self.entry.get_mut().1
+        assert_eq!(map.len(), 700);
+        assert_eq!(map, expected);
+        assert_eq!(map.get(&0), Some(&700));
+        assert_eq!(map.get(&699), Some(&699));
     }
 
-    /// Converts the entry into a mutable reference to the value.
-    pub fn into_mut(self) -> &'a mut V {
-        &mut self.entry.into_mut().1
-    }
+    #[test]
+    fn test_auto_shrink_after_removals() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        let grown = map.capacity();
 
-    /// Inserts a value into the entry and returns the old value.
-    pub fn insert(
-        &mut self,
-        value: V,
-    ) -> V {
-        core::mem::replace(&mut self.entry.get_mut().1, value)
-    }
+        for i in 0..9_900 {
+            map.remove(&i);
+        }
+        assert_eq!(map.capacity(), grown);
+
+        map.set_auto_shrink(Some(0.25));
+        assert_eq!(map.auto_shrink(), Some(0.25));
+        map.remove(&9_900);
+        assert!(map.capacity() < grown);
+        assert_eq!(map.len(), 99);
+        for i in 9_901..10_000 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
 
-    /// Removes the entry from the map and returns the value.
-    pub fn remove(self) -> V {
-        self.entry.remove().1
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        let grown = map.capacity();
+        map.extract_if(|&k, _| k >= 50).for_each(drop);
+        assert!(map.capacity() < grown);
+        assert_eq!(map.len(), 50);
+
+        map.set_auto_shrink(None);
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        let grown = map.capacity();
+        map.retain(|&k, _| k < 10);
+        assert_eq!(map.capacity(), grown);
     }
 
-    /// Removes the entry from the map and returns the key and value.
-    pub fn remove_entry(self) -> (K, V) {
-        self.entry.remove()
+    #[test]
+    fn test_clone_preserves_order_and_is_independent() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..500 {
+            map.insert(i, i.to_string());
+        }
+
+        let mut cloned = map.clone();
+        assert!(map.iter().eq(cloned.iter()));
+        let try_cloned = map.try_clone().unwrap();
+        assert!(map.iter().eq(try_cloned.iter()));
+
+        cloned.insert(0, "changed".to_string());
+        cloned.remove(&1);
+        assert_eq!(map.get(&0), Some(&"0".to_string()));
+        assert_eq!(map.get(&1), Some(&"1".to_string()));
+        assert_eq!(map.len(), 500);
+        assert_eq!(cloned.len(), 499);
     }
-}
 
-/// An iterator over the key-value pairs of a `HashMap`.
-pub struct Iter<'a, K, V> {
-    inner: crate::hash_table::Iter<'a, (K, V)>,
-}
+    #[test]
+    fn test_from_iter_first_wins_keeps_first_duplicate() {
+        let pairs = [(1, "one"), (2, "two"), (1, "uno"), (3, "three"), (2, "dos")];
+
+        let first: HashMap<i32, &str, SipHashBuilder> = HashMap::from_iter_first_wins(pairs);
+        assert_eq!(first.len(), 3);
+        assert_eq!(first.get(&1), Some(&"one"));
+        assert_eq!(first.get(&2), Some(&"two"));
+        assert_eq!(first.get(&3), Some(&"three"));
+
+        let last: HashMap<i32, &str, SipHashBuilder> = pairs.into_iter().collect();
+        assert_eq!(last.len(), 3);
+        assert_eq!(last.get(&1), Some(&"uno"));
+        assert_eq!(last.get(&2), Some(&"dos"));
+    }
 
-impl<'a, K, V> Iterator for Iter<'a, K, V> {
-    type Item = (&'a K, &'a V);
+    #[test]
+    fn test_remove_many_skips_absent_keys() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..100 {
+            map.insert(i, i * 10);
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|(k, v)| (k, v))
+        let keys = [5, 200, 17, 5, 99, -1];
+        let mut removed = map.remove_many(&keys);
+        removed.sort_unstable();
+        assert_eq!(removed, vec![(5, 50), (17, 170), (99, 990)]);
+        assert_eq!(map.len(), 97);
+        assert!(!map.contains_key(&5));
+        assert!(!map.contains_key(&17));
+        assert!(!map.contains_key(&99));
+        assert_eq!(map.get(&6), Some(&60));
+
+        assert!(map.remove_many(&[]).is_empty());
     }
-}
 
-/// A mutable iterator over the key-value pairs of a `HashMap`.
-pub struct IterMut<'a, K, V> {
-    inner: crate::hash_table::IterMut<'a, (K, V)>,
-}
+    #[test]
+    fn test_insert_unique_unchecked() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..1_000 {
+            let (k, v) = map.insert_unique_unchecked(i, i * 2);
+            assert_eq!((*k, *v), (i, i * 2));
+        }
 
-impl<'a, K, V> Iterator for IterMut<'a, K, V> {
-    type Item = (&'a K, &'a mut V);
+        assert_eq!(map.len(), 1_000);
+        for i in 0..1_000 {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|(k, v)| (&*k, v))
+    #[test]
+    #[cfg(all(feature = "debug-checks", debug_assertions))]
+    #[should_panic(expected = "an equal value is already present")]
+    fn test_insert_unique_unchecked_panics_on_duplicate() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.insert_unique_unchecked(1, "a");
+        map.insert_unique_unchecked(1, "b");
     }
-}
 
-/// An iterator over the keys of a `HashMap`.
-pub struct Keys<'a, K, V> {
-    inner: Iter<'a, K, V>,
-}
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_par_values_mut_matches_serial() {
+        use rayon::iter::ParallelIterator;
 
-impl<'a, K, V> Iterator for Keys<'a, K, V> {
-    type Item = &'a K;
+        let mut parallel = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..50_000u64 {
+            parallel.insert(i, i);
+        }
+        let mut serial = parallel.clone();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|(k, _)| k)
+        parallel.par_values_mut().for_each(|v| *v = *v * 3 + 1);
+        for v in serial.values_mut() {
+            *v = *v * 3 + 1;
+        }
+
+        assert_eq!(parallel, serial);
+        assert_eq!(parallel.get(&7), Some(&22));
     }
-}
 
-/// An iterator over the values of a `HashMap`.
-pub struct Values<'a, K, V> {
-    inner: Iter<'a, K, V>,
-}
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_reserve_overflow_panics() {
+        let mut map: HashMap<i32, i32, SipHashBuilder> =
+            HashMap::with_hasher(SipHashBuilder::default());
+        map.insert(1, 1);
+        map.reserve(usize::MAX);
+    }
 
-impl<'a, K, V> Iterator for Values<'a, K, V> {
-    type Item = &'a V;
+    #[test]
+    fn test_try_reserve_reports_overflow() {
+        let mut map: HashMap<i32, i32, SipHashBuilder> =
+            HashMap::with_hasher(SipHashBuilder::default());
+        map.insert(1, 1);
+        let capacity = map.capacity();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|(_, v)| v)
-    }
-}
+        assert_eq!(
+            map.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(
+            map.try_reserve(usize::MAX / 2),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(map.capacity(), capacity);
+        assert_eq!(map.get(&1), Some(&1));
 
-/// A mutable iterator over the values of a `HashMap`.
-pub struct ValuesMut<'a, K, V> {
-    inner: IterMut<'a, K, V>,
-}
+        assert_eq!(map.try_reserve(1_000), Ok(()));
+        assert!(map.capacity() >= 1_001);
+        assert_eq!(map.get(&1), Some(&1));
+    }
 
-impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
-    type Item = &'a mut V;
+    #[test]
+    fn test_occupied_entry_into_key() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.insert(String::from("a"), 1);
+        map.insert(String::from("b"), 2);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|(_, v)| v)
+        match map.entry(String::from("a")) {
+            Entry::Occupied(entry) => assert_eq!(entry.into_key(), "a"),
+            Entry::Vacant(_) => panic!("a is present"),
+        }
+        assert!(!map.contains_key(&String::from("a")));
+        assert_eq!(map.len(), 1);
     }
-}
 
-/// A draining iterator over the key-value pairs of a `HashMap`.
-pub struct Drain<'a, K, V> {
-    inner: crate::hash_table::Drain<'a, (K, V)>,
-}
+    #[test]
+    fn test_occupied_entry_replace_key() {
+        use alloc::rc::Rc;
 
-/// A consuming iterator over the key-value pairs of a `HashMap`.
-pub struct IntoIter<K, V> {
-    inner: crate::hash_table::IntoIter<(K, V)>,
-}
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        let original: Rc<str> = Rc::from("k");
+        map.insert(original.clone(), 5);
 
-impl<K, V> Iterator for Drain<'_, K, V> {
-    type Item = (K, V);
+        let replacement: Rc<str> = Rc::from("k");
+        match map.entry(replacement.clone()) {
+            Entry::Occupied(mut entry) => {
+                let old = entry.replace_key(replacement.clone());
+                assert!(Rc::ptr_eq(&old, &original));
+                assert_eq!(*entry.get(), 5);
+            }
+            Entry::Vacant(_) => panic!("k is present"),
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+        let (key, value) = map.get_key_value(&replacement).unwrap();
+        assert!(Rc::ptr_eq(key, &replacement));
+        assert_eq!(*value, 5);
     }
-}
 
-impl<K, V> Drop for Drain<'_, K, V> {
-    fn drop(&mut self) {
-        for _ in self {}
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not equal to the stored key")]
+    fn test_occupied_entry_replace_key_rejects_unequal_key() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.insert(1, "a");
+        if let Entry::Occupied(mut entry) = map.entry(1) {
+            entry.replace_key(2);
+        }
     }
-}
 
-impl<K, V> Iterator for IntoIter<K, V> {
-    type Item = (K, V);
+    #[test]
+    fn test_and_modify_or_insert_counts() {
+        let mut counts = HashMap::with_hasher(SipHashBuilder::default());
+        let words = ["x", "y", "x", "z", "x", "y"];
+        for word in words {
+            let count = counts.entry(word).and_modify_or_insert(|c| *c += 1, 1);
+            assert!(*count >= 1);
+        }
+        assert_eq!(counts.get(&"x"), Some(&3));
+        assert_eq!(counts.get(&"y"), Some(&2));
+        assert_eq!(counts.get(&"z"), Some(&1));
+
+        let mut defaults_built = 0;
+        for word in words {
+            counts.entry(word).and_modify_or_insert_with(
+                |c| *c *= 10,
+                || {
+                    defaults_built += 1;
+                    0
+                },
+            );
+        }
+        counts
+            .entry("w")
+            .and_modify_or_insert_with(|c| *c *= 10, || 7);
+        assert_eq!(defaults_built, 0);
+        assert_eq!(counts.get(&"x"), Some(&3000));
+        assert_eq!(counts.get(&"y"), Some(&200));
+        assert_eq!(counts.get(&"z"), Some(&10));
+        assert_eq!(counts.get(&"w"), Some(&7));
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+    #[test]
+    fn test_push_groups_into_multimap() {
+        let mut map: HashMap<&str, Vec<i32>, SipHashBuilder> =
+            HashMap::with_hasher(SipHashBuilder::default());
+        for (i, word) in ["a", "b", "a", "c", "a", "b"].into_iter().enumerate() {
+            map.push(word, i as i32);
+        }
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&"a"), Some(&vec![0, 2, 4]));
+        assert_eq!(map.get(&"b"), Some(&vec![1, 5]));
+        assert_eq!(map.get(&"c"), Some(&vec![3]));
     }
-}
 
-impl<K, V, S> IntoIterator for HashMap<K, V, S>
-where
-    K: Hash + Eq,
-    S: BuildHasher,
-{
-    type IntoIter = IntoIter<K, V>;
-    type Item = (K, V);
+    #[test]
+    fn test_insert_to_set_dedups_per_key() {
+        use crate::hash_set::HashSet;
 
-    fn into_iter(self) -> Self::IntoIter {
-        IntoIter {
-            inner: self.table.into_iter(),
+        let mut map: HashMap<u8, HashSet<u32, SipHashBuilder>, SipHashBuilder> =
+            HashMap::with_hasher(SipHashBuilder::default());
+        let mut new = 0;
+        for n in [1u32, 2, 11, 1, 21, 2, 12] {
+            if map.insert_to_set((n % 10) as u8, n) {
+                new += 1;
+            }
         }
+
+        assert_eq!(new, 5);
+        assert_eq!(map.len(), 2);
+        let ones = map.get(&1).unwrap();
+        assert_eq!(ones.len(), 3);
+        assert!(ones.contains(&1) && ones.contains(&11) && ones.contains(&21));
+        let twos = map.get(&2).unwrap();
+        assert_eq!(twos.len(), 2);
+        assert!(twos.contains(&2) && twos.contains(&12));
     }
-}
 
-impl<'a, K, V, S> IntoIterator for &'a HashMap<K, V, S>
-where
-    K: Hash + Eq,
-    S: BuildHasher,
-{
-    type IntoIter = Iter<'a, K, V>;
-    type Item = (&'a K, &'a V);
+    #[test]
+    fn test_replace_value_only_updates_present_keys() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.insert("a", 1);
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
+        assert_eq!(map.replace_value(&"a", 2), Some(1));
+        assert_eq!(map.get(&"a"), Some(&2));
+
+        assert_eq!(map.replace_value(&"b", 3), None);
+        assert!(!map.contains_key(&"b"));
+        assert_eq!(map.len(), 1);
     }
-}
 
-impl<'a, K, V, S> IntoIterator for &'a mut HashMap<K, V, S>
-where
-    K: Hash + Eq,
-    S: BuildHasher,
-{
-    type IntoIter = IterMut<'a, K, V>;
-    type Item = (&'a K, &'a mut V);
+    #[test]
+    fn test_get_or_insert_kv_returns_stored_key() {
+        struct Name {
+            normalized: String,
+            original: String,
+        }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter_mut()
-    }
-}
+        impl From<&str> for Name {
+            fn from(s: &str) -> Self {
+                Name {
+                    normalized: s.to_lowercase(),
+                    original: s.to_string(),
+                }
+            }
+        }
 
-impl<K, V, S> FromIterator<(K, V)> for HashMap<K, V, S>
-where
-    K: Hash + Eq,
-    S: BuildHasher + Default,
-{
-    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
-        let mut map = HashMap::new();
-        for (k, v) in iter {
-            map.insert(k, v);
+        impl Hash for Name {
+            fn hash<H: core::hash::Hasher>(
+                &self,
+                state: &mut H,
+            ) {
+                self.normalized.hash(state);
+            }
         }
-        map
+
+        impl PartialEq for Name {
+            fn eq(
+                &self,
+                other: &Self,
+            ) -> bool {
+                self.normalized == other.normalized
+            }
+        }
+
+        impl Eq for Name {}
+
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        let (key, value) = map.get_or_insert_kv(Name::from("Hello"), || 1);
+        assert_eq!(key.original, "Hello");
+        *value += 1;
+
+        let (key, value) = map.get_or_insert_kv(Name::from("HELLO"), || panic!("present"));
+        assert_eq!(key.original, "Hello");
+        assert_eq!(key.normalized, "hello");
+        assert_eq!(*value, 2);
+        assert_eq!(map.len(), 1);
     }
-}
 
-impl<K, V, S> Extend<(K, V)> for HashMap<K, V, S>
-where
-    K: Hash + Eq,
-    S: BuildHasher,
-{
-    fn extend<T: IntoIterator<Item = (K, V)>>(
-        &mut self,
-        iter: T,
-    ) {
-        for (k, v) in iter {
-            self.insert(k, v);
+    #[test]
+    fn test_iterators_stay_exhausted() {
+        fn assert_fused<I: FusedIterator>(mut iter: I) {
+            for _ in iter.by_ref() {}
+            for _ in 0..3 {
+                assert!(iter.next().is_none());
+            }
+        }
+
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..50 {
+            map.insert(i, i);
         }
+
+        assert_fused(map.iter());
+        assert_fused(map.keys());
+        assert_fused(map.values());
+        assert_fused(map.values_mut());
+        assert_fused(map.iter_mut());
+        assert_fused(map.clone().drain());
+        assert_fused(map.clone().into_keys());
+        assert_fused(map.clone().into_values());
+        assert_fused(map.into_iter());
     }
-}
 
-/// An iterator that removes and yields all values from the set that satisfy
-/// a given predicate.
-pub struct ExtractIf<'a, K, V> {
-    #[allow(clippy::type_complexity)]
-    inner: crate::hash_table::ExtractIf<
-        'a,
-        (K, V),
-        Box<dyn FnMut(&mut (K, V)) -> bool + 'a>,
-        Box<dyn Fn(&(K, V)) -> u64 + 'a>,
-    >,
-}
+    #[test]
+    fn test_iterators_report_exact_len() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..100 {
+            map.insert(i, i * 2);
+        }
 
-impl<K, V> Iterator for ExtractIf<'_, K, V> {
-    type Item = (K, V);
+        assert_eq!(map.iter().len(), map.len());
+        assert_eq!(map.keys().len(), map.len());
+        assert_eq!(map.values().len(), map.len());
+        assert_eq!(map.iter_mut().len(), 100);
+        assert_eq!(map.values_mut().len(), 100);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+        let mut values = map.values();
+        values.by_ref().take(40).for_each(drop);
+        assert_eq!(values.len(), 60);
+        assert_eq!(values.count(), 60);
+
+        let mut values_mut = map.values_mut();
+        values_mut.next();
+        assert_eq!(values_mut.size_hint(), (99, Some(99)));
     }
-}
-#[cfg(test)]
-mod tests {
-    use alloc::format;
-    use alloc::string::String;
-    use alloc::string::ToString;
-    use alloc::vec;
-    use alloc::vec::Vec;
-    use core::hash::BuildHasher;
 
-    use rand::TryRngCore;
-    use rand::rngs::OsRng;
-    use siphasher::sip::SipHasher;
+    #[test]
+    fn test_keys_clone() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..100 {
+            map.insert(i, i * 2);
+        }
 
-    use super::*;
+        let keys = map.keys();
+        let a: Vec<_> = keys.clone().collect();
+        let b: Vec<_> = keys.collect();
+        assert_eq!(a.len(), 100);
+        assert_eq!(a, b);
 
-    #[derive(Clone)]
-    struct SipHashBuilder {
-        k1: u64,
-        k2: u64,
+        let mut values = map.values();
+        values.next();
+        assert_eq!(values.clone().count(), 99);
+        assert_eq!(values.count(), 99);
     }
 
-    impl BuildHasher for SipHashBuilder {
-        type Hasher = SipHasher;
+    #[test]
+    fn test_get_many_matches_get() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..1000 {
+            map.insert(i, i * 10);
+        }
 
-        fn build_hasher(&self) -> Self::Hasher {
-            SipHasher::new_with_keys(self.k1, self.k2)
+        let queries: Vec<i32> = (0..200).map(|i| (i * 7) % 1500).chain([5, 5, 5]).collect();
+        let keys: Vec<&i32> = queries.iter().collect();
+        let results = map.get_many(&keys);
+
+        assert_eq!(results.len(), keys.len());
+        for (key, result) in keys.iter().zip(results) {
+            assert_eq!(result, map.get(key));
         }
+
+        assert!(map.get_many(&[]).is_empty());
     }
 
-    impl Default for SipHashBuilder {
-        fn default() -> Self {
-            let mut rng = OsRng;
-            Self {
-                k1: rng.try_next_u64().unwrap_or(0),
-                k2: rng.try_next_u64().unwrap_or(0),
-            }
+    #[test]
+    fn test_for_each_mut_by_keys_updates_present_keys() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..100 {
+            map.insert(i, i * 10);
         }
+
+        let queries = [3, 150, 42, 3, 99, -1];
+        let keys: Vec<&i32> = queries.iter().collect();
+        let mut visited = Vec::new();
+        map.for_each_mut_by_keys(&keys, |key, value| {
+            visited.push((*key, value.is_some()));
+            if let Some(value) = value {
+                *value += 1;
+            }
+        });
+
+        assert_eq!(
+            visited,
+            [
+                (3, true),
+                (150, false),
+                (42, true),
+                (3, true),
+                (99, true),
+                (-1, false)
+            ]
+        );
+        assert_eq!(map.get(&3), Some(&32));
+        assert_eq!(map.get(&42), Some(&421));
+        assert_eq!(map.get(&99), Some(&991));
+        assert_eq!(map.get(&0), Some(&0));
+        assert_eq!(map.len(), 100);
     }
 
     #[test]
-    fn test_new_and_with_hasher() {
-        let map: HashMap<i32, String, SipHashBuilder> = HashMap::new();
-        assert!(map.is_empty());
-        assert_eq!(map.len(), 0);
+    fn test_remove_precomputed() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..100 {
+            map.insert(i, i * 2);
+        }
 
-        let map2 = HashMap::<i32, String, _>::with_hasher(SipHashBuilder::default());
-        assert!(map2.is_empty());
-        assert_eq!(map2.len(), 0);
+        let hash = map.hasher().hash_one(42);
+        assert_eq!(map.remove_precomputed(&42, hash), Some(84));
+        assert_eq!(map.remove_precomputed(&42, hash), None);
+        assert_eq!(map.len(), 99);
+        assert!(!map.contains_key(&42));
     }
 
     #[test]
-    fn test_with_capacity() {
-        let map: HashMap<i32, String, SipHashBuilder> = HashMap::with_capacity(100);
-        assert!(map.capacity() >= 100);
-        assert!(map.is_empty());
-
-        let map2 =
-            HashMap::<i32, String, _>::with_capacity_and_hasher(200, SipHashBuilder::default());
-        assert!(map2.capacity() >= 200);
-        assert!(map2.is_empty());
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "remove_precomputed: hash does not match key")]
+    fn test_remove_precomputed_rejects_wrong_hash() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.insert(1, 1);
+        let hash = map.hasher().hash_one(2);
+        map.remove_precomputed(&1, hash);
     }
 
     #[test]
-    fn test_insert_and_get() {
+    fn test_reserve_at_load_leaves_headroom() {
         let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..100 {
+            map.insert(i, i);
+        }
 
-        assert_eq!(map.insert(1, "hello".to_string()), None);
-        assert_eq!(map.len(), 1);
-        assert!(!map.is_empty());
+        // 1000 planned entries at a target load of 0.5.
+        map.reserve_at_load(900, 0.5);
+        let capacity = map.capacity();
+        assert!(capacity >= 2_000, "capacity {capacity} is below the target");
+        for i in 100..1_000 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.capacity(), capacity, "planned inserts must not resize");
 
-        assert_eq!(map.get(&1), Some(&"hello".to_string()));
-        assert_eq!(map.get(&2), None);
+        // Capacity is rounded up to the table's geometry, so the load can land
+        // anywhere at or below the target.
+        let load = map.capacity_info().load_factor;
+        assert!(load <= 0.5, "load {load} exceeds the target");
+    }
+
+    #[test]
+    #[should_panic(expected = "target load must be within (0.0, 1.0]")]
+    fn test_reserve_at_load_rejects_zero_target() {
+        let mut map: HashMap<i32, i32, SipHashBuilder> =
+            HashMap::with_hasher(SipHashBuilder::default());
+        map.reserve_at_load(10, 0.0);
+    }
 
+    #[test]
+    #[cfg(any(feature = "std", feature = "foldhash"))]
+    fn test_fixed_maps_iterate_identically() {
+        let build = |mut map: HashMap<u64, u64, crate::FixedState>| {
+            for i in 0..1_000u64 {
+                map.insert(i.wrapping_mul(0x9e37_79b9), i);
+            }
+            for i in (0..1_000u64).step_by(3) {
+                map.remove(&i.wrapping_mul(0x9e37_79b9));
+            }
+            map
+        };
+
+        // Two maps with separately constructed fixed hashers produce the same
+        // output, in the same order.
+        let a = build(HashMap::fixed());
+        let b = build(HashMap::with_hasher(crate::FixedState::default()));
+        assert_eq!(a.iter().collect::<Vec<_>>(), b.iter().collect::<Vec<_>>());
+        assert_eq!(format!("{a:?}"), format!("{b:?}"));
         assert_eq!(
-            map.insert(1, "world".to_string()),
-            Some("hello".to_string())
+            a.hasher().hash_one("snapshot"),
+            crate::FixedState::default().hash_one("snapshot")
         );
-        assert_eq!(map.len(), 1);
-        assert_eq!(map.get(&1), Some(&"world".to_string()));
     }
 
     #[test]
-    fn test_get_mut() {
+    fn test_map_values_in_place_doubles_without_resizing() {
         let mut map = HashMap::with_hasher(SipHashBuilder::default());
-        map.insert(1, "hello".to_string());
-
-        if let Some(value) = map.get_mut(&1) {
-            value.push_str(" world");
+        for i in 0..500 {
+            map.insert(i, i);
         }
+        let info = map.capacity_info();
+        let order: Vec<i32> = map.keys().copied().collect();
 
-        assert_eq!(map.get(&1), Some(&"hello world".to_string()));
-        assert_eq!(map.get_mut(&2), None);
+        map.map_values_in_place(|_, v| *v *= 2);
+
+        assert_eq!(map.capacity_info(), info);
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), order);
+        for i in 0..500 {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
     }
 
     #[test]
@@ -1556,4 +4172,460 @@ mod tests {
         assert_eq!(map.get(&2), Some(&40));
         assert_eq!(map.get(&3), Some(&60));
     }
+
+    #[test]
+    fn test_retain_with_hash() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..1000u64 {
+            map.insert(i, i * 2);
+        }
+
+        let mut visited = 0;
+        let hasher = map.hasher().clone();
+        map.retain_with_hash(|hash, k, v| {
+            assert_eq!(hash, hasher.hash_one(k));
+            assert_eq!(*v, k * 2);
+            visited += 1;
+            hash % 2 == 0
+        });
+        assert_eq!(visited, 1000);
+
+        let expected = (0..1000u64).filter(|k| hasher.hash_one(k) % 2 == 0).count();
+        assert_eq!(map.len(), expected);
+        for i in 0..1000u64 {
+            let even = hasher.hash_one(i) % 2 == 0;
+            assert_eq!(map.get(&i), even.then_some(&(i * 2)));
+        }
+    }
+
+    #[test]
+    fn test_or_insert_reporting_counts_first_occurrences() {
+        let mut rng = OsRng;
+        let stream: Vec<u64> = (0..500)
+            .map(|_| rng.try_next_u64().unwrap() % 100)
+            .collect();
+
+        let mut counts = HashMap::with_hasher(SipHashBuilder::default());
+        let mut first_occurrences = Vec::new();
+        for (position, &value) in stream.iter().enumerate() {
+            let (count, inserted) = counts.entry(value).or_insert_reporting(0u32);
+            *count += 1;
+            if inserted {
+                first_occurrences.push(position);
+            }
+        }
+
+        let expected: Vec<_> = (0..stream.len())
+            .filter(|&i| !stream[..i].contains(&stream[i]))
+            .collect();
+        assert_eq!(first_occurrences, expected);
+        assert_eq!(counts.len(), expected.len());
+        for (&value, &count) in counts.iter() {
+            let occurrences = stream.iter().filter(|&&v| v == value).count();
+            assert_eq!(count as usize, occurrences);
+        }
+    }
+
+    #[test]
+    fn test_remove_by_handle() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..100u32 {
+            map.insert(i, i.to_string());
+        }
+
+        let (value, handle) = map.entry(500).or_insert_handle("new".to_string());
+        value.push('!');
+        assert_eq!(
+            map.remove_by_handle(handle),
+            Some((500, "new!".to_string()))
+        );
+        assert_eq!(map.len(), 100);
+        assert!(!map.contains_key(&500));
+
+        let (_, handle) = map.entry(42).or_insert_handle("unused".to_string());
+        assert_eq!(map.remove_by_handle(handle), Some((42, "42".to_string())));
+        assert_eq!(map.len(), 99);
+
+        let handle = match map.entry(7) {
+            Entry::Occupied(entry) => entry.handle(),
+            Entry::Vacant(_) => unreachable!(),
+        };
+        assert_eq!(map.remove_by_handle(handle), Some((7, "7".to_string())));
+
+        for i in (0..100).filter(|&i| i != 42 && i != 7) {
+            assert_eq!(map.get(&i), Some(&i.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_remove_by_stale_handle_returns_none() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        let (_, handle) = map.entry(1).or_insert_handle(1);
+        map.insert(2, 2);
+        assert_eq!(map.remove_by_handle(handle), None);
+
+        let mut other = HashMap::with_hasher(SipHashBuilder::default());
+        other.insert(1, 1);
+        let (_, handle) = map.entry(3).or_insert_handle(3);
+        assert_eq!(other.remove_by_handle(handle), None);
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(other.len(), 1);
+        assert_eq!(map.remove_by_handle(handle), Some((3, 3)));
+    }
+
+    #[test]
+    fn test_eq_detects_single_differing_value() {
+        let a: HashMap<u64, u64, SipHashBuilder> = (0..10_000).map(|i| (i, i)).collect();
+        let mut b = HashMap::with_hasher(SipHashBuilder::default());
+        for i in (0..10_000).rev() {
+            b.insert(i, i);
+        }
+        assert_eq!(a, b);
+
+        *b.get_mut(&5_000).unwrap() += 1;
+        assert_ne!(a, b);
+
+        b.insert(5_000, 5_000);
+        assert_eq!(a, b);
+        b.remove(&0);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_insert_reserved_after_reserve() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.reserve(1000);
+        let capacity = map.capacity();
+        for i in 0..1000u64 {
+            assert_eq!(map.insert_reserved(i, i), None);
+        }
+        assert_eq!(map.insert_reserved(10, 11), Some(10));
+        assert_eq!(map.capacity(), capacity);
+        assert_eq!(map.len(), 1000);
+        for i in (0..1000u64).filter(|&i| i != 10) {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+        assert_eq!(map.get(&10), Some(&11));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "entry_reserved called without reserved capacity")]
+    fn test_insert_reserved_without_capacity_panics() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.reserve(10);
+        // A full neighborhood can grow the map before it reaches capacity at the
+        // higher densities, so fill whatever capacity it ends up with.
+        let mut i = 0u64;
+        while map.len() < map.capacity() {
+            map.insert_reserved(i, i);
+            i += 1;
+        }
+        map.insert_reserved(i, i);
+    }
+
+    #[test]
+    fn test_extend_reporting_counts_overwrites() {
+        let mut map: HashMap<u64, u64, SipHashBuilder> = (0..1000).map(|i| (i, i)).collect();
+        let overwritten = map.extend_reporting((500..2000).map(|i| (i, i + 1)));
+        assert_eq!(overwritten, 500);
+        assert_eq!(map.len(), 2000);
+        for i in 0..2000 {
+            let expected = if i < 500 { i } else { i + 1 };
+            assert_eq!(map.get(&i), Some(&expected));
+        }
+
+        assert_eq!(map.extend_reporting([(5000, 0), (5000, 1), (0, 0)]), 2);
+        assert_eq!(map.get(&5000), Some(&1));
+        assert_eq!(map.extend_reporting(core::iter::empty()), 0);
+    }
+
+    #[test]
+    fn test_compute_transitions() {
+        let mut map: HashMap<u64, u64, SipHashBuilder> = (0..100).map(|i| (i, i)).collect();
+
+        // None -> Some inserts.
+        assert_eq!(map.compute(500, |_, old| old.or(Some(7))), Some(&mut 7));
+        // Some -> Some updates.
+        assert_eq!(map.compute(5, |k, old| old.map(|v| v + k)), Some(&mut 10));
+        // Some -> None removes.
+        assert_eq!(map.compute(6, |_, _| None), None);
+        // None -> None does nothing.
+        let mut called = false;
+        assert_eq!(
+            map.compute(600, |_, old| {
+                called = true;
+                old
+            }),
+            None
+        );
+        assert!(called);
+
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.get(&500), Some(&7));
+        assert_eq!(map.get(&5), Some(&10));
+        assert!(!map.contains_key(&6));
+        assert!(!map.contains_key(&600));
+
+        assert_eq!(
+            map.compute_if_present(&7, |_, v| Some(v * 3)),
+            Some(&mut 21)
+        );
+        assert_eq!(map.compute_if_present(&8, |_, _| None), None);
+        assert_eq!(map.compute_if_present(&700, |_, _| unreachable!()), None);
+        assert_eq!(map.len(), 99);
+        for i in (0..100).filter(|i| ![5, 6, 7, 8].contains(i)) {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_clear_to_capacity_shrinks_to_floor() {
+        let minimum =
+            HashMap::<u64, u64, _>::with_capacity_and_hasher(1, SipHashBuilder::default())
+                .capacity_info();
+
+        let mut map: HashMap<u64, u64, SipHashBuilder> = (0..1_000_000).map(|i| (i, i)).collect();
+        map.clear_to_capacity(16);
+        assert!(map.is_empty());
+        assert_eq!(map.capacity(), minimum.live);
+        assert_eq!(
+            map.capacity_info().allocation_bytes,
+            minimum.allocation_bytes
+        );
+
+        // A floor larger than the current geometry keeps the allocation.
+        map.extend((0..10).map(|i| (i, i)));
+        map.clear_to_capacity(1_000_000);
+        assert!(map.is_empty());
+        assert_eq!(map.capacity(), minimum.live);
+
+        map.insert(1, 1);
+        assert_eq!(map.get(&1), Some(&1));
+        map.clear_to_capacity(0);
+        assert_eq!(map.capacity(), 0);
+        assert_eq!(map.capacity_info().allocation_bytes, 0);
+        map.insert(2, 2);
+        assert_eq!(map.get(&2), Some(&2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_collect_exact_size_allocates_once() {
+        use crate::hash_table::tests::alloc_hooks;
+
+        let pairs: Vec<(u64, u64)> = (0..100_000).map(|i| (i, i)).collect();
+        let (map, allocs) = alloc_hooks::count_allocs(|| {
+            pairs
+                .iter()
+                .copied()
+                .collect::<HashMap<u64, u64, SipHashBuilder>>()
+        });
+        assert_eq!(allocs, 1);
+        assert_eq!(map.len(), pairs.len());
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn test_metrics_sink_counts_operations() {
+        use crate::metrics::tests::CountingSink;
+
+        static SINK: CountingSink = CountingSink::new();
+        let counts = || SINK.counts();
+
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.set_metrics_sink(Some(&SINK));
+        let mut resizes = 0;
+        for i in 0..1000u64 {
+            let slots = map.slot_capacity();
+            map.insert(i, i);
+            if map.slot_capacity() != slots {
+                resizes += 1;
+            }
+        }
+        map.insert(0, 1);
+        for i in 0..10u64 {
+            assert!(map.contains_key(&i));
+        }
+        assert_eq!(map.get_mut(&5000), None);
+        map.remove(&1);
+        map.remove(&5000);
+        assert_eq!(counts(), [11, 1001, 2, resizes]);
+
+        let slots = map.slot_capacity();
+        map.reserve(100_000);
+        assert_ne!(map.slot_capacity(), slots);
+        map.set_metrics_sink(None);
+        map.insert(5000, 5000);
+        assert_eq!(counts(), [11, 1001, 2, resizes + 1]);
+    }
+
+    #[test]
+    fn test_sorted_conversions() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for key in (0..1000u64).map(|i| i * 7919 % 10_000) {
+            map.insert(key, key * 2);
+        }
+
+        let sorted = map.to_sorted_vec();
+        assert_eq!(sorted.len(), map.len());
+        assert!(sorted.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(sorted.iter().all(|&(&k, &v)| v == k * 2));
+        let sorted: Vec<(u64, u64)> = sorted.into_iter().map(|(&k, &v)| (k, v)).collect();
+
+        let btree = map.into_btree_map();
+        assert!(btree.into_iter().eq(sorted));
+    }
+
+    #[test]
+    fn test_remove_if() {
+        let mut map: HashMap<u64, u64, SipHashBuilder> = (0..100).map(|i| (i, i)).collect();
+
+        assert_eq!(map.remove_if(&10, |&v| v == 10), Some(10));
+        assert!(!map.contains_key(&10));
+
+        let mut seen = None;
+        assert_eq!(
+            map.remove_if(&20, |&v| {
+                seen = Some(v);
+                false
+            }),
+            None
+        );
+        assert_eq!(seen, Some(20));
+        assert_eq!(map.get(&20), Some(&20));
+
+        assert_eq!(map.remove_if(&500, |_| unreachable!()), None);
+        assert_eq!(map.len(), 99);
+        for i in (0..100).filter(|&i| i != 10) {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_auto_shrink_is_stable_under_alternating_updates() {
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.set_auto_shrink(Some(0.25));
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        let grown = map.capacity();
+
+        // 3584 pairs nearly fill the smallest geometry that fits them, so
+        // shrinking to fit would leave no room to double.
+        map.retain(|&k, _| k < 3_584);
+        let shrunk = map.capacity();
+        assert!(shrunk < grown);
+
+        // Doubling the map and emptying it back out again must neither grow
+        // nor shrink it.
+        for round in 0..10 {
+            for i in 0..3_584 {
+                map.insert(20_000 + i, round);
+                assert_eq!(map.capacity(), shrunk);
+            }
+            for i in 0..3_584 {
+                map.remove(&(20_000 + i));
+                assert_eq!(map.capacity(), shrunk);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "auto-shrink load must be within 0.0..=0.25")]
+    fn test_auto_shrink_rejects_high_load() {
+        let mut map: HashMap<i32, i32, SipHashBuilder> =
+            HashMap::with_hasher(SipHashBuilder::default());
+        map.set_auto_shrink(Some(0.5));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_auto_shrink_keeps_allocation_when_emptied() {
+        use crate::hash_table::tests::alloc_hooks;
+
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.set_auto_shrink(Some(0.25));
+        for i in 0..1_000 {
+            map.insert(i, i);
+        }
+        map.retain(|_, _| false);
+        assert!(map.capacity() > 0);
+
+        let ((), allocs) = alloc_hooks::count_allocs(|| {
+            for i in 0..1_000 {
+                map.insert(i, i);
+                map.remove(&i);
+            }
+        });
+        assert_eq!(allocs, 0);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn test_metrics_sink_counts_entry_paths() {
+        use crate::metrics::tests::CountingSink;
+
+        static SINK: CountingSink = CountingSink::new();
+
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        map.set_metrics_sink(Some(&SINK));
+        let mut resizes = 0;
+        for i in 0..3000u64 {
+            let slots = map.slot_capacity();
+            match i % 3 {
+                0 => {
+                    map.entry(i).or_insert(i);
+                }
+                1 => {
+                    map.get_or_insert_kv(i, || i);
+                }
+                _ => {
+                    map.insert_unique_unchecked(i, i);
+                }
+            }
+            if map.slot_capacity() != slots {
+                resizes += 1;
+            }
+        }
+        assert!(resizes > 1);
+        assert_eq!(SINK.counts(), [0, 3000, 0, resizes]);
+
+        // Lookups through the entry API store nothing, so they are not inserts.
+        map.entry(0).or_insert(1);
+        map.get_or_insert_kv(1, || 2);
+        assert_eq!(SINK.counts(), [0, 3000, 0, resizes]);
+
+        assert_eq!(map.try_insert(0, 1), Ok(Some(0)));
+        map.entry(5000).or_insert_handle(5000);
+        if let Entry::Occupied(mut entry) = map.entry(5000) {
+            entry.insert(5001);
+        }
+        assert_eq!(SINK.counts(), [0, 3003, 0, resizes]);
+    }
+
+    #[test]
+    fn test_with_capacity_compact_stays_under_a_kilobyte() {
+        let mut map: HashMap<u64, u64, SipHashBuilder> =
+            HashMap::with_capacity_compact_and_hasher(8, SipHashBuilder::default());
+        let regular: HashMap<u64, u64, SipHashBuilder> =
+            HashMap::with_capacity_and_hasher(8, SipHashBuilder::default());
+        let bytes = map.capacity_info().allocation_bytes;
+        assert!(bytes < 1024, "{bytes} bytes");
+        assert!(bytes < regular.capacity_info().allocation_bytes / 4);
+
+        let capacity = map.capacity() as u64;
+        map.extend((0..capacity).map(|i| (i, i)));
+        assert_eq!(map.capacity_info().allocation_bytes, bytes);
+        for i in 0..capacity {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+
+        map.insert(capacity, capacity);
+        assert!(map.capacity_info().allocation_bytes > bytes);
+        assert_eq!(map.len() as u64, capacity + 1);
+        assert!((0..=capacity).all(|i| map.get(&i) == Some(&i)));
+    }
 }
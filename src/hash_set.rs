@@ -1,10 +1,21 @@
 use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
 use core::fmt::Debug;
 use core::hash::BuildHasher;
 use core::hash::Hash;
+use core::iter::FusedIterator;
 
+use crate::hash_table::CapacityInfo;
 use crate::hash_table::HashTable;
 use crate::hash_table::TryEntryError;
+use crate::hash_table::TryReserveError;
+use crate::hash_table::extend_reserve_hint;
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsEvent;
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsSink;
 
 /// A hash set implemented using the hopscotch HashTable as the underlying
 /// storage.
@@ -24,6 +35,14 @@ use crate::hash_table::TryEntryError;
 /// - **Lookup**: O(1) with a bounded probe distance of at most 16 buckets (8
 ///   for 8-way).
 /// - **Deletion**: O(1) with the same bounded probe distance as lookup.
+///
+/// # Cloning
+///
+/// Cloning copies the table layout as-is and clones every value into the
+/// same slot, so no value is rehashed and the clone iterates in the same
+/// order as the original. The cost is one allocation plus one `clone` of
+/// each `T`. Use [`try_clone`](Self::try_clone) to handle allocation failure
+/// instead of aborting.
 #[derive(Clone)]
 pub struct HashSet<T, S> {
     table: HashTable<T>,
@@ -108,6 +127,7 @@ where
     /// assert!(set.capacity() >= 100);
     /// # }
     /// ```
+    #[track_caller]
     pub fn with_capacity_and_hasher(
         capacity: usize,
         hash_builder: S,
@@ -118,6 +138,36 @@ where
         }
     }
 
+    /// Creates a new hash set with the specified capacity and hasher builder,
+    /// using as little memory as possible for small capacities.
+    ///
+    /// See [`HashTable::with_capacity_compact`] for when the compact layout
+    /// is used.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// use hop_hash::hash_set::HashSet;
+    ///
+    /// let set: HashSet<u64, _> = HashSet::with_capacity_compact_and_hasher(8, RandomState::new());
+    /// assert!(set.capacity() >= 8);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn with_capacity_compact_and_hasher(
+        capacity: usize,
+        hash_builder: S,
+    ) -> Self {
+        Self {
+            table: HashTable::with_capacity_compact(capacity),
+            hash_builder,
+        }
+    }
+
     /// Returns the number of elements in the set.
     ///
     /// # Examples
@@ -159,7 +209,9 @@ where
     /// Returns the current capacity of the set.
     ///
     /// The capacity represents the maximum number of elements the set can
-    /// hold before it needs to resize.
+    /// hold before it needs to resize. It is smaller than the number of
+    /// allocated slots, which is reported by
+    /// [`slot_capacity`](Self::slot_capacity).
     ///
     /// # Examples
     ///
@@ -176,6 +228,70 @@ where
         self.table.capacity()
     }
 
+    /// Returns the total number of slots allocated by the set.
+    ///
+    /// Slots are allocated in groups of 16, and the set resizes before all of
+    /// them are filled, so this is larger than [`capacity`](Self::capacity)
+    /// for any non-empty allocation. Use it to reason about memory use rather
+    /// than about when the next resize happens.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let set: HashSet<i32> = HashSet::with_capacity(100);
+    /// assert!(set.slot_capacity() > set.capacity());
+    /// # }
+    /// ```
+    pub fn slot_capacity(&self) -> usize {
+        self.table.slot_capacity()
+    }
+
+    /// Returns the set's capacity metrics in a single call.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let set: HashSet<i32> = HashSet::with_capacity(100);
+    /// let info = set.capacity_info();
+    /// assert_eq!(info.live, set.capacity());
+    /// assert_eq!(info.slots, set.slot_capacity());
+    /// assert_eq!(info.load_factor, 0.0);
+    /// # }
+    /// ```
+    pub fn capacity_info(&self) -> CapacityInfo {
+        self.table.capacity_info()
+    }
+
+    /// Returns `true` if `additional` more elements fit in the set without a
+    /// resize.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let set: HashSet<i32> = HashSet::with_capacity(100);
+    /// assert!(set.capacity_fits(100));
+    /// assert!(!set.capacity_fits(set.capacity() + 1));
+    /// # }
+    /// ```
+    pub fn capacity_fits(
+        &self,
+        additional: usize,
+    ) -> bool {
+        self.table.capacity_fits(additional)
+    }
+
     /// Removes all elements from the set.
     ///
     /// This operation preserves the set's allocated capacity.
@@ -198,6 +314,64 @@ where
         self.table.clear();
     }
 
+    /// Removes all elements from the set and shrinks its capacity down to
+    /// what `floor` elements need.
+    ///
+    /// This combines [`clear`](Self::clear) with a shrink, so a set that
+    /// briefly held many more values than usual does not keep that allocation.
+    /// A `floor` of zero frees the allocation entirely. The capacity never
+    /// grows, so a set already smaller than `floor` needs keeps its
+    /// allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut set: HashSet<u64> = (0..100_000).collect();
+    /// set.clear_to_capacity(16);
+    /// assert!(set.is_empty());
+    /// assert!(set.capacity() >= 16);
+    /// assert!(set.capacity() < 1000);
+    /// # }
+    /// ```
+    pub fn clear_to_capacity(
+        &mut self,
+        floor: usize,
+    ) {
+        self.table.clear_to_capacity(floor);
+    }
+
+    /// Registers `sink` to receive this set's operation events, or stops
+    /// reporting them with `None`.
+    ///
+    /// The set reports [`contains`](Self::contains) and [`get`](Self::get) as
+    /// [`MetricsEvent::Get`], and [`remove`](Self::remove) and
+    /// [`take`](Self::take) as [`MetricsEvent::Remove`]. Every insertion
+    /// reports [`MetricsEvent::Insert`], whether it goes through
+    /// [`insert`](Self::insert), [`try_insert`](Self::try_insert),
+    /// [`replace`](Self::replace), or an [`entry`](Self::entry) or
+    /// [`get_or_insert_with`](Self::get_or_insert_with) that stores a value.
+    /// Any operation that grows the table also reports
+    /// [`MetricsEvent::Resize`]. Other operations are not reported. Clones of
+    /// the set report to the same sink.
+    #[cfg(feature = "metrics")]
+    pub fn set_metrics_sink(
+        &mut self,
+        sink: Option<&'static dyn MetricsSink>,
+    ) {
+        self.table.set_metrics_sink(sink);
+    }
+
+    /// Returns the sink registered with
+    /// [`set_metrics_sink`](Self::set_metrics_sink), if any.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_sink(&self) -> Option<&'static dyn MetricsSink> {
+        self.table.metrics_sink()
+    }
+
     /// Shrinks the capacity of the set as much as possible.
     ///
     /// This method will shrink the underlying storage to fit the current number
@@ -233,7 +407,50 @@ where
         self.table.shrink_to_fit(|k| self.hash_builder.hash_one(k));
     }
 
+    /// Clones the set, returning an error instead of aborting if the
+    /// allocation fails.
+    ///
+    /// This performs the same layout-preserving clone as [`Clone::clone`]. If
+    /// the allocation fails, nothing is cloned and `self` is left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut set: HashSet<i32> = HashSet::new();
+    /// set.insert(1);
+    ///
+    /// let cloned = set.try_clone().unwrap();
+    /// assert_eq!(set, cloned);
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::AllocError`] if the allocator fails to
+    /// provide memory for the new set.
+    pub fn try_clone(&self) -> Result<Self, TryReserveError>
+    where
+        T: Clone,
+        S: Clone,
+    {
+        Ok(Self {
+            table: self.table.try_clone()?,
+            hash_builder: self.hash_builder.clone(),
+        })
+    }
+
     /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics with "capacity overflow" if the required capacity cannot be
+    /// represented. Use [`try_reserve`](Self::try_reserve) to handle this
+    /// instead.
+    #[track_caller]
     pub fn reserve(
         &mut self,
         additional: usize,
@@ -242,6 +459,40 @@ where
             .reserve(additional, |k| self.hash_builder.hash_one(k));
     }
 
+    /// Tries to reserve capacity for at least `additional` more elements.
+    ///
+    /// On error the set is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    /// use hop_hash::TryReserveError;
+    ///
+    /// let mut set: HashSet<i32> = HashSet::new();
+    /// assert!(set.try_reserve(10).is_ok());
+    /// assert_eq!(
+    ///     set.try_reserve(usize::MAX),
+    ///     Err(TryReserveError::CapacityOverflow)
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::CapacityOverflow`] if the required capacity
+    /// cannot be represented, or [`TryReserveError::AllocError`] if the
+    /// allocator fails.
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), TryReserveError> {
+        self.table
+            .try_reserve(additional, |k| self.hash_builder.hash_one(k))
+    }
+
     /// Adds a value to the set.
     ///
     /// Returns whether the value was newly inserted. That is:
@@ -266,6 +517,8 @@ where
         &mut self,
         value: T,
     ) -> bool {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Insert);
         let hash = self.hash_builder.hash_one(&value);
         match self
             .table
@@ -279,6 +532,98 @@ where
         }
     }
 
+    /// Adds a value to a set that already has room for it.
+    ///
+    /// This behaves like [`insert`](Self::insert), but skips the capacity
+    /// check `insert` performs before every lookup. It is meant for loops
+    /// that follow a [`reserve`](Self::reserve) covering every value they
+    /// insert.
+    ///
+    /// # Contract
+    ///
+    /// The set must have spare capacity, i.e. `len() < capacity()`, even if
+    /// `value` is already present. Debug builds panic otherwise. Release
+    /// builds stay memory safe and still add the value, but the set may fill
+    /// past its target load factor until it next resizes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut set: HashSet<u32> = HashSet::new();
+    /// set.reserve(100);
+    /// for i in 0..100 {
+    ///     assert!(set.insert_reserved(i));
+    /// }
+    /// assert!(!set.insert_reserved(7));
+    /// assert_eq!(set.len(), 100);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn insert_reserved(
+        &mut self,
+        value: T,
+    ) -> bool {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Insert);
+        let hash = self.hash_builder.hash_one(&value);
+        match self
+            .table
+            .entry_reserved(hash, |v| v == &value, |v| self.hash_builder.hash_one(v))
+        {
+            crate::hash_table::Entry::Occupied(_) => false,
+            crate::hash_table::Entry::Vacant(entry) => {
+                entry.insert(value);
+                true
+            }
+        }
+    }
+
+    /// Adds a value to the set, returning it back if an equal value is
+    /// already present.
+    ///
+    /// Unlike [`insert`](Self::insert), a duplicate is reported as an error
+    /// that hands the rejected value back to the caller. The set is left
+    /// unchanged in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut set: HashSet<String> = HashSet::new();
+    /// assert_eq!(set.insert_unique("a".to_string()), Ok(()));
+    /// assert_eq!(set.insert_unique("a".to_string()), Err("a".to_string()));
+    /// assert_eq!(set.len(), 1);
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(value)` if the set already contains a value equal to
+    /// `value`.
+    pub fn insert_unique(
+        &mut self,
+        value: T,
+    ) -> Result<(), T> {
+        let hash = self.hash_builder.hash_one(&value);
+        match self
+            .table
+            .entry(hash, |v| v == &value, |v| self.hash_builder.hash_one(v))
+        {
+            crate::hash_table::Entry::Occupied(_) => Err(value),
+            crate::hash_table::Entry::Vacant(entry) => {
+                entry.insert(value);
+                Ok(())
+            }
+        }
+    }
+
     /// Tries to add a value to the set without resizing.
     ///
     /// Returns whether the value was newly inserted. That is:
@@ -309,14 +654,17 @@ where
         value: T,
     ) -> Result<bool, (T, TryEntryError)> {
         let hash = self.hash_builder.hash_one(&value);
-        match self.table.try_entry(hash, |v| v == &value) {
-            Ok(crate::hash_table::Entry::Occupied(_)) => Ok(false),
+        let inserted = match self.table.try_entry(hash, |v| v == &value) {
+            Ok(crate::hash_table::Entry::Occupied(_)) => false,
             Ok(crate::hash_table::Entry::Vacant(entry)) => {
                 entry.insert(value);
-                Ok(true)
+                true
             }
-            Err(e) => Err((value, e)),
-        }
+            Err(e) => return Err((value, e)),
+        };
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Insert);
+        Ok(inserted)
     }
 
     /// Returns `true` if the set contains a value.
@@ -338,6 +686,8 @@ where
         &self,
         value: &T,
     ) -> bool {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Get);
         let hash = self.hash_builder.hash_one(value);
         self.table.find(hash, |v| v == value).is_some()
     }
@@ -362,6 +712,8 @@ where
         &mut self,
         value: &T,
     ) -> bool {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Remove);
         let hash = self.hash_builder.hash_one(value);
         self.table.remove(hash, |v| v == value).is_some()
     }
@@ -387,6 +739,8 @@ where
         &mut self,
         value: T,
     ) -> Option<T> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Insert);
         let hash = self.hash_builder.hash_one(&value);
         match self
             .table
@@ -422,6 +776,8 @@ where
         &mut self,
         value: &T,
     ) -> Option<T> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Remove);
         let hash = self.hash_builder.hash_one(value);
         self.table.remove(hash, |v| v == value)
     }
@@ -446,10 +802,109 @@ where
         &self,
         value: &T,
     ) -> Option<&T> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.table.metrics_sink(), MetricsEvent::Get);
         let hash = self.hash_builder.hash_one(value);
         self.table.find(hash, |v| v == value)
     }
 
+    /// Returns a reference to the value in the set equal to `value`,
+    /// inserting `make(value)` first if there is none.
+    ///
+    /// `value` may be any borrowed form of the set's value type, which makes
+    /// this useful for interning: the owned value is only built on a miss.
+    /// As with [`Borrow`], the borrowed form must hash and compare the same
+    /// way as the owned value, and `make` must return a value equal to
+    /// `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut set: HashSet<String> = HashSet::new();
+    /// let a = set.get_or_insert_with("hello", str::to_string).as_ptr();
+    /// let b = set.get_or_insert_with("hello", str::to_string).as_ptr();
+    /// assert_eq!(a, b);
+    /// assert_eq!(set.len(), 1);
+    /// # }
+    /// ```
+    pub fn get_or_insert_with<Q>(
+        &mut self,
+        value: &Q,
+        make: impl FnOnce(&Q) -> T,
+    ) -> &T
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let hash = self.hash_builder.hash_one(value);
+        match self.table.entry(
+            hash,
+            |v| v.borrow() == value,
+            |v| self.hash_builder.hash_one(v),
+        ) {
+            crate::hash_table::Entry::Occupied(entry) => entry.into_mut(),
+            crate::hash_table::Entry::Vacant(entry) => {
+                let owned = make(value);
+                debug_assert!(
+                    owned.borrow() == value,
+                    "get_or_insert_with: make returned a value not equal to its input"
+                );
+                #[cfg(feature = "metrics")]
+                crate::metrics::record(entry.metrics_sink(), MetricsEvent::Insert);
+                entry.insert(owned)
+            }
+        }
+    }
+
+    /// Gets the entry for `value` in the set, which is either the equal value
+    /// already stored or a vacant slot holding `value` until it is inserted.
+    ///
+    /// This allows acting on presence with a single lookup.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    /// use hop_hash::hash_set::Entry;
+    ///
+    /// let mut set: HashSet<&str> = HashSet::new();
+    /// set.insert("a");
+    ///
+    /// match set.entry("a") {
+    ///     Entry::Occupied(entry) => assert_eq!(entry.get(), &"a"),
+    ///     Entry::Vacant(_) => unreachable!(),
+    /// }
+    ///
+    /// match set.entry("b") {
+    ///     Entry::Occupied(_) => unreachable!(),
+    ///     Entry::Vacant(entry) => {
+    ///         entry.insert();
+    ///     }
+    /// }
+    ///
+    /// assert!(set.contains(&"b"));
+    /// # }
+    /// ```
+    pub fn entry(
+        &mut self,
+        value: T,
+    ) -> Entry<'_, T> {
+        let hash = self.hash_builder.hash_one(&value);
+        match self
+            .table
+            .entry(hash, |v| v == &value, |v| self.hash_builder.hash_one(v))
+        {
+            crate::hash_table::Entry::Occupied(entry) => Entry::Occupied(OccupiedEntry { entry }),
+            crate::hash_table::Entry::Vacant(entry) => Entry::Vacant(VacantEntry { entry, value }),
+        }
+    }
+
     /// Returns an iterator over the values of the set.
     ///
     /// # Examples
@@ -504,6 +959,49 @@ where
         }
     }
 
+    /// Returns references to every value, in sorted order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let set: HashSet<i32> = [3, 1, 2].into_iter().collect();
+    /// assert_eq!(set.to_sorted_vec(), [&1, &2, &3]);
+    /// # }
+    /// ```
+    pub fn to_sorted_vec(&self) -> Vec<&T>
+    where
+        T: Ord,
+    {
+        let mut values: Vec<_> = self.iter().collect();
+        values.sort_unstable();
+        values
+    }
+
+    /// Consumes the set, moving every value into a [`BTreeSet`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let set: HashSet<i32> = [3, 1, 2].into_iter().collect();
+    /// let sorted = set.into_btree_set();
+    /// assert!(sorted.into_iter().eq([1, 2, 3]));
+    /// # }
+    /// ```
+    pub fn into_btree_set(self) -> BTreeSet<T>
+    where
+        T: Ord,
+    {
+        self.into_iter().collect()
+    }
+
     /// Returns `true` if the set contains no elements in common with `other`.
     ///
     /// # Examples
@@ -727,6 +1225,108 @@ where
         }
     }
 
+    /// Removes every element of `other` from `self`, in place.
+    ///
+    /// This is equivalent to replacing `self` with `self.difference(other)`,
+    /// but does not build an intermediate set. Whichever of the two sets is
+    /// smaller is the one iterated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut a: HashSet<i32> = [1, 2, 3].into_iter().collect();
+    /// let b: HashSet<i32> = [2, 3, 4].into_iter().collect();
+    ///
+    /// a.difference_update(&b);
+    /// assert_eq!(a.len(), 1);
+    /// assert!(a.contains(&1));
+    /// # }
+    /// ```
+    pub fn difference_update(
+        &mut self,
+        other: &HashSet<T, S>,
+    ) {
+        if other.len() < self.len() {
+            for value in other.iter() {
+                self.remove(value);
+            }
+        } else {
+            self.retain(|v| !other.contains(v));
+        }
+    }
+
+    /// Retains only the elements of `self` that are also in `other`, in
+    /// place.
+    ///
+    /// This is equivalent to replacing `self` with
+    /// `self.intersection(other)`, but does not build an intermediate set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut a: HashSet<i32> = [1, 2, 3].into_iter().collect();
+    /// let b: HashSet<i32> = [2, 3, 4].into_iter().collect();
+    ///
+    /// a.intersection_update(&b);
+    /// assert_eq!(a.len(), 2);
+    /// assert!(a.contains(&2));
+    /// assert!(a.contains(&3));
+    /// # }
+    /// ```
+    pub fn intersection_update(
+        &mut self,
+        other: &HashSet<T, S>,
+    ) {
+        if other.is_empty() {
+            self.clear();
+            return;
+        }
+        self.retain(|v| other.contains(v));
+    }
+
+    /// Replaces `self` with the symmetric difference of `self` and `other`,
+    /// in place.
+    ///
+    /// Elements of `other` already in `self` are removed, and the rest are
+    /// cloned into `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut a: HashSet<i32> = [1, 2, 3].into_iter().collect();
+    /// let b: HashSet<i32> = [2, 3, 4].into_iter().collect();
+    ///
+    /// a.symmetric_difference_update(&b);
+    /// assert_eq!(a.len(), 2);
+    /// assert!(a.contains(&1));
+    /// assert!(a.contains(&4));
+    /// # }
+    /// ```
+    pub fn symmetric_difference_update(
+        &mut self,
+        other: &HashSet<T, S>,
+    ) where
+        T: Clone,
+    {
+        for value in other.iter() {
+            if !self.remove(value) {
+                self.insert(value.clone());
+            }
+        }
+    }
+
     /// Retains only the elements specified by the predicate.
     ///
     /// In other words, remove all elements `e` for which `f(&e)` returns
@@ -759,6 +1359,39 @@ where
         self.table.retain(f, |k| self.hash_builder.hash_one(k));
     }
 
+    /// Returns an iterator over the values for which `pred` returns `false`.
+    ///
+    /// This yields exactly what [`retain`](Self::retain) with the negated
+    /// predicate would keep, without modifying the set. It is equivalent to
+    /// `iter().filter(|v| !pred(v))`, but skips empty slots in groups.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let set: HashSet<i32> = (1..=6).collect();
+    ///
+    /// let mut odd: Vec<_> = set.filter_out(|&x| x % 2 == 0).copied().collect();
+    /// odd.sort();
+    /// assert_eq!(odd, [1, 3, 5]);
+    /// assert_eq!(set.len(), 6);
+    /// # }
+    /// ```
+    pub fn filter_out<F>(
+        &self,
+        pred: F,
+    ) -> FilterOut<'_, T, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        FilterOut {
+            inner: self.table.filter_out(pred),
+        }
+    }
+
     /// Creates an iterator that removes and yields elements from the set for
     /// which the predicate returns `true`.
     ///
@@ -799,14 +1432,92 @@ where
                 .extract_if(f, Box::new(|k| self.hash_builder.hash_one(k))),
         }
     }
+
+    /// Removes the elements for which the predicate returns `true` and returns
+    /// them in ascending order.
+    ///
+    /// Unlike [`extract_if`](Self::extract_if), every matching element is
+    /// removed before this returns, and the removed elements are buffered and
+    /// sorted. This makes the output deterministic regardless of the hasher,
+    /// at the cost of an allocation and an `O(n log n)` sort of the removed
+    /// elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let mut set: HashSet<i32> = (1..=6).collect();
+    /// let extracted: Vec<_> = set.extract_if_sorted(|&mut x| x % 2 == 0).collect();
+    /// assert_eq!(extracted, [2, 4, 6]);
+    /// assert_eq!(set.len(), 3);
+    /// # }
+    /// ```
+    pub fn extract_if_sorted<F>(
+        &mut self,
+        f: F,
+    ) -> alloc::vec::IntoIter<T>
+    where
+        T: Ord,
+        F: FnMut(&mut T) -> bool,
+    {
+        let mut extracted: Vec<T> = self.extract_if(f).collect();
+        extracted.sort_unstable();
+        extracted.into_iter()
+    }
 }
 
-impl<T, S> HashSet<T, S>
-where
-    T: Hash + Eq,
-    S: BuildHasher + Default,
-{
-    /// Creates a new hash set using the default hasher builder.
+impl<T, S> HashSet<T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Creates a new hash set using the default hasher builder.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let set: HashSet<i32> = HashSet::new();
+    /// assert!(set.is_empty());
+    /// # }
+    /// ```
+    pub fn new() -> Self {
+        Self::with_hasher(S::default())
+    }
+
+    /// Creates a new hash set with the specified capacity using the default
+    /// hasher builder.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "std", feature = "foldhash"))]
+    /// # {
+    /// use hop_hash::HashSet;
+    ///
+    /// let set: HashSet<i32> = HashSet::with_capacity(100);
+    /// assert!(set.capacity() >= 100);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::default())
+    }
+
+    /// Creates a new hash set with the specified capacity using the default
+    /// hasher builder, using as little memory as possible for small
+    /// capacities.
+    ///
+    /// Capacities that fit in a single bucket (14 values at the default
+    /// density) allocate one bucket with no padding; larger ones behave like
+    /// [`with_capacity`](Self::with_capacity). See
+    /// [`HashTable::with_capacity_compact`] for details.
     ///
     /// # Examples
     ///
@@ -815,30 +1526,46 @@ where
     /// # {
     /// use hop_hash::HashSet;
     ///
-    /// let set: HashSet<i32> = HashSet::new();
-    /// assert!(set.is_empty());
+    /// let set: HashSet<u64> = HashSet::with_capacity_compact(8);
+    /// assert!(set.capacity_info().allocation_bytes < 1024);
     /// # }
     /// ```
-    pub fn new() -> Self {
-        Self::with_hasher(S::default())
+    #[track_caller]
+    pub fn with_capacity_compact(capacity: usize) -> Self {
+        Self::with_capacity_compact_and_hasher(capacity, S::default())
     }
+}
 
-    /// Creates a new hash set with the specified capacity using the default
-    /// hasher builder.
+#[cfg(any(feature = "std", feature = "foldhash"))]
+impl<T> HashSet<T, crate::FixedState>
+where
+    T: Hash + Eq,
+{
+    /// Creates a new hash set with a deterministic hasher.
+    ///
+    /// Sets created this way iterate in the same order whenever they receive
+    /// the same sequence of operations, which makes them suitable for
+    /// snapshot tests. See [`FixedState`](crate::FixedState) for why they
+    /// should not hold untrusted values.
     ///
     /// # Examples
     ///
     /// ```rust
     /// # #[cfg(any(feature = "std", feature = "foldhash"))]
     /// # {
-    /// use hop_hash::HashSet;
+    /// use hop_hash::hash_set::HashSet;
     ///
-    /// let set: HashSet<i32> = HashSet::with_capacity(100);
-    /// assert!(set.capacity() >= 100);
+    /// let mut a = HashSet::fixed();
+    /// let mut b = HashSet::fixed();
+    /// for x in 0..100 {
+    ///     a.insert(x);
+    ///     b.insert(x);
+    /// }
+    /// assert!(a.iter().eq(b.iter()));
     /// # }
     /// ```
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self::with_capacity_and_hasher(capacity, S::default())
+    pub fn fixed() -> Self {
+        Self::with_hasher(crate::FixedState::default())
     }
 }
 
@@ -852,6 +1579,87 @@ where
     }
 }
 
+/// A view into a single entry in the set, which may either be vacant or
+/// occupied.
+///
+/// This enum is constructed from the [`entry`] method on [`HashSet`].
+///
+/// [`entry`]: HashSet::entry
+pub enum Entry<'a, T> {
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, T>),
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, T>),
+}
+
+impl<'a, T> Entry<'a, T> {
+    /// Inserts the value if the entry is vacant and returns a reference to the
+    /// value stored in the set.
+    pub fn or_insert(self) -> &'a T {
+        match self {
+            Entry::Occupied(entry) => entry.into_ref(),
+            Entry::Vacant(entry) => entry.insert(),
+        }
+    }
+
+    /// Returns a reference to this entry's value.
+    ///
+    /// For a vacant entry this is the value that would be inserted.
+    pub fn get(&self) -> &T {
+        match self {
+            Entry::Occupied(entry) => entry.get(),
+            Entry::Vacant(entry) => entry.get(),
+        }
+    }
+}
+
+/// A view into a vacant entry in the set.
+pub struct VacantEntry<'a, T> {
+    entry: crate::hash_table::VacantEntry<'a, T>,
+    value: T,
+}
+
+impl<'a, T> VacantEntry<'a, T> {
+    /// Gets a reference to the value that would be inserted.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Take ownership of the value without inserting it.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Inserts the value into the set and returns a reference to it.
+    pub fn insert(self) -> &'a T {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(self.entry.metrics_sink(), MetricsEvent::Insert);
+        self.entry.insert(self.value)
+    }
+}
+
+/// A view into an occupied entry in the set.
+pub struct OccupiedEntry<'a, T> {
+    entry: crate::hash_table::OccupiedEntry<'a, T>,
+}
+
+impl<'a, T> OccupiedEntry<'a, T> {
+    /// Gets a reference to the value stored in the set.
+    pub fn get(&self) -> &T {
+        self.entry.get()
+    }
+
+    /// Converts the entry into a reference to the stored value.
+    pub fn into_ref(self) -> &'a T {
+        self.entry.into_mut()
+    }
+
+    /// Removes the value from the set and returns it.
+    pub fn remove(self) -> T {
+        self.entry.remove()
+    }
+}
+
 /// An iterator over the values of a `HashSet`.
 pub struct Iter<'a, T> {
     inner: crate::hash_table::Iter<'a, T>,
@@ -863,6 +1671,18 @@ impl<'a, T> Iterator for Iter<'a, T> {
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
 }
 
 /// A draining iterator over the values of a `HashSet`.
@@ -883,6 +1703,8 @@ impl<T> Iterator for Drain<'_, T> {
     }
 }
 
+impl<T> FusedIterator for Drain<'_, T> {}
+
 impl<T> Drop for Drain<'_, T> {
     fn drop(&mut self) {
         for _ in self {}
@@ -895,6 +1717,18 @@ impl<T> Iterator for IntoIter<T> {
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T> ExactSizeIterator for IntoIter<T> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
 }
 
 impl<T, S> IntoIterator for HashSet<T, S>
@@ -932,9 +1766,7 @@ where
 {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
         let mut set = HashSet::new();
-        for value in iter {
-            set.insert(value);
-        }
+        set.extend(iter);
         set
     }
 }
@@ -948,6 +1780,8 @@ where
         &mut self,
         iter: I,
     ) {
+        let iter = iter.into_iter();
+        self.reserve(extend_reserve_hint(iter.size_hint()));
         for value in iter {
             self.insert(value);
         }
@@ -979,6 +1813,20 @@ where
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Every remaining value of the first set is yielded, followed by at most
+        // every remaining value of the second.
+        let first = self.iter.len();
+        (first, first.checked_add(self.other_iter.len()))
+    }
+}
+
+impl<T, S> FusedIterator for Union<'_, T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
 }
 
 /// An iterator over the intersection of two sets.
@@ -1002,6 +1850,17 @@ where
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.iter.len()))
+    }
+}
+
+impl<T, S> FusedIterator for Intersection<'_, T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
 }
 
 /// An iterator over the difference of two sets.
@@ -1025,6 +1884,19 @@ where
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // At most `other.len()` of the remaining values can be filtered out.
+        let remaining = self.iter.len();
+        (remaining.saturating_sub(self.other.len()), Some(remaining))
+    }
+}
+
+impl<T, S> FusedIterator for Difference<'_, T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
 }
 
 /// An iterator over the symmetric difference of two sets.
@@ -1042,6 +1914,17 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, S> FusedIterator for SymmetricDifference<'_, T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
 }
 
 /// An iterator that removes and yields all values from the set that satisfy
@@ -1062,11 +1945,37 @@ where
     }
 }
 
+impl<T, F> FusedIterator for ExtractIf<'_, T, F> where F: FnMut(&mut T) -> bool {}
+
+/// An iterator over the values of a `HashSet` that do not satisfy a given
+/// predicate.
+pub struct FilterOut<'a, T, F> {
+    inner: crate::hash_table::FilterOut<'a, T, F>,
+}
+
+impl<'a, T, F> Iterator for FilterOut<'a, T, F>
+where
+    F: FnMut(&T) -> bool,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, F> FusedIterator for FilterOut<'_, T, F> where F: FnMut(&T) -> bool {}
+
 #[cfg(test)]
 mod tests {
+    use alloc::format;
+    use alloc::string::String;
     use alloc::string::ToString;
     use alloc::vec;
-    use alloc::vec::Vec;
     use core::hash::BuildHasher;
 
     use rand::TryRngCore;
@@ -1120,6 +2029,173 @@ mod tests {
         assert!(set2.is_empty());
     }
 
+    #[test]
+    fn test_iter_reports_exact_len() {
+        let mut set = HashSet::with_hasher(SipHashBuilder::default());
+        for i in 0..50 {
+            set.insert(i);
+        }
+
+        let mut iter = set.iter();
+        assert_eq!(iter.len(), 50);
+        iter.next();
+        assert_eq!(iter.len(), 49);
+
+        let mut into_iter = set.into_iter();
+        into_iter.next();
+        assert_eq!(into_iter.len(), 49);
+    }
+
+    #[test]
+    fn test_set_operation_size_hints() {
+        let a: HashSet<i32, SipHashBuilder> = (0..30).collect();
+        let b: HashSet<i32, SipHashBuilder> = (20..40).collect();
+
+        let union = a.union(&b);
+        assert_eq!(union.size_hint(), (30, Some(50)));
+        assert_eq!(union.count(), 40);
+
+        let intersection = a.intersection(&b);
+        assert_eq!(intersection.size_hint(), (0, Some(20)));
+        assert_eq!(intersection.count(), 10);
+
+        let difference = a.difference(&b);
+        assert_eq!(difference.size_hint(), (10, Some(30)));
+        assert_eq!(difference.count(), 20);
+
+        let symmetric = a.symmetric_difference(&b);
+        assert_eq!(symmetric.size_hint(), (10, Some(50)));
+        assert_eq!(symmetric.count(), 30);
+
+        // Bounds shrink as the iterators advance.
+        let mut difference = a.difference(&b);
+        let mut yielded = 0;
+        loop {
+            let (lower, upper) = difference.size_hint();
+            let remaining = a.difference(&b).count() - yielded;
+            assert!(lower <= remaining && remaining <= upper.unwrap());
+            if difference.next().is_none() {
+                break;
+            }
+            yielded += 1;
+        }
+    }
+
+    #[test]
+    fn test_entry_counts_new_and_duplicate_inserts() {
+        let mut set = HashSet::with_hasher(SipHashBuilder::default());
+        let input = [1, 2, 3, 2, 4, 1, 1, 5];
+
+        let (mut new, mut duplicate) = (0, 0);
+        for value in input {
+            match set.entry(value) {
+                Entry::Occupied(entry) => {
+                    assert_eq!(*entry.get(), value);
+                    duplicate += 1;
+                }
+                Entry::Vacant(entry) => {
+                    assert_eq!(*entry.insert(), value);
+                    new += 1;
+                }
+            }
+        }
+
+        assert_eq!((new, duplicate), (5, 3));
+        assert_eq!(set.len(), 5);
+
+        match set.entry(3) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 3),
+            Entry::Vacant(_) => panic!("3 should be present"),
+        }
+        assert!(!set.contains(&3));
+        assert_eq!(set.entry(6).get(), &6);
+        assert!(!set.contains(&6));
+    }
+
+    #[test]
+    fn test_clone_preserves_order_and_is_independent() {
+        let mut set = HashSet::with_hasher(SipHashBuilder::default());
+        for i in 0..500 {
+            set.insert(i);
+        }
+
+        let mut cloned = set.clone();
+        assert!(set.iter().eq(cloned.iter()));
+        assert!(set.iter().eq(set.try_clone().unwrap().iter()));
+
+        cloned.remove(&0);
+        cloned.insert(1000);
+        assert!(set.contains(&0));
+        assert!(!set.contains(&1000));
+        assert_eq!(set.len(), 500);
+    }
+
+    #[test]
+    fn test_extract_if_sorted_yields_ascending() {
+        let mut set: HashSet<u32, SipHashBuilder> = (0..1_000).collect();
+
+        let extracted: Vec<u32> = set.extract_if_sorted(|&mut x| x % 7 == 3).collect();
+        let expected: Vec<u32> = (0..1_000).filter(|x| x % 7 == 3).collect();
+        assert_eq!(extracted, expected);
+        assert!(extracted.windows(2).all(|w| w[0] < w[1]));
+
+        assert_eq!(set.len(), 1_000 - expected.len());
+        for x in 0..1_000 {
+            assert_eq!(set.contains(&x), x % 7 != 3);
+        }
+
+        assert_eq!(set.extract_if_sorted(|_| false).len(), 0);
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "foldhash"))]
+    fn test_fixed_sets_iterate_identically() {
+        let build = |mut set: HashSet<String, crate::FixedState>| {
+            for i in 0..1_000 {
+                set.insert(i.to_string());
+            }
+            set
+        };
+
+        // Two sets with separately constructed fixed hashers produce the same
+        // output, in the same order.
+        let a = build(HashSet::fixed());
+        let b = build(HashSet::with_hasher(crate::FixedState::default()));
+        assert_eq!(a.iter().collect::<Vec<_>>(), b.iter().collect::<Vec<_>>());
+        assert_eq!(format!("{a:?}"), format!("{b:?}"));
+    }
+
+    #[test]
+    fn test_insert_unique_rejects_duplicates() {
+        let mut set = HashSet::with_hasher(SipHashBuilder::default());
+        assert_eq!(set.insert_unique(String::from("x")), Ok(()));
+
+        let rejected = set.insert_unique(String::from("x"));
+        assert_eq!(rejected, Err(String::from("x")));
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.insert_unique(String::from("y")), Ok(()));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_reserve_overflow_panics() {
+        let mut set: HashSet<i32, SipHashBuilder> = HashSet::with_hasher(SipHashBuilder::default());
+        set.reserve(usize::MAX);
+    }
+
+    #[test]
+    fn test_try_reserve_reports_overflow() {
+        let mut set: HashSet<i32, SipHashBuilder> = HashSet::with_hasher(SipHashBuilder::default());
+        assert_eq!(
+            set.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(set.capacity(), 0);
+        assert_eq!(set.try_reserve(10), Ok(()));
+        assert!(set.capacity() >= 10);
+    }
+
     #[test]
     fn test_insert_and_contains() {
         let mut set = HashSet::with_hasher(SipHashBuilder::default());
@@ -1513,4 +2589,217 @@ mod tests {
         assert!(sym_diff.contains(&1));
         assert!(sym_diff.contains(&4));
     }
+
+    #[test]
+    fn test_difference_update() {
+        let mut a = HashSet::with_hasher(SipHashBuilder::default());
+        let mut b = HashSet::with_hasher(SipHashBuilder::default());
+        for i in 0..100 {
+            a.insert(i);
+        }
+        for i in 50..60 {
+            b.insert(i);
+        }
+
+        // `other` smaller than `self`.
+        a.difference_update(&b);
+        assert_eq!(a.len(), 90);
+        assert!((0..100).all(|i| a.contains(&i) != (50..60).contains(&i)));
+
+        // `other` larger than `self`.
+        let mut c = HashSet::with_hasher(SipHashBuilder::default());
+        c.insert(1);
+        c.insert(55);
+        c.difference_update(&a);
+        assert_eq!(c.len(), 1);
+        assert!(c.contains(&55));
+    }
+
+    #[test]
+    fn test_intersection_update() {
+        let mut a = HashSet::with_hasher(SipHashBuilder::default());
+        let mut b = HashSet::with_hasher(SipHashBuilder::default());
+        for i in 0..100 {
+            a.insert(i);
+        }
+        for i in 90..110 {
+            b.insert(i);
+        }
+
+        a.intersection_update(&b);
+        assert_eq!(a.len(), 10);
+        assert!((90..100).all(|i| a.contains(&i)));
+
+        a.intersection_update(&HashSet::with_hasher(SipHashBuilder::default()));
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_symmetric_difference_update() {
+        let mut a = HashSet::with_hasher(SipHashBuilder::default());
+        a.insert(1);
+        a.insert(2);
+        a.insert(3);
+
+        let mut b = HashSet::with_hasher(SipHashBuilder::default());
+        b.insert(2);
+        b.insert(3);
+        b.insert(4);
+
+        a.symmetric_difference_update(&b);
+        assert_eq!(a.len(), 2);
+        assert!(a.contains(&1));
+        assert!(a.contains(&4));
+    }
+
+    #[test]
+    fn test_get_or_insert_with_interns() {
+        let mut set: HashSet<Box<str>, _> = HashSet::with_hasher(SipHashBuilder::default());
+        let words = ["alpha", "beta", "alpha", "gamma", "beta", "alpha"];
+        let mut made = 0;
+        let mut first_seen = Vec::new();
+
+        for word in words {
+            let interned: *const str = &**set.get_or_insert_with(word, |w| {
+                made += 1;
+                Box::from(w)
+            });
+            match first_seen.iter().find(|(w, _)| *w == word) {
+                Some((_, ptr)) => assert!(core::ptr::eq(*ptr, interned)),
+                None => first_seen.push((word, interned)),
+            }
+        }
+
+        assert_eq!(made, 3);
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&Box::from("gamma")));
+    }
+
+    #[test]
+    fn test_try_insert_full_returns_value() {
+        let mut set = HashSet::with_capacity_and_hasher(32, SipHashBuilder::default());
+        // A full neighborhood can grow the set before it reaches capacity at the
+        // higher densities, so fill whatever capacity it ends up with.
+        let mut missing = 0u64;
+        while set.len() < set.capacity() {
+            set.insert(missing);
+            missing += 1;
+        }
+        let capacity = set.capacity();
+
+        assert_eq!(set.try_insert(0), Ok(false));
+        assert_eq!(
+            set.try_insert(missing),
+            Err((missing, TryEntryError::CapacityTooSmall))
+        );
+        assert_eq!(set.len(), capacity);
+        assert!(!set.contains(&missing));
+    }
+
+    #[test]
+    fn test_filter_out_matches_iter_filter() {
+        let mut rng = OsRng;
+        let mut sparse = HashSet::with_capacity_and_hasher(4096, SipHashBuilder::default());
+        let mut dense = HashSet::with_hasher(SipHashBuilder::default());
+        for _ in 0..50 {
+            sparse.insert(rng.try_next_u64().unwrap());
+        }
+        for _ in 0..5000 {
+            dense.insert(rng.try_next_u64().unwrap());
+        }
+
+        for set in [&sparse, &dense] {
+            let pred = |v: &u64| v.is_multiple_of(3);
+            let mut filtered: Vec<_> = set.filter_out(pred).copied().collect();
+            let mut expected: Vec<_> = set.iter().filter(|v| !pred(v)).copied().collect();
+            filtered.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(filtered, expected);
+
+            assert_eq!(set.filter_out(|_| false).count(), set.len());
+            assert_eq!(set.filter_out(|_| true).next(), None);
+        }
+    }
+
+    #[test]
+    fn test_tiny_values_are_not_padded() {
+        fn bytes_per_slot<T: Hash + Eq>(set: &HashSet<T, SipHashBuilder>) -> f64 {
+            let info = set.capacity_info();
+            info.allocation_bytes as f64 / info.slots as f64
+        }
+
+        let mut bytes = HashSet::with_hasher(SipHashBuilder::default());
+        let mut shorts = HashSet::with_hasher(SipHashBuilder::default());
+        let mut longs = HashSet::with_hasher(SipHashBuilder::default());
+        for i in 0..=u8::MAX {
+            bytes.insert(i);
+            shorts.insert(u16::from(i) * 7);
+            longs.insert(u64::from(i) * 7);
+        }
+
+        assert!(bytes_per_slot(&bytes) <= 3.0);
+        assert!(bytes_per_slot(&shorts) <= 4.0);
+        assert!(bytes_per_slot(&longs) <= 10.0);
+        assert!(
+            bytes.capacity_info().allocation_bytes < longs.capacity_info().allocation_bytes / 3
+        );
+    }
+
+    #[test]
+    fn test_sorted_conversions() {
+        let mut set = HashSet::with_hasher(SipHashBuilder::default());
+        for key in (0..1000u64).map(|i| i * 7919 % 10_000) {
+            set.insert(key);
+        }
+
+        let sorted: Vec<u64> = set.to_sorted_vec().into_iter().copied().collect();
+        assert_eq!(sorted.len(), set.len());
+        assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+
+        let btree = set.into_btree_set();
+        assert!(btree.into_iter().eq(sorted));
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn test_metrics_sink_counts_operations() {
+        use crate::metrics::tests::CountingSink;
+
+        static SINK: CountingSink = CountingSink::new();
+
+        let mut set = HashSet::with_hasher(SipHashBuilder::default());
+        set.set_metrics_sink(Some(&SINK));
+        let mut resizes = 0;
+        for i in 0..3000u64 {
+            let slots = set.slot_capacity();
+            match i % 3 {
+                0 => {
+                    set.insert(i);
+                }
+                1 => {
+                    set.entry(i).or_insert();
+                }
+                _ => {
+                    set.get_or_insert_with(&i, |&i| i);
+                }
+            }
+            if set.slot_capacity() != slots {
+                resizes += 1;
+            }
+        }
+        assert!(resizes > 1);
+        assert_eq!(SINK.counts(), [0, 3000, 0, resizes]);
+
+        set.get_or_insert_with(&0, |&i| i);
+        assert!(set.contains(&1));
+        assert_eq!(set.try_insert(2), Ok(false));
+        set.remove(&3);
+        assert_eq!(set.try_insert(3), Ok(true));
+        assert_eq!(SINK.counts(), [1, 3002, 1, resizes]);
+
+        let slots = set.slot_capacity();
+        set.reserve(100_000);
+        assert_ne!(set.slot_capacity(), slots);
+        assert_eq!(SINK.counts(), [1, 3002, 1, resizes + 1]);
+    }
 }
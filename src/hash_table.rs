@@ -67,6 +67,15 @@
 //! might indicate that it's safe to split out the allocations. I'm not sure if
 //! this would actually simplify the code much, though.
 //!
+//! The split layout is the one most likely to cost something for very large
+//! `V`, since a tag hit is followed by a jump into the far-away value region.
+//! The `interleaved-layout` feature stores `[ HopInfo | Buckets ]` instead,
+//! where each bucket is its 16 tags followed by its 16 values. The tags of a
+//! bucket stay contiguous and 16-byte aligned for the SIMD probes, but scans
+//! that cross buckets must start on a bucket boundary. In testing, `find_hit`
+//! on `LargeTestItem` was 2-16% slower with it up to 16k entries and only
+//! came out ahead (about 11%) at 32k, so it stays off by default.
+//!
 //! Sizes are always rounded up to the next power-of-two for the extent of the
 //! root buckets to allow for simple & masking operations to compute root
 //! buckets based on hashes. Using this over modulo has a significant
@@ -127,6 +136,7 @@
 
 use alloc::alloc::handle_alloc_error;
 use alloc::boxed::Box;
+use alloc::collections::VecDeque;
 use alloc::vec::Vec;
 use core::alloc::Layout;
 #[cfg(target_arch = "x86")]
@@ -136,11 +146,19 @@ use core::arch::x86_64::*;
 use core::error::Error;
 use core::fmt::Debug;
 use core::fmt::Display;
+use core::iter::FusedIterator;
 use core::mem::MaybeUninit;
 use core::ptr::NonNull;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
 
 use cfg_if::cfg_if;
 
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsEvent;
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsSink;
+
 /// Errors that can occur during a `try_entry` operation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TryEntryError {
@@ -166,6 +184,96 @@ impl Display for TryEntryError {
 
 impl Error for TryEntryError {}
 
+/// Errors that can occur when the table fails to allocate memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested capacity exceeds the maximum size of an allocation.
+    CapacityOverflow,
+    /// The allocator returned an error for the given layout.
+    AllocError {
+        /// The layout of the allocation request that failed.
+        layout: Layout,
+    },
+}
+
+impl Display for TryReserveError {
+    fn fmt(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => write!(f, "capacity overflow"),
+            TryReserveError::AllocError { layout } => {
+                write!(f, "memory allocation of {} bytes failed", layout.size())
+            }
+        }
+    }
+}
+
+impl Error for TryReserveError {}
+
+/// Errors that can occur when a fallible `rehash` is used to resize the
+/// table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryRehashError {
+    /// `rehash` returned `None` for a value in the table. The table was not
+    /// resized.
+    HashUnavailable,
+    /// Allocating the resized table failed. The table was not resized.
+    Reserve(TryReserveError),
+}
+
+impl Display for TryRehashError {
+    fn fmt(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        match self {
+            TryRehashError::HashUnavailable => write!(f, "a value could not be rehashed"),
+            TryRehashError::Reserve(e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl Error for TryRehashError {}
+
+impl From<TryReserveError> for TryRehashError {
+    fn from(e: TryReserveError) -> Self {
+        TryRehashError::Reserve(e)
+    }
+}
+
+impl TryReserveError {
+    /// Reports the error the way an infallible allocation path would: by
+    /// calling the allocation error handler or panicking on overflow.
+    #[cold]
+    #[track_caller]
+    fn handle(self) -> ! {
+        match self {
+            TryReserveError::CapacityOverflow => panic!("capacity overflow"),
+            TryReserveError::AllocError { layout } => handle_alloc_error(layout),
+        }
+    }
+
+    /// Like [`handle`](Self::handle), but names the requested capacity and
+    /// the size of `V` in the overflow message.
+    #[cold]
+    #[track_caller]
+    fn handle_for<V>(
+        self,
+        requested: usize,
+    ) -> ! {
+        match self {
+            TryReserveError::CapacityOverflow => panic!(
+                "hop_hash: capacity overflow: requested capacity {requested} for V of size {} \
+                 overflows allocation limits",
+                size_of::<V>()
+            ),
+            TryReserveError::AllocError { layout } => handle_alloc_error(layout),
+        }
+    }
+}
+
 cfg_if! {
     // Try to save someone if they are in a situation where multiple versions of the crate
     // specify eight-way, density-ninety-two, and density-ninety-seven.
@@ -182,6 +290,55 @@ cfg_if! {
     }
 }
 
+/// Picks how many elements to reserve up front when extending from an
+/// iterator with the given `size_hint`.
+///
+/// The upper bound is trusted when it is known and within twice the lower
+/// bound, which covers exact-size and lightly filtered iterators without
+/// over-allocating for something like a sparse `filter`.
+pub(crate) fn extend_reserve_hint((lower, upper): (usize, Option<usize>)) -> usize {
+    match upper {
+        Some(upper) if upper <= lower.saturating_mul(2) => upper,
+        _ => lower,
+    }
+}
+
+/// Picks how many elements to reserve up front when merging `incoming`
+/// values into a collection of `len` values.
+///
+/// Unless the collection is empty, roughly half of the incoming values are
+/// assumed to already exist, so overlapping merges don't over-allocate.
+pub(crate) fn merge_reserve_hint(
+    len: usize,
+    incoming: usize,
+) -> usize {
+    if len == 0 {
+        incoming
+    } else {
+        incoming.div_ceil(2)
+    }
+}
+
+/// How many slots ahead of the current position `Iter` and `Drain` prefetch.
+///
+/// On the `iteration_prefetch_distance` benchmark for `LargeTestItem`, 2 was
+/// the fastest from 1k to 8k entries, up to 1.9x faster than 1. At 16k and
+/// 32k entries the table is memory bound and 2, 4 and 8 are within noise of
+/// each other.
+const ITER_PREFETCH_DISTANCE: usize = 2;
+
+/// The largest growth factor for which resizing prefetches every destination
+/// of a migrated value.
+///
+/// Growing the root count by a factor of `n` gives each value `n` possible
+/// new root buckets. Past this factor, the destinations are spread too thin
+/// for prefetching them all to pay off, so migration skips the prefetch.
+const MAX_MIGRATION_PREFETCH_GROWTH: usize = 4;
+
+/// How many queries ahead `FindStream` prefetches, matching the distance the
+/// map's `get_many` uses.
+const FIND_STREAM_PREFETCH_DISTANCE: usize = 8;
+
 #[inline(always)]
 fn target_load_factor(capacity: usize) -> usize {
     (capacity as f32 * TARGET_LOAD) as usize
@@ -247,35 +404,53 @@ fn hashtag(tag: u64) -> u8 {
     (tag >> 57) as u8
 }
 
+/// Panics if `hash` does not match the tag a value was stored with.
+///
+/// Every path that calls `rehash` on a stored value runs this in debug builds,
+/// catching the common misuse of passing a `rehash` (or `eq`) that disagrees
+/// with the hash the value was originally inserted under. Only the 7 tag bits
+/// are compared, so this finds most, but not all, mismatches.
+#[cfg(debug_assertions)]
+#[track_caller]
+fn debug_check_rehash(
+    stored_tag: u8,
+    hash: u64,
+) {
+    assert!(
+        stored_tag == hashtag(hash),
+        "hash/eq inconsistency: `rehash` returned a different hash than the value was inserted \
+         with"
+    );
+}
+
 /// Search for a movable index in the bubble range
 ///
 /// # Safety
-/// - `values` must point to a slice of `MaybeUninit<V>` with length greater
-///   than or equal to `empty_idx`.
+/// - `table` must be allocated.
 /// - The range `[bubble_base, empty_idx)` must be initialized.
-/// - Caller must ensure `0 <= bubble_base < empty_idx <= values.len()`.
-/// - `max_root_mask` must match the table’s current mask; roots are
-///   `0..=max_root_mask` and map to absolute indices as `root*16`.
+/// - Caller must ensure `0 <= bubble_base < empty_idx <=
+///   table.slot_capacity()`.
+/// - Roots are `0..=table.max_root_mask` and map to absolute indices as
+///   `root*16`.
 #[inline(always)]
 unsafe fn find_next_movable_index<V>(
-    values: &[MaybeUninit<V>],
+    table: &HashTable<V>,
     bubble_base: usize,
     empty_idx: usize,
-    max_root_mask: usize,
     rehash: &dyn Fn(&V) -> u64,
 ) -> Option<(usize, u64)> {
     for idx in bubble_base..empty_idx {
         // SAFETY: The caller guarantees that `idx` is within `bubble_base..empty_idx`
-        // and that `empty_idx` is within the bounds of `values`, making
-        // `get_unchecked` safe. The caller also ensures that elements in this range
-        // are initialized, making `assume_init_ref` safe.
+        // and that `empty_idx` is within the bounds of `table`, making `value_ptr`
+        // safe. The caller also ensures that elements in this range are
+        // initialized, making `assume_init_ref` safe.
         // Using `wrapping_sub` because `empty_idx` is guaranteed to be
         // >= `hopmap_index` by the hopscotch algorithm invariant (empty slots are
         // always found forward from or at the root bucket position). The wrapping
         // behavior handles the algebraic calculation without overflow concerns.
         unsafe {
-            let hash = rehash(values.get_unchecked(idx).assume_init_ref());
-            let hopmap_index = (hash as usize & max_root_mask) * LANES;
+            let hash = rehash(table.value_ptr(idx).as_ref().assume_init_ref());
+            let hopmap_index = (hash as usize & table.max_root_mask) * LANES;
 
             let distance = empty_idx.wrapping_sub(hopmap_index);
             if distance < HOP_RANGE * LANES {
@@ -302,16 +477,50 @@ impl From<usize> for Capacity {
             // reading out OOB since we don't do wrapping, and computing the root buckets
             // relies on this being power-of-two for masking to work. Yes using & instead of
             // modulo makes a difference for performance.
-            value.next_power_of_two().checked_add(HOP_RANGE).unwrap()
+            match value
+                .checked_next_power_of_two()
+                .and_then(|value| value.checked_add(HOP_RANGE))
+            {
+                Some(base) => base,
+                None => TryReserveError::CapacityOverflow.handle(),
+            }
         };
         Capacity { base }
     }
 }
 
 impl Capacity {
+    /// Computes the smallest geometry that holds `required` elements at the
+    /// target load factor.
+    fn try_for_elements(required: usize) -> Result<Self, TryReserveError> {
+        let buckets = target_load_factor_inverse(required.div_ceil(LANES));
+        if buckets == 0 {
+            return Ok(Capacity { base: 0 });
+        }
+
+        buckets
+            .checked_next_power_of_two()
+            .and_then(|buckets| buckets.checked_add(HOP_RANGE))
+            .map(|base| Capacity { base })
+            .ok_or(TryReserveError::CapacityOverflow)
+    }
+
+    /// A single bucket with no `HOP_RANGE` padding, for
+    /// [`HashTable::with_capacity_compact`].
+    ///
+    /// Its only root bucket is its only bucket, so every neighborhood scan
+    /// stops at the end of the table instead of running into the padding.
+    fn compact() -> Self {
+        Capacity { base: 1 }
+    }
+
     #[inline(always)]
     fn max_root_mask(self) -> usize {
-        self.base.saturating_sub(HOP_RANGE).wrapping_sub(1)
+        if self.base == 0 {
+            usize::MAX
+        } else {
+            self.base.saturating_sub(HOP_RANGE + 1)
+        }
     }
 }
 
@@ -400,37 +609,262 @@ impl HopInfo {
     }
 }
 
+/// One bucket of the interleaved layout: its 16 tags, followed by the values
+/// they describe.
+///
+/// The alignment keeps every tag group 16-byte aligned for the SIMD probes,
+/// whatever the alignment of `V`.
+#[cfg(feature = "interleaved-layout")]
+#[repr(C, align(16))]
+struct InterleavedBucket<V> {
+    tags: [u8; LANES],
+    values: [MaybeUninit<V>; LANES],
+}
+
 #[derive(Debug, Clone, Copy)]
 struct DataLayout {
     layout: Layout,
     hopmap_offset: usize,
+    /// Offset of the tag array. The interleaved layout keeps each bucket's
+    /// tags inside the bucket instead.
+    #[cfg(not(feature = "interleaved-layout"))]
     tags_offset: usize,
 
     buckets_offset: usize,
+    /// Size of one `InterleavedBucket<V>`, the stride between tag groups.
+    #[cfg(feature = "interleaved-layout")]
+    bucket_size: usize,
+    slots: usize,
 }
 
 impl DataLayout {
     fn new<V>(capacity: Capacity) -> Self {
-        let hopmap_layout = Layout::array::<HopInfo>(capacity.max_root_mask().wrapping_add(1))
-            .expect("allocation size overflow");
-        let tags_layout =
-            Layout::array::<u8>(capacity.base * LANES).expect("allocation size overflow");
-        let buckets_layout = Layout::array::<MaybeUninit<V>>(capacity.base * LANES)
-            .expect("allocation size overflow");
-
-        let (layout, hopmap_offset) = Layout::new::<()>().extend(hopmap_layout).unwrap();
-        let (layout, tags_offset) = layout.extend(tags_layout).unwrap();
-        let (layout, buckets_offset) = layout.extend(buckets_layout).unwrap();
-
-        DataLayout {
-            layout,
-            hopmap_offset,
-            tags_offset,
-            buckets_offset,
+        Self::try_new::<V>(capacity).unwrap_or_else(|e| e.handle())
+    }
+
+    fn try_new<V>(capacity: Capacity) -> Result<Self, TryReserveError> {
+        let overflow = |_| TryReserveError::CapacityOverflow;
+        let slots = capacity
+            .base
+            .checked_mul(LANES)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        let hopmap_layout =
+            Layout::array::<HopInfo>(capacity.max_root_mask().wrapping_add(1)).map_err(overflow)?;
+
+        let (layout, hopmap_offset) = Layout::new::<()>()
+            .extend(hopmap_layout)
+            .map_err(overflow)?;
+
+        cfg_if! {
+            if #[cfg(feature = "interleaved-layout")] {
+                let buckets_layout =
+                    Layout::array::<InterleavedBucket<V>>(capacity.base).map_err(overflow)?;
+                let (layout, buckets_offset) = layout.extend(buckets_layout).map_err(overflow)?;
+
+                Ok(DataLayout {
+                    layout,
+                    hopmap_offset,
+                    buckets_offset,
+                    bucket_size: size_of::<InterleavedBucket<V>>(),
+                    slots,
+                })
+            } else {
+                let tags_layout = Layout::array::<u8>(slots).map_err(overflow)?;
+                let buckets_layout = Layout::array::<MaybeUninit<V>>(slots).map_err(overflow)?;
+                let (layout, tags_offset) = layout.extend(tags_layout).map_err(overflow)?;
+                let (layout, buckets_offset) = layout.extend(buckets_layout).map_err(overflow)?;
+
+                Ok(DataLayout {
+                    layout,
+                    hopmap_offset,
+                    tags_offset,
+                    buckets_offset,
+                    slots,
+                })
+            }
+        }
+    }
+
+    /// Allocates memory for this layout with the hopmap zeroed and every tag
+    /// marked empty. Zero-sized layouts get a dangling pointer.
+    fn try_allocate(&self) -> Result<NonNull<u8>, TryReserveError> {
+        if self.layout.size() == 0 {
+            return Ok(NonNull::dangling());
+        }
+
+        // SAFETY: We have validated that the layout size is non-zero, and
+        // `reset_metadata` only writes within the allocation it describes.
+        unsafe {
+            let raw_alloc = alloc::alloc::alloc(self.layout);
+            let Some(alloc) = NonNull::new(raw_alloc) else {
+                return Err(TryReserveError::AllocError {
+                    layout: self.layout,
+                });
+            };
+
+            self.reset_metadata(alloc);
+
+            Ok(alloc)
+        }
+    }
+
+    /// Returns a pointer to the tag of slot `index`.
+    ///
+    /// # Safety
+    ///
+    /// `alloc` must be a live allocation of this layout, and `index` must be
+    /// less than `self.slots`.
+    #[inline(always)]
+    unsafe fn tag_ptr(
+        &self,
+        alloc: NonNull<u8>,
+        index: usize,
+    ) -> NonNull<u8> {
+        cfg_if! {
+            if #[cfg(feature = "interleaved-layout")] {
+                // SAFETY: The caller guarantees `index` is a slot of this allocation, so
+                // `index / LANES` is one of its buckets, whose tags come first.
+                unsafe {
+                    alloc.add(
+                        self.buckets_offset + index / LANES * self.bucket_size + index % LANES,
+                    )
+                }
+            } else {
+                // SAFETY: The caller guarantees `index` is a slot of this allocation,
+                // and the tag array holds one byte per slot.
+                unsafe { alloc.add(self.tags_offset + index) }
+            }
+        }
+    }
+
+    /// Returns a pointer to the value slot `index`.
+    ///
+    /// # Safety
+    ///
+    /// `alloc` must be a live allocation of this layout for values of type
+    /// `V`, and `index` must be less than `self.slots`.
+    #[inline(always)]
+    unsafe fn value_ptr<V>(
+        &self,
+        alloc: NonNull<u8>,
+        index: usize,
+    ) -> NonNull<MaybeUninit<V>> {
+        cfg_if! {
+            if #[cfg(feature = "interleaved-layout")] {
+                // SAFETY: The caller guarantees `index` is a slot of this allocation, so
+                // `index / LANES` is one of its buckets and `index % LANES` one of that
+                // bucket's values.
+                unsafe {
+                    let bucket = alloc
+                        .add(self.buckets_offset)
+                        .cast::<InterleavedBucket<V>>()
+                        .add(index / LANES);
+                    NonNull::new_unchecked(&raw mut (*bucket.as_ptr()).values)
+                        .cast::<MaybeUninit<V>>()
+                        .add(index % LANES)
+                }
+            } else {
+                // SAFETY: The caller guarantees `index` is a slot of this allocation, and
+                // the value array holds one `V` per slot.
+                unsafe {
+                    alloc
+                        .add(self.buckets_offset)
+                        .cast::<MaybeUninit<V>>()
+                        .add(index)
+                }
+            }
+        }
+    }
+
+    /// Zeroes the hopmap and marks every tag empty.
+    ///
+    /// # Safety
+    ///
+    /// `alloc` must be a live allocation of this layout with a non-zero size.
+    unsafe fn reset_metadata(
+        &self,
+        alloc: NonNull<u8>,
+    ) {
+        // SAFETY: The hopmap occupies `hopmap_offset..buckets_offset` in both layouts
+        // (the split tag array starts right after it). The tags are written through
+        // `tag_ptr`, which stays within the allocation.
+        unsafe {
+            cfg_if! {
+                if #[cfg(feature = "interleaved-layout")] {
+                    core::ptr::write_bytes(
+                        alloc.add(self.hopmap_offset).as_ptr(),
+                        0x0,
+                        self.buckets_offset - self.hopmap_offset,
+                    );
+                    for base in (0..self.slots).step_by(LANES) {
+                        core::ptr::write_bytes(self.tag_ptr(alloc, base).as_ptr(), EMPTY, LANES);
+                    }
+                } else {
+                    core::ptr::write_bytes(
+                        alloc.add(self.hopmap_offset).as_ptr(),
+                        0x0,
+                        self.tags_offset - self.hopmap_offset,
+                    );
+                    core::ptr::write_bytes(self.tag_ptr(alloc, 0).as_ptr(), EMPTY, self.slots);
+                }
+            }
+        }
+    }
+
+    /// Copies the hopmap and tags of `src` into `dst`, leaving the values of
+    /// `dst` uninitialized.
+    ///
+    /// # Safety
+    ///
+    /// `src` and `dst` must be distinct live allocations of this layout with a
+    /// non-zero size.
+    unsafe fn copy_metadata(
+        &self,
+        src: NonNull<u8>,
+        dst: NonNull<u8>,
+    ) {
+        // SAFETY: Both allocations share this layout, so every range copied here is in
+        // bounds for both, and they do not overlap.
+        unsafe {
+            cfg_if! {
+                if #[cfg(feature = "interleaved-layout")] {
+                    core::ptr::copy_nonoverlapping(
+                        src.add(self.hopmap_offset).as_ptr(),
+                        dst.add(self.hopmap_offset).as_ptr(),
+                        self.buckets_offset - self.hopmap_offset,
+                    );
+                    for base in (0..self.slots).step_by(LANES) {
+                        core::ptr::copy_nonoverlapping(
+                            self.tag_ptr(src, base).as_ptr(),
+                            self.tag_ptr(dst, base).as_ptr(),
+                            LANES,
+                        );
+                    }
+                } else {
+                    core::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_ptr(), self.buckets_offset);
+                }
+            }
         }
     }
 }
 
+/// A snapshot of a table's capacity metrics.
+///
+/// Returned by [`HashTable::capacity_info`]. Unlike the `stats` feature's
+/// `DebugStats`, this is always available and computed in O(1).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CapacityInfo {
+    /// Maximum number of elements before a resize (see
+    /// [`HashTable::capacity`])
+    pub live: usize,
+    /// Total number of slots allocated (see [`HashTable::slot_capacity`])
+    pub slots: usize,
+    /// Size in bytes of the table's single allocation
+    pub allocation_bytes: usize,
+    /// Load factor (len / live), or 0.0 for an unallocated table
+    pub load_factor: f64,
+}
+
 /// Debug statistics for hash table analysis.
 #[cfg(feature = "stats")]
 #[derive(Debug, Clone)]
@@ -593,6 +1027,14 @@ impl ProbeHistogram {
     }
 }
 
+/// Source of the ids that tell tables apart in [`EntryHandle`]s.
+static NEXT_TABLE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns an id no other table created so far has.
+fn next_table_id() -> usize {
+    NEXT_TABLE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 /// A high-performance hash table using 16-way hopscotch hashing.
 ///
 /// `HashTable<V>` stores values of type `V` and provides fast insertion,
@@ -617,17 +1059,65 @@ pub struct HashTable<V> {
     max_pop: usize,
     max_root_mask: usize,
 
+    /// Identifies this table in the [`EntryHandle`]s it hands out.
+    id: usize,
+    /// Bumped on every structural change so handles and iterators can detect
+    /// that the table was modified since they were created.
+    generation: usize,
+
+    /// Number of values moved by bubbling since creation or the last
+    /// [`reset_bubble_count`](Self::reset_bubble_count).
+    #[cfg(feature = "stats")]
+    bubble_count: usize,
+
+    /// Receives a [`MetricsEvent::Resize`] every time the table reallocates to
+    /// grow, whichever operation triggered it.
+    #[cfg(feature = "metrics")]
+    metrics: Option<&'static dyn MetricsSink>,
+
+    /// How many values a single insert may bubble before the table resizes
+    /// instead. `usize::MAX` means no limit.
+    max_bubble_moves: usize,
+
     _phantom: core::marker::PhantomData<V>,
 }
 
+// SAFETY: The table uniquely owns its allocation, and the raw pointer is never
+// shared outside of it. Moving the table to another thread moves the values it
+// owns, which is sound when `V: Send`.
+unsafe impl<V: Send> Send for HashTable<V> {}
+
+// SAFETY: All shared access goes through `&self` methods which only read the
+// allocation and hand out `&V`, so sharing the table across threads is sound
+// when `V: Sync`. Mutation requires `&mut self`.
+unsafe impl<V: Sync> Sync for HashTable<V> {}
+
+/// Number of buckets shown by `{:?}` on a [`HashTable`].
+const DEBUG_BUCKETS: usize = 64;
+/// Number of buckets shown by `{:#?}` on a [`HashTable`].
+const DEBUG_BUCKETS_ALTERNATE: usize = 1024;
+
 impl<V> Debug for HashTable<V> {
     fn fmt(
         &self,
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
         use alloc::format;
+        use alloc::string::String;
         use alloc::string::ToString;
 
+        let limit = if f.alternate() {
+            DEBUG_BUCKETS_ALTERNATE
+        } else {
+            DEBUG_BUCKETS
+        };
+        let truncated = |total: usize, mut rows: Vec<String>| {
+            if total > limit {
+                rows.push(format!("… ({} more buckets)", total - limit));
+            }
+            rows
+        };
+
         if self.is_empty() {
             return f
                 .debug_struct("HashTable")
@@ -641,46 +1131,53 @@ impl<V> Debug for HashTable<V> {
         // check ensures that this code only runs on a non-empty (and therefore
         // initialized) table. An initialized table guarantees that `self.alloc`
         // points to a valid allocation matching `self.layout`, making the calls to
-        // `hopmap_ptr` and `tags_ptr` safe.
+        // `hopmap_ptr` and `tag_ptr` safe.
         unsafe {
+            let hopmap = self.hopmap_ptr().as_ref();
+            let slots = self.slot_capacity();
             f.debug_struct("HashTable")
                 .field(
                     "metadata",
-                    &self
-                        .hopmap_ptr()
-                        .as_ref()
-                        .iter()
-                        .map(|b| {
-                            let mut items = Vec::new();
-                            for i in 0..HOP_RANGE {
-                                if b.neighbors[i] != 0 {
-                                    items.push(format!("{i:02}x{:02}", b.neighbors[i]));
-                                } else {
-                                    items.push(".....".to_string());
+                    &truncated(
+                        hopmap.len(),
+                        hopmap
+                            .iter()
+                            .take(limit)
+                            .map(|b| {
+                                let mut items = Vec::new();
+                                for i in 0..HOP_RANGE {
+                                    if b.neighbors[i] != 0 {
+                                        items.push(format!("{i:02}x{:02}", b.neighbors[i]));
+                                    } else {
+                                        items.push(".....".to_string());
+                                    }
                                 }
-                            }
-                            items.join(", ")
-                        })
-                        .collect::<Vec<_>>(),
+                                items.join(", ")
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
                 )
                 .field(
                     "popmap",
-                    &self
-                        .tags_ptr()
-                        .as_ref()
-                        .chunks(LANES)
-                        .map(|w| {
-                            let mut items = Vec::new();
-                            for b in w {
-                                if *b == EMPTY {
-                                    items.push("..".to_string());
-                                } else {
-                                    items.push(format!("{:02x}", b));
+                    &truncated(
+                        slots / LANES,
+                        (0..slots)
+                            .step_by(LANES)
+                            .take(limit)
+                            .map(|base| {
+                                let mut items = Vec::new();
+                                for index in base..base + LANES {
+                                    let b = *self.tag_ptr(index).as_ref();
+                                    if b == EMPTY {
+                                        items.push("..".to_string());
+                                    } else {
+                                        items.push(format!("{:02x}", b));
+                                    }
                                 }
-                            }
-                            items.join(", ")
-                        })
-                        .collect::<Vec<_>>(),
+                                items.join(", ")
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
                 )
                 .field("populated", &self.populated)
                 .field("capacity", &self.max_pop)
@@ -694,61 +1191,87 @@ where
     V: Clone,
 {
     fn clone(&self) -> Self {
+        match self.try_clone() {
+            Ok(table) => table,
+            Err(TryReserveError::AllocError { layout }) => handle_alloc_error(layout),
+            // The clone reuses a layout that has already been allocated once.
+            Err(TryReserveError::CapacityOverflow) => unreachable!(),
+        }
+    }
+}
+
+impl<V> HashTable<V>
+where
+    V: Clone,
+{
+    /// Clones the table, returning an error instead of aborting if the
+    /// allocation fails.
+    ///
+    /// The clone has the same capacity and layout as `self`, so values are
+    /// cloned into place without being rehashed. If the allocation fails, no
+    /// values are cloned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::AllocError`] if the allocator fails to
+    /// provide memory for the new table.
+    pub fn try_clone(&self) -> Result<Self, TryReserveError> {
         let new_table = Self {
             layout: self.layout,
             alloc: if self.layout.layout.size() == 0 {
                 NonNull::dangling()
             } else {
                 // SAFETY: We have validated that the layout size is non-zero. The `alloc`
-                // function returns a valid pointer, and we handle allocation errors
-                // if it returns null.
+                // function returns a valid pointer, and we return an error if it returns
+                // null.
                 unsafe {
                     let raw_alloc = alloc::alloc::alloc(self.layout.layout);
-                    if raw_alloc.is_null() {
-                        handle_alloc_error(self.layout.layout);
-                    }
+                    let Some(new_alloc) = NonNull::new(raw_alloc) else {
+                        return Err(TryReserveError::AllocError {
+                            layout: self.layout.layout,
+                        });
+                    };
 
-                    core::ptr::copy_nonoverlapping(
-                        self.alloc.as_ptr(),
-                        raw_alloc,
-                        self.layout.buckets_offset,
-                    );
+                    self.layout.copy_metadata(self.alloc, new_alloc);
 
-                    NonNull::new_unchecked(raw_alloc)
+                    new_alloc
                 }
             },
             populated: self.populated,
             max_pop: self.max_pop,
             max_root_mask: self.max_root_mask,
+            id: next_table_id(),
+            generation: 0,
+            #[cfg(feature = "stats")]
+            bubble_count: 0,
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics,
+            max_bubble_moves: self.max_bubble_moves,
             _phantom: core::marker::PhantomData,
         };
 
         // SAFETY: The new table has the same capacity and layout as the source
         // table. We iterate through the tags, and for each occupied slot, we clone
         // the value. This is safe because:
-        // 1. `get_unchecked` is safe as we iterate up to `src_tags.len()`, which is
-        //    within the bounds of all allocated slices.
+        // 1. `tag_ptr` and `value_ptr` are safe as we iterate up to the slot count,
+        //    which both tables share.
         // 2. `assume_init_ref` is safe because a non-`EMPTY` tag guarantees that the
         //    corresponding bucket is initialized.
-        // 3. `write` to `dst_buckets` is safe because the destination is uninitialized
+        // 3. `write` to the new table is safe because the destination is uninitialized
         //    and within bounds.
         unsafe {
-            let src_buckets = self.buckets_ptr().as_ref();
-            let dst_buckets = new_table.buckets_ptr().as_mut();
-            let src_tags = self.tags_ptr().as_ref();
-
-            for i in 0..src_tags.len() {
-                let tag = *src_tags.get_unchecked(i);
-                if tag != EMPTY {
-                    dst_buckets
-                        .get_unchecked_mut(i)
-                        .write(src_buckets.get_unchecked(i).assume_init_ref().clone());
+            for i in 0..self.slot_capacity() {
+                if *self.tag_ptr(i).as_ref() != EMPTY {
+                    new_table
+                        .value_ptr(i)
+                        .as_mut()
+                        .write(self.value_ptr(i).as_ref().assume_init_ref().clone());
                 }
             }
 
             debug_assert!(new_table.populated == self.populated);
 
-            new_table
+            Ok(new_table)
         }
     }
 }
@@ -760,12 +1283,9 @@ impl<V> Drop for HashTable<V> {
         // deallocating.
         unsafe {
             if core::mem::needs_drop::<V>() && self.populated > 0 {
-                for (index, tag) in self.tags_ptr().as_ref().iter().enumerate() {
-                    if *tag != EMPTY {
-                        self.buckets_ptr()
-                            .as_mut()
-                            .get_unchecked_mut(index)
-                            .assume_init_drop();
+                for index in 0..self.slot_capacity() {
+                    if *self.tag_ptr(index).as_ref() != EMPTY {
+                        self.value_ptr(index).as_mut().assume_init_drop();
                     }
                 }
             }
@@ -782,31 +1302,46 @@ impl<V> HashTable<V> {
     ///
     /// The actual capacity may be larger than requested due to the bucket-based
     /// organization.
+    #[track_caller]
     pub fn with_capacity(capacity: usize) -> Self {
-        let capacity: Capacity = target_load_factor_inverse(capacity.div_ceil(LANES)).into();
-
-        let layout = DataLayout::new::<V>(capacity);
-        let alloc = if layout.layout.size() == 0 {
-            NonNull::dangling()
-        } else {
-            // SAFETY: We have validated that the layout size is non-zero. The `alloc`
-            // function returns a valid pointer, and we handle allocation errors
-            // if it returns null.
-            unsafe {
-                let raw_alloc = alloc::alloc::alloc(layout.layout);
-                if raw_alloc.is_null() {
-                    handle_alloc_error(layout.layout);
-                }
-
-                core::ptr::write_bytes(raw_alloc, 0x0, layout.tags_offset);
-                core::ptr::write_bytes(
-                    raw_alloc.add(layout.tags_offset),
-                    EMPTY,
-                    layout.buckets_offset - layout.tags_offset,
-                );
+        Self::with_geometry(capacity, Capacity::try_for_elements(capacity))
+    }
 
-                NonNull::new_unchecked(raw_alloc)
-            }
+    /// Creates a new hash table with the specified capacity, using as little
+    /// memory as possible for small capacities.
+    ///
+    /// A capacity that fits in a single bucket of 16 slots at the target load
+    /// factor (14 values, or 15 with `density-ninety-seven`) gets a table of
+    /// exactly that one bucket, without the `HOP_RANGE` padding every other
+    /// table carries. Every lookup then scans the same 16 tags. Larger
+    /// capacities, and zero, behave like
+    /// [`with_capacity`](Self::with_capacity).
+    ///
+    /// Growing, or reserving past the capacity, moves the table to the
+    /// regular layout.
+    #[track_caller]
+    pub fn with_capacity_compact(capacity: usize) -> Self {
+        if capacity == 0 || capacity > target_load_factor(LANES) {
+            return Self::with_capacity(capacity);
+        }
+
+        Self::with_geometry(capacity, Ok(Capacity::compact()))
+    }
+
+    /// Allocates a table of `capacity`, reporting failures against
+    /// `requested`.
+    #[track_caller]
+    fn with_geometry(
+        requested: usize,
+        capacity: Result<Capacity, TryReserveError>,
+    ) -> Self {
+        let allocated = capacity.and_then(|capacity| {
+            let layout = DataLayout::try_new::<V>(capacity)?;
+            Ok((capacity, layout, layout.try_allocate()?))
+        });
+        let (capacity, layout, alloc) = match allocated {
+            Ok(allocated) => allocated,
+            Err(e) => e.handle_for::<V>(requested),
         };
 
         Self {
@@ -815,11 +1350,24 @@ impl<V> HashTable<V> {
             populated: 0,
             max_pop: target_load_factor(capacity.base * LANES),
             max_root_mask: capacity.max_root_mask(),
+            id: next_table_id(),
+            generation: 0,
+            #[cfg(feature = "stats")]
+            bubble_count: 0,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            max_bubble_moves: usize::MAX,
             _phantom: core::marker::PhantomData,
         }
     }
 
     fn hopmap_ptr(&self) -> NonNull<[HopInfo]> {
+        if self.layout.layout.size() == 0 {
+            // `alloc` is only byte-aligned here, so use a pointer that is aligned for
+            // `HopInfo` to keep the empty slice valid.
+            return NonNull::slice_from_raw_parts(NonNull::dangling(), 0);
+        }
+
         // SAFETY: This is safe because `self.alloc` is guaranteed to point to a
         // valid allocation with a layout described by `self.layout`. The offset
         // `self.layout.hopmap_offset` and the length `self.max_root_mask + 1` are
@@ -833,40 +1381,46 @@ impl<V> HashTable<V> {
         }
     }
 
-    fn buckets_ptr(&self) -> NonNull<[MaybeUninit<V>]> {
-        // SAFETY: This is safe because `self.alloc` is guaranteed to point to a
-        // valid allocation with a layout described by `self.layout`. The offset
-        // `self.layout.buckets_offset` and the calculated length are derived from
-        // the capacity and are guaranteed to be within the bounds of the allocated
-        // memory block.
-        unsafe {
-            NonNull::slice_from_raw_parts(
-                self.alloc.add(self.layout.buckets_offset).cast(),
-                if self.layout.layout.size() == 0 {
-                    0
-                } else {
-                    (self.max_root_mask.wrapping_add(1) + HOP_RANGE) * LANES
-                },
-            )
-        }
+    /// Returns a pointer to the value slot at `index`.
+    ///
+    /// # Safety
+    ///
+    /// The table must be allocated, and `index` must be less than
+    /// [`slot_capacity`](Self::slot_capacity).
+    #[inline(always)]
+    unsafe fn value_ptr(
+        &self,
+        index: usize,
+    ) -> NonNull<MaybeUninit<V>> {
+        // SAFETY: `self.alloc` is a live allocation described by `self.layout`, and
+        // the caller guarantees `index` is one of its slots.
+        unsafe { self.layout.value_ptr(self.alloc, index) }
     }
 
-    fn tags_ptr(&self) -> NonNull<[u8]> {
-        // SAFETY: This is safe because `self.alloc` is guaranteed to point to a
-        // valid allocation with a layout described by `self.layout`. The offset
-        // `self.layout.tags_offset` and the calculated length are derived from
-        // the capacity and are guaranteed to be within the bounds of the allocated
-        // memory block.
-        unsafe {
-            NonNull::slice_from_raw_parts(
-                self.alloc.add(self.layout.tags_offset).cast(),
-                if self.layout.layout.size() == 0 {
-                    0
-                } else {
-                    (self.max_root_mask.wrapping_add(1) + HOP_RANGE) * LANES
-                },
-            )
-        }
+    /// Returns a pointer to the tag of the slot at `index`.
+    ///
+    /// Slots `base..base + LANES` for a `base` that is a multiple of `LANES`
+    /// always have their tags stored contiguously, so the pointer for such a
+    /// `base` can be used to load the whole group at once.
+    ///
+    /// # Safety
+    ///
+    /// The table must be allocated, and `index` must be less than
+    /// [`slot_capacity`](Self::slot_capacity).
+    #[inline(always)]
+    unsafe fn tag_ptr(
+        &self,
+        index: usize,
+    ) -> NonNull<u8> {
+        // SAFETY: `self.alloc` is a live allocation described by `self.layout`, and
+        // the caller guarantees `index` is one of its slots.
+        unsafe { self.layout.tag_ptr(self.alloc, index) }
+    }
+
+    /// Records a structural change to the table, invalidating its handles.
+    #[inline(always)]
+    fn note_modified(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
     }
 
     /// Returns an iterator over all values in the table.
@@ -874,9 +1428,22 @@ impl<V> HashTable<V> {
     /// The iterator yields `&V` references in an arbitrary order.
     /// The iteration order is not specified and may change between versions.
     pub fn iter(&self) -> Iter<'_, V> {
+        self.iter_with_prefetch_distance()
+    }
+
+    /// Returns an iterator over all values in the table that prefetches
+    /// `DISTANCE` slots ahead instead of the default.
+    ///
+    /// This is for benchmarking the prefetch distance; use
+    /// [`iter`](Self::iter) otherwise.
+    #[doc(hidden)]
+    pub fn iter_with_prefetch_distance<const DISTANCE: usize>(&self) -> Iter<'_, V, DISTANCE> {
         Iter {
             table: self,
             bucket_index: 0,
+            remaining: self.populated,
+            #[cfg(debug_assertions)]
+            generation: self.generation,
         }
     }
 
@@ -885,33 +1452,167 @@ impl<V> HashTable<V> {
     /// The iterator yields `&mut V` references in an arbitrary order.
     /// The iteration order is not specified and may change between versions.
     pub fn iter_mut(&mut self) -> IterMut<'_, V> {
-        // SAFETY: We have ensured that `self` is a valid mutable reference to
-        // the hash table. The `IterMut` struct will only yield mutable references
-        // to values within the table, and the lifetime is tied to `&mut self`,
-        // ensuring no other mutable references can exist simultaneously.
-        unsafe {
-            IterMut {
-                tags: self.tags_ptr().as_ref(),
-                values: self.buckets_ptr().as_mut(),
-            }
+        IterMut {
+            slots: OccupiedSlots::new(self.populated),
+            table: self,
         }
     }
 
-    /// Returns an iterator that removes and yields all values from the table.
+    /// Returns an iterator over the table's values grouped by root bucket.
     ///
-    /// After calling `drain()`, the table will be empty. The iterator yields
-    /// owned values in an arbitrary order.
+    /// For every root bucket that has at least one value, this yields the
+    /// bucket index (as returned by [`bucket_index`](Self::bucket_index)) and
+    /// an iterator over the values whose root is that bucket. Buckets are
+    /// visited in ascending order and every value is yielded exactly once.
     ///
-    /// Calling `mem::forget` on the iterator will leak all unyielded values in
-    /// the table without dropping them. This will cause memory to be leaked
-    /// _even if the type does not allocate heap memory_ as the iterator
-    /// allocates memory to track its internal state.
-    pub fn drain(&mut self) -> Drain<'_, V> {
-        let total_slots = if self.layout.layout.size() == 0 {
-            0
-        } else {
-            (self.max_root_mask.wrapping_add(1) + HOP_RANGE) * LANES
-        };
+    /// A bucket's values all live in its neighborhood, so processing them
+    /// together keeps accesses within a few adjacent cache lines. The
+    /// neighborhood is shared with other roots, so `rehash` is used to pick
+    /// out the values that belong to each bucket.
+    ///
+    /// # Arguments
+    ///
+    /// * `rehash` - A closure that computes the hash for a value
+    pub fn iter_by_bucket<'a>(
+        &'a self,
+        rehash: &'a dyn Fn(&V) -> u64,
+    ) -> BucketIter<'a, V> {
+        BucketIter {
+            table: self,
+            rehash,
+            root: 0,
+            remaining: self.populated,
+        }
+    }
+
+    /// Returns a parallel iterator over mutable references to all values in
+    /// the table.
+    ///
+    /// The slot range is split into disjoint chunks, each owning a contiguous
+    /// part of the value array, so no two threads ever see the same value.
+    /// Mutating a value in place does not change its placement.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter_mut(&mut self) -> impl rayon::iter::ParallelIterator<Item = &mut V> + '_
+    where
+        V: Send,
+    {
+        use rayon::prelude::*;
+
+        let slots = self.slot_capacity();
+        cfg_if! {
+            if #[cfg(feature = "interleaved-layout")] {
+                let buckets: &mut [InterleavedBucket<V>] = if slots == 0 {
+                    &mut []
+                } else {
+                    // SAFETY: A non-empty layout holds `slots / LANES` buckets starting at
+                    // `buckets_offset`. We hold `&mut self` for the lifetime of the iterator,
+                    // so nothing else can access them while it is alive.
+                    unsafe {
+                        core::slice::from_raw_parts_mut(
+                            self.alloc.add(self.layout.buckets_offset).cast().as_ptr(),
+                            slots / LANES,
+                        )
+                    }
+                };
+                buckets.par_iter_mut().flat_map_iter(|bucket| {
+                    bucket.tags.iter().zip(bucket.values.iter_mut()).filter_map(|(&tag, value)| {
+                        // SAFETY: A non-`EMPTY` tag guarantees the slot holds an initialized
+                        // value.
+                        (tag != EMPTY).then(|| unsafe { value.assume_init_mut() })
+                    })
+                })
+            } else {
+                let (tags, values): (&[u8], &mut [MaybeUninit<V>]) = if slots == 0 {
+                    (&[], &mut [])
+                } else {
+                    // SAFETY: A non-empty layout holds `slots` tags and `slots` values in two
+                    // disjoint arrays. We hold `&mut self` for the lifetime of the iterator, so
+                    // nothing else can access them while it is alive. The tags are only read,
+                    // and every value slot is handed out at most once by `par_iter_mut`.
+                    unsafe {
+                        (
+                            core::slice::from_raw_parts(self.tag_ptr(0).as_ptr(), slots),
+                            core::slice::from_raw_parts_mut(self.value_ptr(0).as_ptr(), slots),
+                        )
+                    }
+                };
+                tags.par_iter()
+                    .zip(values.par_iter_mut())
+                    .filter_map(|(&tag, value)| {
+                        // SAFETY: A non-`EMPTY` tag guarantees the slot holds an initialized
+                        // value.
+                        (tag != EMPTY).then(|| unsafe { value.assume_init_mut() })
+                    })
+            }
+        }
+    }
+
+    /// Calls `f` on every value in the table.
+    ///
+    /// This visits values in the same order as [`iter`](Self::iter), but scans
+    /// the tags 16 slots at a time and skips empty groups without any
+    /// per-slot iterator state, which makes it faster for sparse tables.
+    pub fn for_each(
+        &self,
+        mut f: impl FnMut(&V),
+    ) {
+        let mut slots = OccupiedSlots::new(self.populated);
+        // SAFETY: The scan was started for `self`, which `f` cannot modify.
+        while let Some(index) = unsafe { slots.next(self) } {
+            // SAFETY: `index` is within bounds, and its tag marks it as occupied, so
+            // the bucket is initialized.
+            unsafe {
+                f(self.value_ptr(index).as_ref().assume_init_ref());
+            }
+        }
+    }
+
+    /// Calls `f` on a mutable reference to every value in the table.
+    ///
+    /// This is the mutable counterpart to [`for_each`](Self::for_each).
+    pub fn for_each_mut(
+        &mut self,
+        mut f: impl FnMut(&mut V),
+    ) {
+        let mut slots = OccupiedSlots::new(self.populated);
+        // SAFETY: The scan was started for `self`, and `f` only sees values, so it
+        // cannot change which slots are occupied.
+        while let Some(index) = unsafe { slots.next(self) } {
+            // SAFETY: `index` is within bounds, and its tag marks it as occupied, so
+            // the bucket is initialized. We hold `&mut self`, so no other reference
+            // to the value exists.
+            unsafe {
+                f(self.value_ptr(index).as_mut().assume_init_mut());
+            }
+        }
+    }
+
+    /// Returns an iterator that removes and yields all values from the table.
+    ///
+    /// After calling `drain()`, the table will be empty. The iterator yields
+    /// owned values in an arbitrary order.
+    ///
+    /// Calling `mem::forget` on the iterator will leak all unyielded values in
+    /// the table without dropping them. This will cause memory to be leaked
+    /// _even if the type does not allocate heap memory_ as the iterator
+    /// allocates memory to track its internal state.
+    pub fn drain(&mut self) -> Drain<'_, V> {
+        self.drain_with(DrainPolicy::KeepCapacity)
+    }
+
+    /// Returns an iterator that removes and yields all values from the table,
+    /// applying `policy` to the allocation once the iterator is dropped.
+    ///
+    /// With [`DrainPolicy::KeepCapacity`] this is the same as
+    /// [`drain`](Self::drain). With [`DrainPolicy::ShrinkAfter`] the table
+    /// releases its allocation when the iterator is dropped, as if
+    /// [`shrink_to_fit`](Self::shrink_to_fit) had been called on the empty
+    /// table.
+    pub fn drain_with(
+        &mut self,
+        policy: DrainPolicy,
+    ) -> Drain<'_, V> {
+        let total_slots = self.slot_capacity();
 
         if total_slots == 0 {
             return Drain {
@@ -919,41 +1620,88 @@ impl<V> HashTable<V> {
                 occupied: Box::new([]),
                 table: self,
                 bucket_index: 0,
+                remaining: 0,
+                policy,
             };
         }
 
-        let old_populated = self.tags_ptr();
-        let mut occupied = Box::new_uninit_slice(old_populated.len());
+        let mut occupied = Box::<[u8]>::new_uninit_slice(total_slots);
 
-        // SAFETY: We have ensured that `old_populated` and `occupied` point to
-        // valid memory regions of the same length. We copy the tags from
-        // `old_populated` to `occupied`, then zero out the hopmap and mark all buckets
-        // as empty so we don't double-drop. Finally, we assume that `occupied`
-        // is initialized since we just copied data into it.
+        // SAFETY: The table is allocated, and `occupied` has one byte per slot. We copy
+        // the tags into `occupied` a group at a time, since each group of `LANES` tags
+        // is contiguous, then zero out the hopmap and mark all buckets as empty so we
+        // don't double-drop. Finally, we assume that `occupied` is initialized since
+        // we just copied data into every byte of it.
         let occupied = unsafe {
-            core::ptr::copy_nonoverlapping(
-                old_populated.as_ref().as_ptr(),
-                occupied.as_mut_ptr().cast(),
-                old_populated.len(),
-            );
+            for base in (0..total_slots).step_by(LANES) {
+                core::ptr::copy_nonoverlapping(
+                    self.tag_ptr(base).as_ptr(),
+                    occupied.as_mut_ptr().add(base).cast(),
+                    LANES,
+                );
+            }
 
-            core::ptr::write_bytes(self.alloc.as_ptr(), 0x0, self.layout.tags_offset);
-            core::ptr::write_bytes(
-                self.alloc.as_ptr().add(self.layout.tags_offset),
-                EMPTY,
-                self.layout.buckets_offset - self.layout.tags_offset,
-            );
+            self.layout.reset_metadata(self.alloc);
 
             occupied.assume_init()
         };
 
+        let remaining = self.populated;
         self.populated = 0;
+        self.note_modified();
 
         Drain {
             total_slots,
             occupied,
             table: self,
             bucket_index: 0,
+            remaining,
+            policy,
+        }
+    }
+
+    /// Returns an iterator that removes every value from the table and yields
+    /// the result of applying `f` to it.
+    ///
+    /// This behaves exactly like [`drain`](HashTable::drain): the table is
+    /// emptied up front, so forgetting or dropping the iterator early never
+    /// double-drops a value. Values that are not yielded are dropped without
+    /// being passed to `f`.
+    pub fn drain_map<W, F>(
+        &mut self,
+        f: F,
+    ) -> DrainMap<'_, V, F>
+    where
+        F: FnMut(V) -> W,
+    {
+        DrainMap {
+            inner: self.drain(),
+            f,
+        }
+    }
+
+    /// Returns an iterator that removes every value from the table and yields
+    /// it together with its hash.
+    ///
+    /// This behaves exactly like [`drain`](HashTable::drain), but calls
+    /// `rehash` on each value as it is yielded. It suits moving values into
+    /// other tables, e.g. repartitioning across shards, where the hash is
+    /// needed again straight away. Values that are not yielded are dropped
+    /// without being rehashed.
+    ///
+    /// # Arguments
+    ///
+    /// * `rehash` - A closure that computes the hash for a value
+    pub fn drain_hashed<R>(
+        &mut self,
+        rehash: R,
+    ) -> DrainHashed<'_, V, R>
+    where
+        R: Fn(&V) -> u64,
+    {
+        DrainHashed {
+            inner: self.drain(),
+            rehash,
         }
     }
 
@@ -972,32 +1720,65 @@ impl<V> HashTable<V> {
     /// This operation preserves the table's allocated capacity. All values are
     /// properly dropped if they implement `Drop`. After calling `clear()`, the
     /// table will be empty but maintain its current capacity.
+    ///
+    /// For types without drop glue this is just two `memset`s over the
+    /// metadata, and never touches the values. Clearing a table that is
+    /// already empty does not touch memory at all, so it is O(1) regardless
+    /// of capacity.
     pub fn clear(&mut self) {
-        // SAFETY: We have ensured that values are properly initialized before being
-        // dropped.
-        unsafe {
-            if core::mem::needs_drop::<V>() && self.populated > 0 {
-                for (index, tag) in self.tags_ptr().as_ref().iter().enumerate() {
-                    if *tag != EMPTY {
-                        self.buckets_ptr()
-                            .as_mut()
-                            .get_unchecked_mut(index)
-                            .assume_init_drop();
-                    }
-                }
-            }
+        // Removals keep the metadata in sync, so an empty table's hopmap and tags
+        // are already clean.
+        if self.populated == 0 {
+            return;
+        }
+
+        // `needs_drop` is a constant, so for types without drop glue this branch is
+        // compiled out entirely.
+        if core::mem::needs_drop::<V>() {
+            // SAFETY: `for_each_mut` only yields initialized values, and we reset every
+            // tag below so none of them will be observed or dropped again.
+            self.for_each_mut(|value| unsafe { core::ptr::drop_in_place(value) });
+        }
 
+        // SAFETY: The allocation is valid for the offsets described by `self.layout`.
+        unsafe {
             if self.layout.layout.size() != 0 {
-                core::ptr::write_bytes(self.alloc.as_ptr(), 0x0, self.layout.tags_offset);
-                core::ptr::write_bytes(
-                    self.alloc.as_ptr().add(self.layout.tags_offset),
-                    EMPTY,
-                    self.layout.buckets_offset - self.layout.tags_offset,
-                );
+                self.layout.reset_metadata(self.alloc);
             }
         }
 
         self.populated = 0;
+        self.note_modified();
+    }
+
+    /// Removes all elements from the table and shrinks its allocation down to
+    /// the geometry needed for `floor` elements.
+    ///
+    /// This is [`clear`](Self::clear) followed by a shrink, for tables that
+    /// briefly held far more elements than they usually do. A `floor` of zero
+    /// frees the allocation entirely. The allocation is kept if it is already
+    /// no larger than `floor` needs; this never grows the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `floor` - The number of elements the table should still have room for
+    pub fn clear_to_capacity(
+        &mut self,
+        floor: usize,
+    ) {
+        self.clear();
+        if floor == 0 {
+            self.release_allocation();
+            return;
+        }
+
+        match Capacity::try_for_elements(floor) {
+            Ok(new_capacity) if new_capacity.base * LANES < self.slot_capacity() => {
+                // The table is empty, so nothing is rehashed.
+                self.do_resize_rehash(new_capacity, &|_| unreachable!());
+            }
+            _ => {}
+        }
     }
 
     /// Shrinks the capacity of the hash table as much as possible.
@@ -1007,31 +1788,141 @@ impl<V> HashTable<V> {
     /// memory.
     ///
     /// If the table is empty, it will be completely deallocated and reset to
-    /// a zero-capacity state.
+    /// a zero-capacity state. If the table is already at the smallest geometry
+    /// that fits its elements, this does nothing and keeps the allocation.
     pub fn shrink_to_fit(
         &mut self,
         rehash: impl Fn(&V) -> u64,
     ) {
         if self.populated == 0 {
-            if self.layout.layout.size() != 0 {
-                // SAFETY: We have ensured that the allocation is valid before
-                // deallocating.
-                unsafe {
-                    alloc::alloc::dealloc(self.alloc.as_ptr(), self.layout.layout);
-                }
-                self.alloc = NonNull::dangling();
-                let new_capacity: Capacity = 0.into();
-                self.layout = DataLayout::new::<V>(new_capacity);
-                self.max_root_mask = new_capacity.max_root_mask();
-                self.max_pop = 0;
-            }
+            self.release_allocation();
             return;
         }
 
-        let required = self.populated;
+        self.shrink_to_hold(self.populated, &rehash);
+    }
+
+    /// Shrinks the table to the smallest geometry that holds `required`
+    /// values, if that is smaller than the current one.
+    fn shrink_to_hold(
+        &mut self,
+        required: usize,
+        rehash: &dyn Fn(&V) -> u64,
+    ) {
         let new_capacity: Capacity = target_load_factor_inverse(required.div_ceil(LANES)).into();
-        if new_capacity.max_root_mask() < self.max_root_mask {
-            self.do_resize_rehash(new_capacity, &rehash);
+        if new_capacity.max_root_mask() >= self.max_root_mask {
+            // The table is already at (or below) its minimal geometry, so keep the
+            // current allocation rather than rehashing into an identical one.
+            return;
+        }
+
+        self.do_resize_rehash(new_capacity, rehash);
+    }
+
+    /// Shrinks the table if fewer than `min_load * capacity()` values are
+    /// stored.
+    ///
+    /// The table shrinks to a geometry with room for twice its current
+    /// length rather than to fit, so a single insert after shrinking does not
+    /// grow it again. With `min_load` at most 0.25, the load after shrinking
+    /// stays above `min_load`, so a single removal does not shrink it again
+    /// either. An empty table keeps the smallest allocated geometry instead
+    /// of freeing it, for the same reason.
+    pub(crate) fn shrink_if_below(
+        &mut self,
+        min_load: f64,
+        rehash: impl Fn(&V) -> u64,
+    ) {
+        if (self.populated as f64) >= min_load * self.max_pop as f64 {
+            return;
+        }
+
+        self.shrink_to_hold(self.populated.saturating_mul(2).max(1), &rehash);
+    }
+
+    /// Lowers the table's [`capacity`](Self::capacity) to `target` without
+    /// reallocating.
+    ///
+    /// The capacity never drops below the current length, and this never
+    /// raises it. The allocation is kept as is until
+    /// [`commit_shrink`](Self::commit_shrink) is called. If the table grows
+    /// past the lowered capacity first, it takes back the capacity of its
+    /// current allocation instead of reallocating. This suits tables of large
+    /// values that shrink and grow again, where a resize is costly.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The capacity to lower the table to
+    pub fn soft_shrink(
+        &mut self,
+        target: usize,
+    ) {
+        self.max_pop = self.max_pop.min(target.max(self.populated));
+    }
+
+    /// Reallocates the table to the smallest geometry that holds its current
+    /// [`capacity`](Self::capacity), as lowered by
+    /// [`soft_shrink`](Self::soft_shrink).
+    ///
+    /// If the capacity fits no smaller geometry, the allocation is kept and
+    /// the capacity goes back to what the allocation can hold.
+    ///
+    /// # Arguments
+    ///
+    /// * `rehash` - A closure that computes the hash for a value, used to move
+    ///   values into the new allocation
+    pub fn commit_shrink(
+        &mut self,
+        rehash: impl Fn(&V) -> u64,
+    ) {
+        if self.max_pop == 0 {
+            self.release_allocation();
+            return;
+        }
+
+        let new_capacity: Capacity =
+            target_load_factor_inverse(self.max_pop.div_ceil(LANES)).into();
+        if new_capacity.max_root_mask() >= self.max_root_mask {
+            self.max_pop = self.allocated_max_pop();
+            return;
+        }
+
+        self.do_resize_rehash(new_capacity, &rehash);
+    }
+
+    /// The capacity of the current allocation, ignoring any
+    /// [`soft_shrink`](Self::soft_shrink).
+    fn allocated_max_pop(&self) -> usize {
+        target_load_factor(self.slot_capacity())
+    }
+
+    /// Undoes a [`soft_shrink`](Self::soft_shrink), returning `true` if that
+    /// raised the capacity.
+    fn reclaim_soft_capacity(&mut self) -> bool {
+        let allocated = self.allocated_max_pop();
+        if self.max_pop < allocated {
+            self.max_pop = allocated;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Frees the allocation of an empty table and resets it to zero capacity.
+    fn release_allocation(&mut self) {
+        debug_assert_eq!(self.populated, 0);
+        if self.layout.layout.size() != 0 {
+            // SAFETY: We have ensured that the allocation is valid before
+            // deallocating, and the table holds no values that would need it.
+            unsafe {
+                alloc::alloc::dealloc(self.alloc.as_ptr(), self.layout.layout);
+            }
+            self.alloc = NonNull::dangling();
+            let new_capacity: Capacity = 0.into();
+            self.layout = DataLayout::new::<V>(new_capacity);
+            self.max_root_mask = new_capacity.max_root_mask();
+            self.max_pop = 0;
+            self.note_modified();
         }
     }
 
@@ -1042,21 +1933,143 @@ impl<V> HashTable<V> {
     /// equal to `self.len() + additional`. Does nothing if capacity is already
     /// sufficient.
     ///
+    /// Growing always allocates a new block and rehashes every value into it,
+    /// since the slot geometry changes with the capacity.
+    ///
     /// # Arguments
     ///
     /// * `additional` - The number of additional elements the table should be
     ///   able to hold
+    ///
+    /// # Panics
+    ///
+    /// Panics with "capacity overflow" if the required capacity cannot be
+    /// represented. Use [`try_reserve`](Self::try_reserve) to handle this
+    /// instead.
+    #[track_caller]
     pub fn reserve(
         &mut self,
         additional: usize,
         rehash: impl Fn(&V) -> u64,
     ) {
-        let required = self.populated.saturating_add(additional);
-        if required > self.max_pop {
-            let new_capacity: Capacity =
-                target_load_factor_inverse(required.div_ceil(LANES)).into();
-            self.do_resize_rehash(new_capacity, &rehash);
+        self.reserve_reporting(additional, rehash);
+    }
+
+    /// Reserves capacity for at least `additional` more elements, reporting
+    /// whether the table was resized.
+    ///
+    /// Behaves exactly like [`reserve`](Self::reserve), but returns `true` if
+    /// the table had to be reallocated and `false` if the existing capacity
+    /// was already sufficient.
+    ///
+    /// # Arguments
+    ///
+    /// * `additional` - The number of additional elements the table should be
+    ///   able to hold
+    #[track_caller]
+    pub fn reserve_reporting(
+        &mut self,
+        additional: usize,
+        rehash: impl Fn(&V) -> u64,
+    ) -> bool {
+        match self.try_reserve_reporting(additional, rehash) {
+            Ok(resized) => resized,
+            Err(e) => e.handle_for::<V>(self.populated.saturating_add(additional)),
+        }
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements,
+    /// returning an error instead of panicking or aborting.
+    ///
+    /// On error the table is left unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `additional` - The number of additional elements the table should be
+    ///   able to hold
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryReserveError::CapacityOverflow`] if the required capacity
+    /// cannot be represented, or [`TryReserveError::AllocError`] if the
+    /// allocator fails.
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+        rehash: impl Fn(&V) -> u64,
+    ) -> Result<(), TryReserveError> {
+        self.try_reserve_reporting(additional, rehash).map(|_| ())
+    }
+
+    fn try_reserve_reporting(
+        &mut self,
+        additional: usize,
+        rehash: impl Fn(&V) -> u64,
+    ) -> Result<bool, TryReserveError> {
+        let required = self
+            .populated
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if required <= self.max_pop {
+            return Ok(false);
+        }
+        if required <= self.allocated_max_pop() {
+            self.reclaim_soft_capacity();
+            return Ok(false);
+        }
+
+        let new_capacity = Capacity::try_for_elements(required)?;
+        let new_layout = DataLayout::try_new::<V>(new_capacity)?;
+        let new_alloc = new_layout.try_allocate()?;
+        // SAFETY: `new_alloc` was just allocated for `new_layout`, which was built
+        // from `new_capacity`.
+        unsafe { self.rehash_into(new_capacity, new_layout, new_alloc, &rehash) };
+        // The `HOP_RANGE` pad keeps even the smallest geometry well above
+        // `required`, so one resize is always enough.
+        debug_assert!(self.max_pop >= required);
+        Ok(true)
+    }
+
+    /// Reserves capacity for at least `additional` more elements using a
+    /// `rehash` that may fail.
+    ///
+    /// If the table has to grow, `rehash` is first called on every value. If
+    /// any call returns `None`, nothing is moved and
+    /// [`TryRehashError::HashUnavailable`] is returned. `rehash` must keep
+    /// returning the same hash for a value for the duration of the call.
+    ///
+    /// On error the table is left unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `additional` - The number of additional elements the table should be
+    ///   able to hold
+    /// * `rehash` - A closure that computes the hash for a value, or `None` if
+    ///   it can no longer be computed
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryRehashError::HashUnavailable`] if `rehash` fails for any
+    /// value, or [`TryRehashError::Reserve`] if the required capacity cannot
+    /// be represented or allocated.
+    pub fn try_reserve_with_rehash(
+        &mut self,
+        additional: usize,
+        rehash: impl Fn(&V) -> Option<u64>,
+    ) -> Result<(), TryRehashError> {
+        let required = self
+            .populated
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if required <= self.max_pop {
+            return Ok(());
+        }
+        if required <= self.allocated_max_pop() {
+            self.reclaim_soft_capacity();
+            return Ok(());
         }
+
+        self.try_resize_with_rehash(Capacity::try_for_elements(required)?, &rehash)
     }
 
     /// Removes and returns a value from the table.
@@ -1085,10 +2098,11 @@ impl<V> HashTable<V> {
         let index = unsafe { self.search_neighborhood(hash, hop_bucket, &eq) };
         if let Some(index) = index {
             self.populated -= 1;
+            self.note_modified();
 
             // SAFETY: We have validated that `index` is within bounds through
             // `search_neighborhood`.
-            let bucket_ref = unsafe { self.buckets_ptr().as_ref().get_unchecked(index) };
+            let bucket_ref = unsafe { self.value_ptr(index).as_ref() };
             // SAFETY: We have confirmed that the value at this index is initialized due to
             // an occupied tag.
             let value = unsafe { bucket_ref.assume_init_read() };
@@ -1117,50 +2131,506 @@ impl<V> HashTable<V> {
         None
     }
 
-    /// Gets an entry for the given hash and equality predicate.
+    /// Removes and returns the value stored at slot `index`.
     ///
-    /// This method returns an `Entry` enum that allows for efficient insertion
-    /// or modification of values. The entry API provides zero-cost abstractions
-    /// for common patterns like "insert if not exists" or "update if exists".
+    /// `index` is a slot index as returned by [`find_index`](Self::find_index),
+    /// which lets a lookup be followed by a removal without searching the
+    /// neighborhood again. Returns `None` if `index` is out of range or the
+    /// slot is empty.
+    ///
+    /// Slot indexes are only stable until the table is next modified: an
+    /// insert may move values between slots, and a resize moves all of them.
     ///
     /// # Arguments
     ///
-    /// * `hash` - The hash value for the entry
-    /// * `eq` - A predicate function that returns `true` for matching values
-    #[inline(always)]
-    pub fn entry(
+    /// * `index` - The slot index of the value to remove
+    /// * `rehash` - A function that computes the hash of a stored value
+    pub fn remove_index(
         &mut self,
-        hash: u64,
-        eq: impl Fn(&V) -> bool,
+        index: usize,
         rehash: impl Fn(&V) -> u64,
-    ) -> Entry<'_, V> {
-        self.maybe_resize_rehash(&rehash);
-        // SAFETY: We have ensured that the table is properly initialized and has
-        // sufficient capacity through `maybe_resize_rehash`.
-        unsafe { self.entry_impl(hash, eq, &rehash) }
+    ) -> Option<V> {
+        if index >= self.slot_capacity() {
+            return None;
+        }
+
+        // SAFETY: `index` was checked against the slot count above.
+        if !unsafe { self.is_occupied(index) } {
+            return None;
+        }
+
+        // SAFETY: `index` is in bounds and occupied.
+        Some(unsafe { self.remove_occupied(index, rehash) })
     }
 
-    /// Gets an entry for the given hash and equality predicate without
-    /// triggering a resize.
-    ///
-    /// This method returns an `Entry` enum that allows for efficient insertion
-    /// or modification of values, similar to [`entry`](HashTable::entry), but
-    /// without automatically resizing the table. If the table is full or cannot
-    /// accommodate the entry without resizing or bubbling, an error is returned
-    /// instead.
-    ///
-    /// This method provides a constant-time worst-case bound for insertion,
-    /// provided you can tolerate failures. It will at most search the
-    /// neighborhood of the root bucket for an empty slot (rather than the
-    /// entire table), and will not attempt to resize or bubble entries.o
+    /// Removes and returns the value that `handle` refers to, without
+    /// hashing or searching for it.
     ///
-    /// For medium to large tables with a reasonable load factor, this has a
-    /// very high success rate, as most insertions will find a free slot in
-    /// the neighborhood.
+    /// Returns `None` if `handle` came from another table, or if this table
+    /// was modified after the handle was created. Both are checked in O(1).
     ///
     /// # Arguments
     ///
-    /// * `hash` - The hash value for the entry
+    /// * `handle` - A handle from [`VacantEntry::insert_with_handle`] or
+    ///   [`OccupiedEntry::handle`]
+    pub fn remove_by_handle(
+        &mut self,
+        handle: EntryHandle,
+    ) -> Option<V> {
+        if handle.table_id != self.id || handle.generation != self.generation {
+            return None;
+        }
+
+        // SAFETY: The handle was created by this table, and the table has not been
+        // modified since, so the value it was created for is still in its slot.
+        // That slot lies `handle.index - handle.root * LANES` slots into the
+        // neighborhood of `handle.root`, the value's root bucket.
+        Some(unsafe {
+            self.remove_in_neighborhood(handle.root, handle.index - handle.root * LANES)
+        })
+    }
+
+    /// Removes the value `n_index` slots into the neighborhood of `root`,
+    /// without rehashing it.
+    ///
+    /// # Safety
+    ///
+    /// `root` must be a valid root bucket, `n_index` must be less than
+    /// `HOP_RANGE * LANES`, and the slot must be occupied by a value whose
+    /// root bucket is `root`.
+    unsafe fn remove_in_neighborhood(
+        &mut self,
+        root: usize,
+        n_index: usize,
+    ) -> V {
+        self.populated -= 1;
+        self.note_modified();
+
+        // SAFETY: The caller guarantees the slot is in bounds and initialized, so
+        // the value can be read out. `n_index / LANES` is less than `HOP_RANGE`, so
+        // it is a valid neighbor index of `root`, and the value is counted there.
+        unsafe {
+            let value = self
+                .value_ptr(root * LANES + n_index)
+                .as_ref()
+                .assume_init_read();
+            self.hopmap_ptr()
+                .as_mut()
+                .get_unchecked_mut(root)
+                .clear(n_index / LANES);
+            self.clear_occupied(root * LANES + n_index);
+
+            value
+        }
+    }
+
+    /// Removes the value at an occupied slot, clearing its tag and the hopmap
+    /// bit of its home bucket.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be in bounds and the slot must be occupied.
+    unsafe fn remove_occupied(
+        &mut self,
+        index: usize,
+        rehash: impl Fn(&V) -> u64,
+    ) -> V {
+        self.populated -= 1;
+        self.note_modified();
+        // SAFETY: The caller guarantees the bucket is initialized and `index` is in
+        // bounds. The value's home bucket is derived from its hash, so `index` lies in
+        // that bucket's neighborhood. We clear its tag and hopmap entry before moving
+        // the value out.
+        unsafe {
+            let value = self.value_ptr(index).as_ref().assume_init_ref();
+            let hash = rehash(value);
+            #[cfg(debug_assertions)]
+            debug_check_rehash(*self.tag_ptr(index).as_ref(), hash);
+            self.clear_occupied(index);
+            let hop_bucket = self.hopmap_index(hash);
+            self.hopmap_ptr()
+                .as_mut()
+                .get_unchecked_mut(hop_bucket)
+                .clear((index - hop_bucket * LANES) / LANES);
+
+            self.value_ptr(index).as_ref().assume_init_read()
+        }
+    }
+
+    /// Gets an entry for the given hash and equality predicate.
+    ///
+    /// This method returns an `Entry` enum that allows for efficient insertion
+    /// or modification of values. The entry API provides zero-cost abstractions
+    /// for common patterns like "insert if not exists" or "update if exists".
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value for the entry
+    /// * `eq` - A predicate function that returns `true` for matching values
+    #[inline(always)]
+    pub fn entry(
+        &mut self,
+        hash: u64,
+        eq: impl Fn(&V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+    ) -> Entry<'_, V> {
+        self.entry_once(hash, eq, rehash)
+    }
+
+    /// Gets an entry for the given hash and a stateful equality predicate.
+    ///
+    /// This is identical to [`entry`](Self::entry), except that `eq` only
+    /// needs to be `FnMut`, so it can be a `move` closure that owns or updates
+    /// its captures. Note that despite the name, `eq` may be called several
+    /// times: once for every candidate in the neighborhood whose tag matches
+    /// `hash`, until it returns `true`.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value for the entry
+    /// * `eq` - A predicate function that returns `true` for matching values
+    /// * `rehash` - A closure that computes the hash for a value, used if the
+    ///   table needs to resize
+    #[inline(always)]
+    pub fn entry_once(
+        &mut self,
+        hash: u64,
+        eq: impl FnMut(&V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+    ) -> Entry<'_, V> {
+        self.maybe_resize_rehash(&rehash);
+        let hop_bucket = self.hopmap_index(hash);
+        // SAFETY: We have ensured that the table is properly initialized and has
+        // sufficient capacity through `maybe_resize_rehash`, and `hop_bucket` is
+        // derived from the hash and mask.
+        unsafe { self.entry_impl(hash, hop_bucket, eq, &rehash) }
+    }
+
+    /// Gets an entry like [`entry`](Self::entry), assuming the caller has
+    /// already reserved room for it.
+    ///
+    /// This skips the load-factor check that [`entry`](Self::entry) performs
+    /// before every lookup, for use in loops that immediately follow a
+    /// [`reserve`](Self::reserve) covering every insertion.
+    ///
+    /// # Contract
+    ///
+    /// The table must have spare [`capacity`](Self::capacity), i.e. `len() <
+    /// capacity()`. Debug builds panic if it does not. Release builds stay
+    /// sound either way: a table without an allocation is still grown, and an
+    /// insertion with no free slot in reach resizes as usual, but the table
+    /// may fill past its target load factor until the next resize.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value for the entry
+    /// * `eq` - A predicate function that returns `true` for matching values
+    /// * `rehash` - A closure that computes the hash for a value, used if the
+    ///   table needs to resize
+    #[inline(always)]
+    #[track_caller]
+    pub fn entry_reserved(
+        &mut self,
+        hash: u64,
+        eq: impl Fn(&V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+    ) -> Entry<'_, V> {
+        debug_assert!(
+            self.populated < self.max_pop,
+            "entry_reserved called without reserved capacity"
+        );
+        // A zero `max_pop` covers both an unallocated table, where
+        // `hopmap_index` would be out of bounds, and a soft-shrunk one.
+        if self.max_pop == 0 {
+            self.resize_rehash(&rehash);
+        }
+        let hop_bucket = self.hopmap_index(hash);
+        // SAFETY: The table has an allocation, since `max_pop` is non-zero, and
+        // `hop_bucket` is derived from the hash and mask.
+        unsafe { self.entry_impl(hash, hop_bucket, eq, &rehash) }
+    }
+
+    /// Gets an entry like [`entry`](Self::entry), and reports whether getting
+    /// it resized the table.
+    ///
+    /// The flag is `true` if the table grew, either because it was already
+    /// at [`capacity`](Self::capacity) or because no free slot could be
+    /// reached from the entry's neighborhood. This is useful for detecting
+    /// tables that outgrow the capacity they were created or reserved with.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value for the entry
+    /// * `eq` - A predicate function that returns `true` for matching values
+    /// * `rehash` - A closure that computes the hash for a value, used if the
+    ///   table needs to resize
+    pub fn entry_tracked(
+        &mut self,
+        hash: u64,
+        eq: impl Fn(&V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+    ) -> (Entry<'_, V>, bool) {
+        // Resizing during `entry` only ever grows the table, so a changed mask
+        // means a resize happened.
+        let mask = self.max_root_mask;
+        let entry = self.entry_once(hash, eq, rehash);
+        let resized = match &entry {
+            Entry::Vacant(vacant) => vacant.table.max_root_mask != mask,
+            Entry::Occupied(occupied) => occupied.table.max_root_mask != mask,
+        };
+        (entry, resized)
+    }
+
+    /// Inserts a value, replacing and returning the value matched by `eq` if
+    /// there is one.
+    ///
+    /// This is the table-level equivalent of
+    /// [`HashMap::insert`](crate::hash_map::HashMap::insert). It returns
+    /// `None` if no value matched, in which case `value` is added as a new
+    /// entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value of `value`
+    /// * `value` - The value to insert
+    /// * `eq` - A predicate function that returns `true` for the value to
+    ///   replace
+    /// * `rehash` - A closure that computes the hash for a value, used if the
+    ///   table needs to resize
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if looking the new value up again with `hash`
+    /// and `eq` does not find it, which means `hash` or `eq` disagrees with
+    /// `value`.
+    #[track_caller]
+    pub fn insert(
+        &mut self,
+        hash: u64,
+        value: V,
+        eq: impl Fn(&V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+    ) -> Option<V> {
+        match self.entry(hash, &eq, rehash) {
+            Entry::Vacant(entry) => {
+                #[cfg(debug_assertions)]
+                let slot = entry.slot();
+                entry.insert(value);
+                #[cfg(debug_assertions)]
+                self.debug_check_inserted(hash, &eq, slot);
+                None
+            }
+            Entry::Occupied(mut entry) => Some(core::mem::replace(entry.get_mut(), value)),
+        }
+    }
+
+    /// Panics if a lookup with `hash` and `eq` does not return `slot`, the
+    /// slot a value was just inserted into under them.
+    #[cfg(debug_assertions)]
+    #[track_caller]
+    fn debug_check_inserted(
+        &self,
+        hash: u64,
+        eq: impl Fn(&V) -> bool,
+        slot: usize,
+    ) {
+        let bucket = self.hopmap_index(hash);
+        // SAFETY: `bucket` is derived from the hash and the current mask.
+        let found = unsafe { self.search_neighborhood(hash, bucket, eq) };
+        assert!(
+            found == Some(slot),
+            "hash/eq inconsistency: looking up the inserted value with its hash and `eq` did not \
+             find it"
+        );
+    }
+
+    /// Inserts a value without checking whether an equal value is already
+    /// present, returning a mutable reference to it.
+    ///
+    /// This skips the neighborhood search that [`entry`](Self::entry) performs
+    /// and goes straight to finding a free slot. The caller must ensure that
+    /// no equal value is in the table. Violating this is not undefined
+    /// behavior, but both values will be stored and lookups may return either
+    /// one.
+    ///
+    /// With the `debug-checks` feature enabled, debug builds search for an
+    /// existing value that `eq` considers equal to `value` first, and panic
+    /// if there is one. Other builds never call `eq`.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value for the new value
+    /// * `value` - The value to insert
+    /// * `eq` - A function that compares a stored value with `value`, used only
+    ///   by the `debug-checks` duplicate check
+    /// * `rehash` - A closure that computes the hash for a value, used if the
+    ///   table needs to resize
+    ///
+    /// # Panics
+    ///
+    /// With `debug-checks` in a debug build, panics if an equal value is
+    /// already present.
+    #[track_caller]
+    pub fn insert_unique_unchecked(
+        &mut self,
+        hash: u64,
+        value: V,
+        eq: impl Fn(&V, &V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+    ) -> &mut V {
+        #[cfg(all(feature = "debug-checks", debug_assertions))]
+        assert!(
+            self.find(hash, |v| eq(v, &value)).is_none(),
+            "insert_unique_unchecked: an equal value is already present"
+        );
+        #[cfg(not(all(feature = "debug-checks", debug_assertions)))]
+        let _ = eq;
+
+        self.place_unique(hash, value, &rehash)
+    }
+
+    /// Places a value in a free slot without searching for an equal one.
+    fn place_unique(
+        &mut self,
+        hash: u64,
+        value: V,
+        rehash: &impl Fn(&V) -> u64,
+    ) -> &mut V {
+        self.maybe_resize_rehash(rehash);
+        let hop_bucket = self.hopmap_index(hash);
+        // SAFETY: `hop_bucket` is derived from the hash and the current mask, and
+        // `maybe_resize_rehash` ran first so the mask is up to date.
+        unsafe { self.do_vacant_lookup(hash, hop_bucket, rehash) }.insert(value)
+    }
+
+    /// Inserts every `(hash, value)` pair from `items` without checking for
+    /// existing equal values.
+    ///
+    /// This reserves room for `items.len()` values once, then inserts them
+    /// straight into free slots, skipping the per-item equality search and
+    /// capacity check that
+    /// [`insert_unique_unchecked`](Self::insert_unique_unchecked) does. It is
+    /// meant for rebuilding a table from a snapshot of pre-hashed, distinct
+    /// values. The same caveat applies as for `insert_unique_unchecked`:
+    /// duplicates are not undefined behavior, but both values will be
+    /// stored.
+    ///
+    /// Items past the reported length are still inserted, one
+    /// `insert_unique_unchecked` at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The values to insert, each paired with its hash
+    /// * `rehash` - A closure that computes the hash for a value, used if the
+    ///   table needs to resize
+    ///
+    /// # Panics
+    ///
+    /// Panics with "capacity overflow" if the required capacity cannot be
+    /// represented.
+    #[track_caller]
+    pub fn insert_all_unique(
+        &mut self,
+        items: impl ExactSizeIterator<Item = (u64, V)>,
+        rehash: impl Fn(&V) -> u64,
+    ) {
+        let mut items = items;
+        let len = items.len();
+        self.reserve(len, &rehash);
+        for (hash, value) in items.by_ref().take(len) {
+            let hop_bucket = self.hopmap_index(hash);
+            // SAFETY: The reservation above leaves room for `len` more values, so the
+            // table has an allocation and `hop_bucket` is derived from the hash and
+            // the current mask. If bubbling has to resize, `do_vacant_lookup`
+            // recomputes the bucket itself.
+            unsafe { self.do_vacant_lookup(hash, hop_bucket, &rehash) }.insert(value);
+        }
+
+        for (hash, value) in items {
+            self.place_unique(hash, value, &rehash);
+        }
+    }
+
+    /// Returns the root bucket that `hash` maps to in the table's current
+    /// geometry.
+    ///
+    /// The result can be passed to
+    /// [`entry_with_bucket`](Self::entry_with_bucket) and stays valid until
+    /// the table is resized.
+    #[inline(always)]
+    pub fn bucket_index(
+        &self,
+        hash: u64,
+    ) -> usize {
+        self.hopmap_index(hash)
+    }
+
+    /// Gets an entry using a precomputed root bucket.
+    ///
+    /// This is identical to [`entry`](Self::entry), but reuses `bucket_hint`
+    /// (as returned by [`bucket_index`](Self::bucket_index)) instead of
+    /// deriving the root bucket from `hash` again. If this call has to resize
+    /// the table, the bucket is recomputed for the new geometry.
+    ///
+    /// In debug builds, a hint that does not match `hash` panics. In release
+    /// builds it is masked into range, so a wrong hint can cause lookups to
+    /// miss but is never unsound.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value for the entry
+    /// * `bucket_hint` - The root bucket for `hash`
+    /// * `eq` - A predicate function that returns `true` for matching values
+    /// * `rehash` - A closure that computes the hash for a value, used if the
+    ///   table needs to resize
+    #[inline(always)]
+    pub fn entry_with_bucket(
+        &mut self,
+        hash: u64,
+        bucket_hint: usize,
+        eq: impl Fn(&V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+    ) -> Entry<'_, V> {
+        debug_assert_eq!(
+            bucket_hint,
+            self.hopmap_index(hash),
+            "bucket hint does not match hash"
+        );
+
+        let mask = self.max_root_mask;
+        self.maybe_resize_rehash(&rehash);
+        let hop_bucket = if mask == self.max_root_mask {
+            bucket_hint & self.max_root_mask
+        } else {
+            self.hopmap_index(hash)
+        };
+        // SAFETY: We have ensured that the table is properly initialized and has
+        // sufficient capacity through `maybe_resize_rehash`, and `hop_bucket` is
+        // masked by `max_root_mask`.
+        unsafe { self.entry_impl(hash, hop_bucket, eq, &rehash) }
+    }
+
+    /// Gets an entry for the given hash and equality predicate without
+    /// triggering a resize.
+    ///
+    /// This method returns an `Entry` enum that allows for efficient insertion
+    /// or modification of values, similar to [`entry`](HashTable::entry), but
+    /// without automatically resizing the table. If the table is full or cannot
+    /// accommodate the entry without resizing or bubbling, an error is returned
+    /// instead.
+    ///
+    /// This method provides a constant-time worst-case bound for insertion,
+    /// provided you can tolerate failures. It will at most search the
+    /// neighborhood of the root bucket for an empty slot (rather than the
+    /// entire table), and will not attempt to resize or bubble entries.o
+    ///
+    /// For medium to large tables with a reasonable load factor, this has a
+    /// very high success rate, as most insertions will find a free slot in
+    /// the neighborhood.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value for the entry
     /// * `eq` - A predicate function that returns `true` for matching values
     ///
     /// # Errors
@@ -1187,6 +2657,7 @@ impl<V> HashTable<V> {
             return Ok(Entry::Occupied(OccupiedEntry {
                 n_index: index - hop_bucket * LANES,
                 table: self,
+                hash,
                 root_index: hop_bucket,
             }));
         }
@@ -1197,9 +2668,8 @@ impl<V> HashTable<V> {
 
         let absolute_empty_idx;
         // SAFETY: We have validated that `hop_bucket` is within bounds through
-        // `hopmap_index`, which derives it from the hash and `max_root_mask`.
-        // We know that there are HOP_RANGE * LANES slots in the neighborhood, due to
-        // how capacity is calculated.
+        // `hopmap_index`, which derives it from the hash and `max_root_mask`. The scan
+        // stops at the end of the table.
         unsafe {
             let Some(empty_idx) =
                 self.find_next_unoccupied_in_range(self.absolute_index(hop_bucket, 0))
@@ -1221,27 +2691,103 @@ impl<V> HashTable<V> {
         }))
     }
 
+    /// Gets an entry using a `rehash` that may fail.
+    ///
+    /// This behaves like [`entry`](Self::entry), except that a resize which
+    /// cannot compute the hash of some value returns
+    /// [`TryRehashError::HashUnavailable`] and leaves the table as it was,
+    /// instead of misplacing the value. This is useful when hashing depends on
+    /// external state that may have gone away, such as keys behind weak
+    /// references.
+    ///
+    /// Existing values are found without resizing, even when the table is
+    /// full. Because bubbling needs the hash of the values it moves, this
+    /// never bubbles. If no free slot is within the neighborhood, the table
+    /// grows instead. Before growing, `rehash` is called on every value, and it
+    /// must keep returning the same hash for a value for the duration of
+    /// the call.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value for the entry
+    /// * `eq` - A predicate function that returns `true` for matching values
+    /// * `rehash` - A closure that computes the hash for a value, or `None` if
+    ///   it can no longer be computed
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryRehashError::HashUnavailable`] if the table had to grow
+    /// and `rehash` failed for any value, or [`TryRehashError::Reserve`] if
+    /// allocating the larger table failed.
+    pub fn try_entry_with_rehash(
+        &mut self,
+        hash: u64,
+        eq: impl Fn(&V) -> bool,
+        rehash: impl Fn(&V) -> Option<u64>,
+    ) -> Result<Entry<'_, V>, TryRehashError> {
+        if self.max_pop == 0 {
+            self.try_grow_with_rehash(&rehash)?;
+        }
+
+        let (hop_bucket, index, occupied) = loop {
+            let hop_bucket = self.hopmap_index(hash);
+            // SAFETY: The table has a non-zero capacity after the check above, and
+            // `hop_bucket` is derived from the hash and `max_root_mask`. The scan stops
+            // at the end of the table.
+            unsafe {
+                if let Some(index) = self.search_neighborhood(hash, hop_bucket, &eq) {
+                    break (hop_bucket, index, true);
+                }
+                if self.populated < self.max_pop {
+                    let start = self.absolute_index(hop_bucket, 0);
+                    if let Some(index) = self.find_next_unoccupied_in_range(start) {
+                        break (hop_bucket, index, false);
+                    }
+                }
+            }
+            self.try_grow_with_rehash(&rehash)?;
+        };
+
+        let n_index = index - hop_bucket * LANES;
+        if occupied {
+            Ok(Entry::Occupied(OccupiedEntry {
+                n_index,
+                table: self,
+                hash,
+                root_index: hop_bucket,
+            }))
+        } else {
+            Ok(Entry::Vacant(VacantEntry {
+                table: self,
+                hopmap_root: hop_bucket,
+                hash,
+                n_index,
+            }))
+        }
+    }
+
     /// Internal entry implementation that performs the actual lookup.
     ///
     /// # Safety
     ///
-    /// The capacity must not be zero.
+    /// The capacity must not be zero, and `hop_bucket` must be within the
+    /// range of root buckets.
     #[inline]
     unsafe fn entry_impl(
         &mut self,
         hash: u64,
-        eq: impl Fn(&V) -> bool,
+        hop_bucket: usize,
+        eq: impl FnMut(&V) -> bool,
         rehash: &dyn Fn(&V) -> u64,
     ) -> Entry<'_, V> {
-        let hop_bucket = self.hopmap_index(hash);
-
         // SAFETY: We have ensured that `hop_bucket` is within bounds, as it is derived
         // from the hash and mask.
-        let index = unsafe { self.search_neighborhood(hash, hop_bucket, &eq) };
+        let index = unsafe { self.search_neighborhood(hash, hop_bucket, eq) };
         if let Some(index) = index {
             return Entry::Occupied(OccupiedEntry {
                 n_index: index - hop_bucket * LANES,
                 table: self,
+                hash,
                 root_index: hop_bucket,
             });
         }
@@ -1262,21 +2808,24 @@ impl<V> HashTable<V> {
         &self,
         hash: u64,
         bucket: usize,
-        eq: impl Fn(&V) -> bool,
+        mut eq: impl FnMut(&V) -> bool,
     ) -> Option<usize> {
         let tag = hashtag(hash);
         let base = bucket * LANES;
 
         // SAFETY: Caller ensures that `bucket` is within bounds, as it is derived from
         // the hash and `max_root_mask`.
+        // A compact table has no bucket past its only root.
         unsafe {
             prefetch(self.hopmap_ptr().as_ref().as_ptr().add(bucket));
-            prefetch(self.tags_ptr().as_ref().as_ptr().add(base + LANES));
+            if base + LANES < self.slot_capacity() {
+                prefetch(self.tag_ptr(base + LANES).as_ptr());
+            }
         }
 
         // SAFETY: We have ensured `base` is valid, calculated from a validated bucket
         // and an index within the neighborhood.
-        if let Some(value) = unsafe { self.search_tags(&eq, tag, base) } {
+        if let Some(value) = unsafe { self.search_tags(&mut eq, tag, base) } {
             return Some(value);
         }
 
@@ -1301,10 +2850,8 @@ impl<V> HashTable<V> {
             // from the hash and `max_root_mask`.
             unsafe {
                 prefetch(
-                    self.tags_ptr()
-                        .as_ref()
-                        .as_ptr()
-                        .add(base + next_index * LANES * usize::from(neighborhood_mask != 0)),
+                    self.tag_ptr(base + next_index * LANES * usize::from(neighborhood_mask != 0))
+                        .as_ptr(),
                 );
             }
 
@@ -1313,7 +2860,7 @@ impl<V> HashTable<V> {
 
                 // SAFETY: We have ensured `base` is valid, calculated from a validated bucket
                 // and an index within the neighborhood.
-                if let Some(value) = unsafe { self.search_tags(&eq, tag, base) } {
+                if let Some(value) = unsafe { self.search_tags(&mut eq, tag, base) } {
                     return Some(value);
                 }
             }
@@ -1331,7 +2878,7 @@ impl<V> HashTable<V> {
     #[inline(always)]
     unsafe fn search_tags(
         &self,
-        eq: impl Fn(&V) -> bool,
+        mut eq: impl FnMut(&V) -> bool,
         tag: u8,
         base: usize,
     ) -> Option<usize> {
@@ -1350,22 +2897,14 @@ impl<V> HashTable<V> {
             // a validated bucket and an index within the neighborhood.
             unsafe {
                 prefetch(
-                    self.buckets_ptr()
-                        .as_ref()
-                        .as_ptr()
-                        .add(base + next_index * usize::from(tags != 0)),
+                    self.value_ptr(base + next_index * usize::from(tags != 0))
+                        .as_ptr(),
                 );
             }
 
             // SAFETY: We have ensured `slot` is within bounds, as it is calculated from a
             // validated base and index.
-            if unsafe {
-                eq(self
-                    .buckets_ptr()
-                    .as_ref()
-                    .get_unchecked(slot)
-                    .assume_init_ref())
-            } {
+            if unsafe { eq(self.value_ptr(slot).as_ref().assume_init_ref()) } {
                 return Some(slot);
             }
         }
@@ -1377,8 +2916,9 @@ impl<V> HashTable<V> {
     ///
     /// # Safety
     ///
-    /// The caller must ensure `bucket` is within a valid range, such that
-    /// `bucket + 16` does not exceed the bounds of the tags array.
+    /// The caller must ensure `bucket` is a multiple of `LANES` within a valid
+    /// range, such that `bucket + 16` does not exceed the bounds of the tags
+    /// array.
     #[inline(always)]
     unsafe fn scan_tags(
         &self,
@@ -1394,12 +2934,11 @@ impl<V> HashTable<V> {
                 // `scan_tags`.
                 unsafe { self.scan_tags_sse2(bucket, tag) }
             } else {
-                let meta_ptr = self.tags_ptr();
                 let mut tags: u16 = 0;
                 for i in 0..LANES {
                     // SAFETY: We have ensured `bucket + i` is within bounds, as `bucket` is a valid
                     // base for `scan_tags`.
-                    let t = unsafe { *meta_ptr.as_ref().get_unchecked(bucket + i) };
+                    let t = unsafe { *self.tag_ptr(bucket + i).as_ref() };
                     if t == tag {
                         tags |= 1 << i;
                     }
@@ -1413,8 +2952,9 @@ impl<V> HashTable<V> {
     ///
     /// # Safety
     ///
-    /// The caller must ensure `bucket` is within a valid range, such that
-    /// `bucket + 16` does not exceed the bounds of the tags array. This
+    /// The caller must ensure `bucket` is a multiple of `LANES` within a valid
+    /// range, such that `bucket + 16` does not exceed the bounds of the tags
+    /// array. This
     /// relies on `EMPTY` (0x80) using the sign bit for complementary SIMD
     /// scans.
     #[cfg(all(
@@ -1430,8 +2970,7 @@ impl<V> HashTable<V> {
         // SAFETY: We have validated that `bucket` is within bounds, allowing for a safe
         // load of 16 consecutive bytes.
         unsafe {
-            let meta_ptr = self.tags_ptr();
-            let tags_ptr = meta_ptr.as_ref().as_ptr().add(bucket);
+            let tags_ptr = self.tag_ptr(bucket).as_ptr();
             let tag_vec = _mm_set1_epi8(tag as i8);
 
             let data = _mm_loadu_si128(tags_ptr as *const __m128i);
@@ -1441,6 +2980,46 @@ impl<V> HashTable<V> {
         }
     }
 
+    /// Returns a mask of the occupied slots among the 16 starting at `base`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure `base` is a multiple of `LANES` within a valid
+    /// range, such that `base + 16` does not exceed the bounds of the tags
+    /// array.
+    #[inline(always)]
+    unsafe fn occupied_mask(
+        &self,
+        base: usize,
+    ) -> u16 {
+        cfg_if! {
+            if #[cfg(all(
+                any(target_arch = "x86_64", target_arch = "x86"),
+                target_feature = "sse2"
+            ))] {
+                // SAFETY: We have validated the base bounds, as per the requirements of
+                // `occupied_mask`. `EMPTY` is the only tag with the sign bit set, so the
+                // inverted movemask is exactly the set of occupied slots.
+                unsafe {
+                    let tags_ptr = self.tag_ptr(base).as_ptr();
+                    let data = _mm_loadu_si128(tags_ptr as *const __m128i);
+                    !(_mm_movemask_epi8(data) as u16)
+                }
+            } else {
+                let mut occupied: u16 = 0;
+                for i in 0..LANES {
+                    // SAFETY: We have ensured `base + i` is within bounds, as `base` is a valid
+                    // base for `occupied_mask`.
+                    let t = unsafe { *self.tag_ptr(base + i).as_ref() };
+                    if t != EMPTY {
+                        occupied |= 1 << i;
+                    }
+                }
+                occupied
+            }
+        }
+    }
+
     #[inline(always)]
     fn hopmap_index(
         &self,
@@ -1471,13 +3050,29 @@ impl<V> HashTable<V> {
         hash: u64,
         hop_bucket: usize,
         rehash: &dyn Fn(&V) -> u64,
+    ) -> VacantEntry<'_, V> {
+        // SAFETY: Forwarded from the caller.
+        unsafe { self.do_vacant_lookup_limited(hash, hop_bucket, rehash, self.max_bubble_moves) }
+    }
+
+    /// [`do_vacant_lookup`](Self::do_vacant_lookup) with an explicit limit on
+    /// the number of values bubbled before resizing.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `hop_bucket` is within the bounds of the
+    /// hopmap array.
+    unsafe fn do_vacant_lookup_limited(
+        &mut self,
+        hash: u64,
+        hop_bucket: usize,
+        rehash: &dyn Fn(&V) -> u64,
+        max_moves: usize,
     ) -> VacantEntry<'_, V> {
         debug_assert!(hop_bucket <= self.max_root_mask);
         let empty_idx = unsafe { self.find_next_unoccupied(self.absolute_index(hop_bucket, 0)) };
 
-        if empty_idx.is_none()
-            || empty_idx.unwrap() >= self.absolute_index(self.max_root_mask + 1 + HOP_RANGE, 0)
-        {
+        if empty_idx.is_none() || empty_idx.unwrap() >= self.slot_capacity() {
             self.resize_rehash(rehash);
             // SAFETY: After resizing, the table has a new `max_root_mask`. The call to
             // `self.hopmap_index(hash)` computes a *new* `hop_bucket` that is valid for
@@ -1501,29 +3096,28 @@ impl<V> HashTable<V> {
             };
         }
 
+        self.note_modified();
+        let mut moves = 0;
         while absolute_empty_idx >= self.absolute_index(hop_bucket + HOP_RANGE, 0) {
             let bubble_base = absolute_empty_idx - (HOP_RANGE - 1) * LANES;
 
-            // SAFETY: We have ensured that `bubble_base` and `absolute_empty_idx` are
-            // within the table bounds.
-            if let Some((absolute_idx, moved_hash)) = unsafe {
-                find_next_movable_index(
-                    self.buckets_ptr().as_ref(),
-                    bubble_base,
-                    absolute_empty_idx,
-                    self.max_root_mask,
-                    rehash,
-                )
-            } {
+            let movable = if moves < max_moves {
+                // SAFETY: We have ensured that `bubble_base` and `absolute_empty_idx` are
+                // within the table bounds.
+                unsafe { find_next_movable_index(self, bubble_base, absolute_empty_idx, rehash) }
+            } else {
+                None
+            };
+            if let Some((absolute_idx, moved_hash)) = movable {
+                moves += 1;
                 // SAFETY: We have validated `absolute_idx` through `find_next_movable_index`,
                 // ensuring it is within bounds.
                 unsafe {
-                    let buckets_ptr = self.buckets_ptr().as_mut().as_mut_ptr();
                     debug_assert_ne!(absolute_idx, absolute_empty_idx);
 
                     core::ptr::copy_nonoverlapping(
-                        buckets_ptr.add(absolute_idx),
-                        buckets_ptr.add(absolute_empty_idx),
+                        self.value_ptr(absolute_idx).as_ptr(),
+                        self.value_ptr(absolute_empty_idx).as_ptr(),
                         1,
                     );
 
@@ -1551,11 +3145,25 @@ impl<V> HashTable<V> {
                     self.clear_occupied(absolute_idx);
                     self.set_occupied(absolute_empty_idx, hashtag(moved_hash));
                     absolute_empty_idx = absolute_idx;
+                    #[cfg(feature = "stats")]
+                    {
+                        self.bubble_count += 1;
+                    }
                 }
             } else {
                 self.resize_rehash(rehash);
+                // If the limit forced this resize, bubble as far as needed in the larger
+                // table. Keys that still collide there would otherwise grow the table
+                // without bound.
+                let max_moves = if moves == max_moves {
+                    usize::MAX
+                } else {
+                    max_moves
+                };
                 // SAFETY: We have ensured `hop_bucket` is within the hopmap bounds.
-                return unsafe { self.do_vacant_lookup(hash, self.hopmap_index(hash), rehash) };
+                return unsafe {
+                    self.do_vacant_lookup_limited(hash, self.hopmap_index(hash), rehash, max_moves)
+                };
             }
         }
 
@@ -1581,7 +3189,7 @@ impl<V> HashTable<V> {
         index: usize,
     ) -> bool {
         // SAFETY: Caller ensures `index` is within bounds of the tags array
-        unsafe { *self.tags_ptr().as_ref().get_unchecked(index) != EMPTY }
+        unsafe { *self.tag_ptr(index).as_ref() != EMPTY }
     }
 
     /// Clear the occupied tag at index
@@ -1596,7 +3204,7 @@ impl<V> HashTable<V> {
     ) {
         // SAFETY: Caller ensures `index` is within bounds of the tags array
         unsafe {
-            *self.tags_ptr().as_mut().get_unchecked_mut(index) = EMPTY;
+            *self.tag_ptr(index).as_mut() = EMPTY;
         }
     }
 
@@ -1615,18 +3223,18 @@ impl<V> HashTable<V> {
         // SAFETY: Caller ensures `index` is within bounds of the tags array
         unsafe {
             debug_assert!(tag != EMPTY);
-            *self.tags_ptr().as_mut().get_unchecked_mut(index) = tag;
+            *self.tag_ptr(index).as_mut() = tag;
         }
     }
 
     /// Find the next unoccupied index in the range starting from `start` and
-    /// only examining the next `HOP_RANGE * LANES` slots.
+    /// only examining the next `HOP_RANGE * LANES` slots, or up to the end of
+    /// the table if it is closer.
     ///
     /// # Safety
     ///
-    /// The caller must ensure `start` is within the bounds of the tags array
-    /// and that there are at least `HOP_RANGE * LANES` slots available from
-    /// `start`.
+    /// The caller must ensure `start` is a multiple of `LANES` within the
+    /// bounds of the tags array.
     #[inline(always)]
     unsafe fn find_next_unoccupied_in_range(
         &self,
@@ -1637,15 +3245,13 @@ impl<V> HashTable<V> {
                 any(target_arch = "x86_64", target_arch = "x86"),
                 target_feature = "sse2"
             ))] {
-                // SAFETY: Caller ensures `start` is within bounds and there are enough slots
-                // to examine.
+                // SAFETY: Caller ensures `start` is within bounds.
                 unsafe { self.find_next_unoccupied_in_range_sse2(start) }
             } else {
-                let end = start + HOP_RANGE * LANES;
-                let meta_ptr = self.tags_ptr();
+                let end = (start + HOP_RANGE * LANES).min(self.slot_capacity());
                 for i in start..end {
                     // SAFETY: Caller ensures `i` is within bounds of the tags array
-                    let t = unsafe { *meta_ptr.as_ref().get_unchecked(i) };
+                    let t = unsafe { *self.tag_ptr(i).as_ref() };
                     if t == EMPTY {
                         return Some(i);
                     }
@@ -1659,10 +3265,9 @@ impl<V> HashTable<V> {
     ///
     /// # Safety
     ///
-    /// The caller must ensure `start` is within the bounds of the tags array
-    /// and that there are at least `HOP_RANGE * LANES` slots available from
-    /// `start`. This relies on `EMPTY` (0x80) having the sign bit set for
-    /// `movemask` to find empty slots.
+    /// The caller must ensure `start` is a multiple of `LANES` within the
+    /// bounds of the tags array. This relies on `EMPTY` (0x80) having the
+    /// sign bit set for `movemask` to find empty slots.
     #[cfg(all(
         any(target_arch = "x86_64", target_arch = "x86"),
         target_feature = "sse2"
@@ -1672,14 +3277,15 @@ impl<V> HashTable<V> {
         start: usize,
     ) -> Option<usize> {
         unsafe {
-            let meta_ptr = self.tags_ptr();
-            let tags_ptr = meta_ptr.as_ref().as_ptr().add(start);
-            let len = (meta_ptr.as_ref().len()).saturating_sub(start);
-            let end = HOP_RANGE * LANES.min(len);
+            debug_assert_eq!(start % LANES, 0);
+            let len = self.slot_capacity().saturating_sub(start);
+            let end = (HOP_RANGE * LANES).min(len);
 
             let mut offset = 0;
             while offset + LANES <= end {
-                let data = _mm_loadu_si128(tags_ptr.add(offset) as *const __m128i);
+                // `start + offset` is a multiple of `LANES`, so its group of tags is
+                // contiguous.
+                let data = _mm_loadu_si128(self.tag_ptr(start + offset).as_ptr() as *const __m128i);
                 let mask = _mm_movemask_epi8(data) as u16;
 
                 if mask != 0 {
@@ -1691,7 +3297,7 @@ impl<V> HashTable<V> {
             }
 
             while offset < end {
-                let byte = *tags_ptr.add(offset);
+                let byte = *self.tag_ptr(start + offset).as_ref();
                 if byte == EMPTY {
                     return Some(start + offset);
                 }
@@ -1706,7 +3312,8 @@ impl<V> HashTable<V> {
     ///
     /// # Safety
     ///
-    /// The caller must ensure `start` is within the bounds of the tags array.
+    /// The caller must ensure `start` is a multiple of `LANES` within the
+    /// bounds of the tags array.
     #[inline(always)]
     unsafe fn find_next_unoccupied(
         &self,
@@ -1721,10 +3328,7 @@ impl<V> HashTable<V> {
                 ))] {
                     self.find_next_unoccupied_sse2(start)
                 } else {
-                    self.tags_ptr().as_ref()[start..]
-                        .iter()
-                        .position(|&b| b == EMPTY)
-                        .map(|idx| idx + start)
+                    (start..self.slot_capacity()).find(|&i| *self.tag_ptr(i).as_ref() == EMPTY)
                 }
             }
         }
@@ -1734,23 +3338,24 @@ impl<V> HashTable<V> {
     ///
     /// # Safety
     ///
-    /// The caller must ensure `start` is within the bounds of the tags array.
-    /// This relies on `EMPTY` (0x80) having the sign bit set for `movemask`
-    /// to find empty slots. Unaligned loads are performed but guarded by
-    /// bounds checks.
+    /// The caller must ensure `start` is a multiple of `LANES` within the
+    /// bounds of the tags array. This relies on `EMPTY` (0x80) having the
+    /// sign bit set for `movemask` to find empty slots. Unaligned loads are
+    /// performed but guarded by bounds checks.
     #[inline(always)]
     unsafe fn find_next_unoccupied_sse2(
         &self,
         start: usize,
     ) -> Option<usize> {
         unsafe {
-            let meta_ptr = self.tags_ptr();
-            let tags_ptr = meta_ptr.as_ref().as_ptr().add(start);
-            let len = (meta_ptr.as_ref().len()).saturating_sub(start);
+            debug_assert_eq!(start % LANES, 0);
+            let len = self.slot_capacity().saturating_sub(start);
 
             let mut offset = 0;
             while offset + LANES <= len {
-                let data = _mm_loadu_si128(tags_ptr.add(offset) as *const __m128i);
+                // `start + offset` is a multiple of `LANES`, so its group of tags is
+                // contiguous.
+                let data = _mm_loadu_si128(self.tag_ptr(start + offset).as_ptr() as *const __m128i);
                 let mask = _mm_movemask_epi8(data) as u16;
 
                 if mask != 0 {
@@ -1762,7 +3367,7 @@ impl<V> HashTable<V> {
             }
 
             while offset < len {
-                let byte = *tags_ptr.add(offset);
+                let byte = *self.tag_ptr(start + offset).as_ref();
                 if byte == EMPTY {
                     return Some(start + offset);
                 }
@@ -1788,6 +3393,48 @@ impl<V> HashTable<V> {
         &self,
         hash: u64,
         eq: impl Fn(&V) -> bool,
+    ) -> Option<&V> {
+        self.find_once(hash, eq)
+    }
+
+    /// Finds the first value with the given hash that satisfies `pred`.
+    ///
+    /// `pred` is evaluated against every value in the neighborhood whose tag
+    /// matches `hash`, so it can pick out one of several values sharing a
+    /// hash, e.g. by enum variant. `pred` must only accept values whose hash
+    /// is `hash`. Tags only narrow the candidates, so a predicate that also
+    /// accepts values with other hashes may return one of them, depending on
+    /// where the table happened to place it.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value to search for
+    /// * `pred` - A predicate that returns `true` for an acceptable value
+    #[inline]
+    pub fn find_where(
+        &self,
+        hash: u64,
+        pred: impl Fn(&V) -> bool,
+    ) -> Option<&V> {
+        self.find_once(hash, pred)
+    }
+
+    /// Finds a value in the table using a stateful equality predicate.
+    ///
+    /// This is identical to [`find`](Self::find), except that `eq` only needs
+    /// to be `FnMut`. Note that despite the name, `eq` may be called several
+    /// times: once for every candidate in the neighborhood whose tag matches
+    /// `hash`, until it returns `true`.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value to search for
+    /// * `eq` - A predicate function that returns `true` for the desired value
+    #[inline]
+    pub fn find_once(
+        &self,
+        hash: u64,
+        eq: impl FnMut(&V) -> bool,
     ) -> Option<&V> {
         if self.populated == 0 {
             return None;
@@ -1796,21 +3443,42 @@ impl<V> HashTable<V> {
         let bucket = self.hopmap_index(hash);
         // SAFETY: We have ensured that `bucket` is within bounds through
         // `hopmap_index`, which derives it from the hash and `max_root_mask`.
-        let index = unsafe { self.search_neighborhood(hash, bucket, &eq) };
+        let index = unsafe { self.search_neighborhood(hash, bucket, eq) };
         if let Some(index) = index {
             // SAFETY: We have validated `index` through `search_neighborhood`, and the
             // bucket is confirmed to be initialized by an occupied tag.
-            return Some(unsafe {
-                self.buckets_ptr()
-                    .as_ref()
-                    .get_unchecked(index)
-                    .assume_init_ref()
-            });
+            return Some(unsafe { self.value_ptr(index).as_ref().assume_init_ref() });
         }
 
         None
     }
 
+    /// Finds the slot index of a value by hash and equality predicate.
+    ///
+    /// The index can be passed to [`remove_index`](Self::remove_index) to
+    /// remove the value without a second search, as long as the table is not
+    /// modified in between.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value to search for
+    /// * `eq` - A predicate function that returns `true` for the desired value
+    #[inline]
+    pub fn find_index(
+        &self,
+        hash: u64,
+        eq: impl Fn(&V) -> bool,
+    ) -> Option<usize> {
+        if self.populated == 0 {
+            return None;
+        }
+
+        let bucket = self.hopmap_index(hash);
+        // SAFETY: We have ensured that `bucket` is within bounds through
+        // `hopmap_index`, which derives it from the hash and `max_root_mask`.
+        unsafe { self.search_neighborhood(hash, bucket, eq) }
+    }
+
     /// Finds a value in the table by hash and equality predicate, returning a
     /// mutable reference.
     ///
@@ -1827,6 +3495,27 @@ impl<V> HashTable<V> {
         &mut self,
         hash: u64,
         eq: impl Fn(&V) -> bool,
+    ) -> Option<&mut V> {
+        self.find_mut_once(hash, eq)
+    }
+
+    /// Finds a value in the table using a stateful equality predicate,
+    /// returning a mutable reference.
+    ///
+    /// This is identical to [`find_mut`](Self::find_mut), except that `eq`
+    /// only needs to be `FnMut`. As with [`find_once`](Self::find_once), `eq`
+    /// is called once for every candidate whose tag matches `hash`, in
+    /// sequence, until it returns `true`.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value to search for
+    /// * `eq` - A predicate function that returns `true` for the desired value
+    #[inline]
+    pub fn find_mut_once(
+        &mut self,
+        hash: u64,
+        eq: impl FnMut(&V) -> bool,
     ) -> Option<&mut V> {
         if self.populated == 0 {
             return None;
@@ -1836,20 +3525,125 @@ impl<V> HashTable<V> {
 
         // SAFETY: We have ensured that `bucket` is within bounds through
         // `hopmap_index`, which derives it from the hash and `max_root_mask`.
-        if let Some(index) = unsafe { self.search_neighborhood(hash, bucket, &eq) } {
+        if let Some(index) = unsafe { self.search_neighborhood(hash, bucket, eq) } {
             // SAFETY: We have validated `index` through `search_neighborhood`, and the
             // bucket is confirmed to be initialized by an occupied tag.
-            return Some(unsafe {
-                self.buckets_ptr()
-                    .as_mut()
-                    .get_unchecked_mut(index)
-                    .assume_init_mut()
-            });
+            return Some(unsafe { self.value_ptr(index).as_mut().assume_init_mut() });
         }
 
         None
     }
 
+    /// Applies `f` to every value with the given hash that satisfies `pred`,
+    /// returning how many values were modified.
+    ///
+    /// The table does not prevent several values from matching the same hash
+    /// and predicate, e.g. when values are inserted with
+    /// [`insert_unique_unchecked`](Self::insert_unique_unchecked) to build a
+    /// multimap. Where [`find_mut`](Self::find_mut) stops at the first
+    /// match, this visits all of them. As with `find_mut`, `f` must not
+    /// change a value's hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value to search for
+    /// * `pred` - A predicate function that returns `true` for values to modify
+    /// * `f` - A closure applied to each matching value
+    pub fn modify_matching(
+        &mut self,
+        hash: u64,
+        pred: impl Fn(&V) -> bool,
+        mut f: impl FnMut(&mut V),
+    ) -> usize {
+        if self.populated == 0 {
+            return 0;
+        }
+
+        let bucket = self.hopmap_index(hash);
+        let tag = hashtag(hash);
+        // SAFETY: `bucket` is derived from the hash and `max_root_mask`, so it
+        // indexes a valid hopmap entry.
+        let mut neighbors = unsafe {
+            self.hopmap_ptr()
+                .as_ref()
+                .get_unchecked(bucket)
+                .candidates()
+        };
+
+        let mut modified = 0;
+        while neighbors != 0 {
+            let base = (bucket + neighbors.trailing_zeros() as usize) * LANES;
+            neighbors &= neighbors - 1;
+            // SAFETY: `bucket <= max_root_mask` and the neighbor is within
+            // `HOP_RANGE`, so the 16 slots starting at `base` are within the table.
+            let mut tags = unsafe { self.scan_tags(base, tag) };
+            while tags != 0 {
+                let slot = base + tags.trailing_zeros() as usize;
+                tags &= tags - 1;
+                // SAFETY: `slot` is within bounds, and its tag matched a non-`EMPTY`
+                // tag, so the bucket is initialized.
+                let value = unsafe { self.value_ptr(slot).as_mut().assume_init_mut() };
+                if pred(value) {
+                    f(value);
+                    modified += 1;
+                }
+            }
+        }
+        modified
+    }
+
+    /// Resolves a stream of lookups lazily, prefetching ahead of the query
+    /// being answered.
+    ///
+    /// Each query is a hash and an equality predicate, as taken by
+    /// [`find`](Self::find). The returned iterator yields one result per
+    /// query, in order. It pulls a few queries ahead of the one it is
+    /// resolving and prefetches their neighborhoods, which hides most of the
+    /// cache misses for large tables. Queries are pulled no faster than
+    /// results are consumed, apart from that lookahead.
+    ///
+    /// # Arguments
+    ///
+    /// * `queries` - The `(hash, eq)` pairs to look up
+    pub fn find_stream<I, F>(
+        &self,
+        queries: I,
+    ) -> FindStream<'_, V, I::IntoIter, F>
+    where
+        I: IntoIterator<Item = (u64, F)>,
+        F: Fn(&V) -> bool,
+    {
+        FindStream {
+            table: self,
+            queries: queries.into_iter(),
+            pending: VecDeque::with_capacity(FIND_STREAM_PREFETCH_DISTANCE + 1),
+        }
+    }
+
+    /// Prefetches the neighborhood metadata for the root bucket of `hash`.
+    ///
+    /// This is a hint for batched lookups: issuing it a few lookups ahead of
+    /// the matching `find` hides most of the cache miss on the hopmap and tag
+    /// arrays for large tables.
+    #[inline(always)]
+    pub(crate) fn prefetch_hash(
+        &self,
+        hash: u64,
+    ) {
+        if self.populated == 0 {
+            return;
+        }
+
+        let bucket = self.hopmap_index(hash);
+        // SAFETY: The table is non-empty, so it has a valid allocation and
+        // `bucket` is derived from the hash and `max_root_mask`, making it a
+        // valid root bucket. Its first tag group is always in bounds.
+        unsafe {
+            prefetch(self.hopmap_ptr().as_ref().as_ptr().add(bucket));
+            prefetch(self.tag_ptr(bucket * LANES).as_ptr());
+        }
+    }
+
     #[inline]
     fn maybe_resize_rehash(
         &mut self,
@@ -1866,43 +3660,104 @@ impl<V> HashTable<V> {
         &mut self,
         rehash: &dyn Fn(&V) -> u64,
     ) {
+        // Growing after a `soft_shrink` first takes back the capacity the current
+        // allocation already has.
+        if self.reclaim_soft_capacity() {
+            return;
+        }
         let capacity = self.max_root_mask.wrapping_add(1).max(HOP_RANGE) + 1;
         let capacity: Capacity = capacity.into();
 
         self.do_resize_rehash(capacity, rehash);
     }
 
+    /// Grows the table one step like [`resize_rehash`](Self::resize_rehash),
+    /// but with a fallible `rehash`.
+    #[cold]
+    fn try_grow_with_rehash(
+        &mut self,
+        rehash: &dyn Fn(&V) -> Option<u64>,
+    ) -> Result<(), TryRehashError> {
+        if self.reclaim_soft_capacity() {
+            return Ok(());
+        }
+        let capacity = self.max_root_mask.wrapping_add(1).max(HOP_RANGE) + 1;
+        self.try_resize_with_rehash(capacity.into(), rehash)
+    }
+
+    /// Moves every value into a freshly allocated table of `capacity`, first
+    /// checking that `rehash` succeeds for all of them.
+    ///
+    /// Nothing is modified if this returns an error.
+    fn try_resize_with_rehash(
+        &mut self,
+        capacity: Capacity,
+        rehash: &dyn Fn(&V) -> Option<u64>,
+    ) -> Result<(), TryRehashError> {
+        if self.iter().any(|value| rehash(value).is_none()) {
+            return Err(TryRehashError::HashUnavailable);
+        }
+
+        let new_layout = DataLayout::try_new::<V>(capacity)?;
+        let new_alloc = new_layout.try_allocate()?;
+        let rehash = |value: &V| {
+            rehash(value)
+                .expect("`rehash` returned `None` for a value it hashed earlier in the call")
+        };
+        // SAFETY: `new_alloc` was just allocated for `new_layout`, which was built
+        // from `capacity`.
+        unsafe { self.rehash_into(capacity, new_layout, new_alloc, &rehash) };
+        Ok(())
+    }
+
+    /// Moves every value into a freshly allocated table of `capacity`.
+    ///
+    /// This deliberately allocates a new block instead of using `realloc`. The
+    /// hopmap, tag, and bucket regions all move when the geometry changes, and
+    /// every value has to be rehashed into its new neighborhood while the old
+    /// regions are still readable, so growing the block in place would not
+    /// save the copy. Resizing therefore costs exactly one allocation and one
+    /// deallocation.
     #[inline]
     fn do_resize_rehash(
         &mut self,
         capacity: Capacity,
         rehash: &dyn Fn(&V) -> u64,
     ) {
-        debug_assert!(
-            capacity.max_root_mask() != self.max_root_mask || self.max_root_mask == usize::MAX
-        );
-
         let new_layout = DataLayout::new::<V>(capacity);
-        // SAFETY: layout.layout validated non-zero size, alloc failure handled
-        let new_alloc = unsafe {
-            let raw_alloc = alloc::alloc::alloc(new_layout.layout);
-            if raw_alloc.is_null() {
-                handle_alloc_error(new_layout.layout);
-            }
-            core::ptr::write_bytes(raw_alloc, 0x0, new_layout.tags_offset);
-            core::ptr::write_bytes(
-                raw_alloc.add(new_layout.tags_offset),
-                EMPTY,
-                new_layout.buckets_offset - new_layout.tags_offset,
-            );
+        let new_alloc = new_layout.try_allocate().unwrap_or_else(|e| e.handle());
+        // SAFETY: `new_alloc` was just allocated for `new_layout`, which was built
+        // from `capacity`.
+        unsafe { self.rehash_into(capacity, new_layout, new_alloc, rehash) };
+    }
 
-            NonNull::new_unchecked(raw_alloc)
-        };
+    /// Moves every value into `new_alloc` and makes it the table's storage.
+    ///
+    /// # Safety
+    ///
+    /// `new_alloc` must have been returned by `new_layout.try_allocate()`, and
+    /// `new_layout` must have been built from `capacity`.
+    unsafe fn rehash_into(
+        &mut self,
+        capacity: Capacity,
+        new_layout: DataLayout,
+        new_alloc: NonNull<u8>,
+        rehash: &dyn Fn(&V) -> u64,
+    ) {
+        debug_assert_ne!(capacity.base * LANES, self.slot_capacity());
+
+        self.note_modified();
         let old_layout = core::mem::replace(&mut self.layout, new_layout);
         let old_alloc = core::mem::replace(&mut self.alloc, new_alloc);
         let old_max_root = self.max_root_mask.wrapping_add(1);
-        let old_base = old_max_root + HOP_RANGE;
-        let old_empty_words = old_base * LANES;
+        let old_empty_words = old_layout.slots;
+        // Both root counts are powers of two, so this is exact when growing and zero
+        // when shrinking.
+        let growth = capacity.max_root_mask().wrapping_add(1) / old_max_root.max(1);
+        #[cfg(feature = "metrics")]
+        if capacity.max_root_mask().wrapping_add(1) > old_max_root {
+            crate::metrics::record(self.metrics, MetricsEvent::Resize);
+        }
         self.max_pop = target_load_factor(capacity.base * LANES);
         self.max_root_mask = capacity.max_root_mask();
         if self.populated == 0 {
@@ -1917,24 +3772,12 @@ impl<V> HashTable<V> {
             return;
         }
         let mut needing_resize = Vec::new();
-        // SAFETY: old_alloc valid, old_empty_words calculated from valid old capacity
-        let old_emptymap: NonNull<[u8]> = unsafe {
-            NonNull::slice_from_raw_parts(
-                old_alloc.add(old_layout.tags_offset).cast(),
-                old_empty_words,
-            )
-        };
-        let old_buckets: NonNull<[MaybeUninit<V>]> = unsafe {
-            NonNull::slice_from_raw_parts(
-                old_alloc.add(old_layout.buckets_offset).cast(),
-                old_empty_words,
-            )
-        };
 
         // SAFETY: This block moves all initialized values from the old allocation to
         // the new one. The safety of this operation relies on the following:
         // - The old allocation is valid and contains `self.populated` initialized
-        //   elements, which are correctly identified by the `old_emptymap` (tags).
+        //   elements, which are correctly identified by its tags. Every slot below
+        //   `old_empty_words` is a slot of `old_layout`.
         // - We iterate through the old tags. For each non-empty tag, we read the value
         //   with `assume_init_read`, which is safe because the tag marks it as
         //   initialized.
@@ -1951,38 +3794,38 @@ impl<V> HashTable<V> {
             // moved-out contents; only the new table will drop values.
             self.populated = 0;
 
-            'tags: for (bucket_index, &tag) in old_emptymap.as_ref().iter().enumerate() {
+            'tags: for bucket_index in 0..old_empty_words {
+                let tag = *old_layout.tag_ptr(old_alloc, bucket_index).as_ref();
                 if tag == EMPTY {
                     continue;
                 }
 
-                if old_base < capacity.base {
-                    // Prefetch the two possible destination locations for this item.
-                    // Due to power-of-2 mask expansion, an item at old root bucket R will
-                    // hash to either new root bucket R or R + old_max_root.
-                    let old_root_bucket = bucket_index / LANES;
-                    prefetch(self.hopmap_ptr().as_ref().as_ptr().add(old_root_bucket));
-                    prefetch(
-                        self.hopmap_ptr()
-                            .as_ref()
-                            .as_ptr()
-                            .add(old_root_bucket + old_max_root),
-                    );
-                    prefetch(self.buckets_ptr().as_ref().as_ptr().add(bucket_index));
-                    prefetch(
-                        self.buckets_ptr()
-                            .as_ref()
-                            .as_ptr()
-                            .add(bucket_index + old_max_root * LANES),
-                    );
+                if (2..=MAX_MIGRATION_PREFETCH_GROWTH).contains(&growth) {
+                    // Prefetch every possible destination for this item. Growing the mask
+                    // by a factor of `growth` sends an item at old root bucket R to new root
+                    // bucket R + k * old_max_root for some k below `growth`. Slots in the
+                    // padding buckets approximate their root with the last old root.
+                    let old_root_bucket = (bucket_index / LANES).min(old_max_root - 1);
+                    for step in 0..growth {
+                        let offset = step * old_max_root;
+                        prefetch(
+                            self.hopmap_ptr()
+                                .as_ref()
+                                .as_ptr()
+                                .add(old_root_bucket + offset),
+                        );
+                        prefetch(self.value_ptr(bucket_index + offset * LANES).as_ptr());
+                    }
                 }
 
-                let value = old_buckets
+                let value = old_layout
+                    .value_ptr::<V>(old_alloc, bucket_index)
                     .as_ref()
-                    .get_unchecked(bucket_index)
                     .assume_init_read();
 
                 let hash = rehash(&value);
+                #[cfg(debug_assertions)]
+                debug_check_rehash(tag, hash);
 
                 let bucket = self.hopmap_index(hash);
                 let base = self.absolute_index(bucket, 0);
@@ -1993,20 +3836,16 @@ impl<V> HashTable<V> {
                         // Bubble the empty slot backward until it's within the neighborhood.
                         // Loop invariant: `idx` remains a valid slot index throughout, initially
                         // found by `find_next_unoccupied` and updated by `find_next_movable_index`
-                        // to maintain `idx < absolute_index(max_root_mask + 1 + HOP_RANGE, 0)`.
+                        // to maintain `idx < slot_capacity()`.
                         while idx >= self.absolute_index(bucket + HOP_RANGE, 0) {
                             let bubble_base = idx - (HOP_RANGE - 1) * LANES;
 
-                            if let Some((absolute_idx, moved_hash)) = find_next_movable_index(
-                                self.buckets_ptr().as_ref(),
-                                bubble_base,
-                                idx,
-                                self.max_root_mask,
-                                &rehash,
-                            ) {
+                            if let Some((absolute_idx, moved_hash)) =
+                                find_next_movable_index(self, bubble_base, idx, &rehash)
+                            {
                                 core::ptr::copy_nonoverlapping(
-                                    self.buckets_ptr().as_ref().as_ptr().add(absolute_idx),
-                                    self.buckets_ptr().as_mut().as_mut_ptr().add(idx),
+                                    self.value_ptr(absolute_idx).as_ptr(),
+                                    self.value_ptr(idx).as_ptr(),
                                     1,
                                 );
 
@@ -2030,6 +3869,10 @@ impl<V> HashTable<V> {
                                 self.clear_occupied(absolute_idx);
                                 self.set_occupied(idx, hashtag(moved_hash));
                                 idx = absolute_idx;
+                                #[cfg(feature = "stats")]
+                                {
+                                    self.bubble_count += 1;
+                                }
                             } else {
                                 needing_resize.push((value, hash));
                                 continue 'tags;
@@ -2053,10 +3896,7 @@ impl<V> HashTable<V> {
                     .get_unchecked_mut(bucket)
                     .set(n_index);
 
-                self.buckets_ptr()
-                    .as_mut()
-                    .get_unchecked_mut(absolute_empty_idx)
-                    .write(value);
+                self.value_ptr(absolute_empty_idx).as_mut().write(value);
             }
 
             for (needs_resize, hash) in needing_resize {
@@ -2082,10 +3922,53 @@ impl<V> HashTable<V> {
     ///
     /// The table maintains a load factor of approximately 87.5% (configurable
     /// up to 97%) before triggering a resize operation.
+    ///
+    /// This is the resize threshold, not the number of allocated slots. See
+    /// [`slot_capacity`](Self::slot_capacity) for the latter.
     pub fn capacity(&self) -> usize {
         self.max_pop
     }
 
+    /// Returns `true` if `additional` more elements fit in the current
+    /// allocation without a resize.
+    ///
+    /// This is the same check [`reserve`](Self::reserve) uses to decide
+    /// whether to grow. Note that [`try_entry`](Self::try_entry) may still
+    /// fail with [`TryEntryError::NoFreeSlot`] if a neighborhood is full.
+    pub fn capacity_fits(
+        &self,
+        additional: usize,
+    ) -> bool {
+        self.populated.saturating_add(additional) <= self.max_pop
+    }
+
+    /// Returns the total number of slots allocated by the table.
+    ///
+    /// This is always larger than [`capacity`](Self::capacity) for a non-empty
+    /// allocation, since the table resizes before every slot is filled. It
+    /// reflects memory use, while `capacity` reflects the resize threshold.
+    ///
+    /// Every slot index reported by [`VacantEntry::slot`] or
+    /// [`OccupiedEntry::slot`] is less than this value, so it can be used to
+    /// size external storage indexed by slot.
+    pub fn slot_capacity(&self) -> usize {
+        self.layout.slots
+    }
+
+    /// Returns the table's capacity metrics in a single call.
+    pub fn capacity_info(&self) -> CapacityInfo {
+        CapacityInfo {
+            live: self.max_pop,
+            slots: self.slot_capacity(),
+            allocation_bytes: self.layout.layout.size(),
+            load_factor: if self.max_pop == 0 {
+                0.0
+            } else {
+                self.populated as f64 / self.max_pop as f64
+            },
+        }
+    }
+
     /// Computes a histogram of probe lengths and bucket distribution for the
     /// current table state.
     ///
@@ -2097,7 +3980,7 @@ impl<V> HashTable<V> {
     pub fn probe_histogram(&self) -> ProbeHistogram {
         let mut probe_hist = ProbeHistogram {
             populated: self.populated,
-            buckets: self.max_root_mask.wrapping_add(1) + HOP_RANGE,
+            buckets: self.slot_capacity() / LANES,
             probe_length_by_bucket: [0; HOP_RANGE],
             probe_length_by_count: [0; HOP_RANGE],
             bucket_distribution: [0; HOP_RANGE],
@@ -2144,11 +4027,7 @@ impl<V> HashTable<V> {
     /// Returns detailed performance and utilization statistics for debugging.
     #[cfg(feature = "stats")]
     pub fn debug_stats(&self) -> DebugStats {
-        let total_slots = if self.max_root_mask == usize::MAX {
-            0
-        } else {
-            (self.max_root_mask.wrapping_add(1) + HOP_RANGE) * LANES
-        };
+        let total_slots = self.slot_capacity();
 
         let mut occupied_slots = 0;
 
@@ -2187,34 +4066,131 @@ impl<V> HashTable<V> {
         }
     }
 
-    /// Retains only the elements specified by the predicate.
-    ///
-    /// The predicate is a closure that takes a reference to a value and returns
-    /// `true` if the value should be kept in the table, or `false` if it should
-    /// be removed.
-    ///
-    /// # Arguments
+    /// Returns the number of values moved by bubbling.
     ///
-    /// * `f` - A closure that determines whether to retain each value
-    /// * `rehash` - A closure that computes the hash for a value, used to
-    ///   update the hopmap when removing entries
-    pub fn retain(
+    /// Bubbling happens when the nearest empty slot is outside the new
+    /// value's neighborhood, during inserts as well as while rehashing into a
+    /// resized table. It is much cheaper than a resize, but a rising count
+    /// indicates congested neighborhoods. The count is cumulative until
+    /// [`reset_bubble_count`](Self::reset_bubble_count) is called.
+    #[cfg(feature = "stats")]
+    pub fn bubble_count(&self) -> usize {
+        self.bubble_count
+    }
+
+    /// Resets the counter reported by [`bubble_count`](Self::bubble_count).
+    #[cfg(feature = "stats")]
+    pub fn reset_bubble_count(&mut self) {
+        self.bubble_count = 0;
+    }
+
+    /// Returns the sink that resize events are reported to, if any.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn metrics_sink(&self) -> Option<&'static dyn MetricsSink> {
+        self.metrics
+    }
+
+    /// Registers `sink` to receive an event every time the table grows.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn set_metrics_sink(
         &mut self,
-        mut f: impl FnMut(&V) -> bool,
-        rehash: impl Fn(&V) -> u64,
+        sink: Option<&'static dyn MetricsSink>,
     ) {
-        self.retain_mut(|v| f(v), rehash);
+        self.metrics = sink;
     }
 
-    /// Retains only the elements specified by the predicate.
+    /// Returns how many values a single insert may bubble before the table
+    /// resizes instead.
     ///
-    /// The predicate is a closure that takes a reference to a value and returns
-    /// `true` if the value should be kept in the table, or `false` if it should
-    /// be removed.
+    /// See [`set_max_bubble_moves`](Self::set_max_bubble_moves).
+    pub fn max_bubble_moves(&self) -> usize {
+        self.max_bubble_moves
+    }
+
+    /// Limits how many values a single insert may bubble before the table
+    /// resizes instead.
     ///
-    /// # Arguments
+    /// By default there is no limit (`usize::MAX`), and an insert only
+    /// resizes when bubbling cannot make room at all. A lower limit caps the
+    /// work a single insert does before giving up and growing, trading memory
+    /// for insert latency. A limit of `0` resizes whenever an insert would
+    /// bubble. After a resize forced by the limit, the insert bubbles as far
+    /// as needed, so the limit costs at most one extra resize per insert.
+    /// Values are still bubbled while being moved into a resized table.
     ///
-    /// * `f` - A closure that determines whether to retain each value
+    /// The limit is kept across resizes and copied by `clone`.
+    pub fn set_max_bubble_moves(
+        &mut self,
+        max_moves: usize,
+    ) {
+        self.max_bubble_moves = max_moves;
+    }
+
+    /// Moves every value from `other` into this table.
+    ///
+    /// Each incoming value is hashed with `rehash` and looked up with `eq`.
+    /// If a matching value already exists, `resolve(existing, incoming)` is
+    /// called to combine them; otherwise the incoming value is inserted.
+    ///
+    /// Both tables must hash values consistently, i.e. `rehash` must produce
+    /// the same hash for equal values in either table.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The table to consume
+    /// * `eq` - A predicate that returns `true` if an existing value (first
+    ///   argument) matches an incoming value (second argument)
+    /// * `rehash` - A closure that computes the hash for a value
+    /// * `resolve` - A closure that folds an incoming value into a matching
+    ///   existing value
+    pub fn merge(
+        &mut self,
+        other: HashTable<V>,
+        eq: impl Fn(&V, &V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+        resolve: impl Fn(&mut V, V),
+    ) {
+        self.reserve(merge_reserve_hint(self.len(), other.len()), &rehash);
+
+        for value in other {
+            let hash = rehash(&value);
+            match self.entry(hash, |v| eq(v, &value), &rehash) {
+                Entry::Occupied(mut entry) => resolve(entry.get_mut(), value),
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+        }
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// The predicate is a closure that takes a reference to a value and returns
+    /// `true` if the value should be kept in the table, or `false` if it should
+    /// be removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - A closure that determines whether to retain each value
+    /// * `rehash` - A closure that computes the hash for a value, used to
+    ///   update the hopmap when removing entries
+    pub fn retain(
+        &mut self,
+        mut f: impl FnMut(&V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+    ) {
+        self.retain_mut(|v| f(v), rehash);
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// The predicate is a closure that takes a reference to a value and returns
+    /// `true` if the value should be kept in the table, or `false` if it should
+    /// be removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - A closure that determines whether to retain each value
     /// * `rehash` - A closure that computes the hash for a value, used to
     ///   update the hopmap when removing entries
     #[inline]
@@ -2227,38 +4203,55 @@ impl<V> HashTable<V> {
             return;
         }
 
-        for idx in 0..(self.max_root_mask.wrapping_add(1) + HOP_RANGE) * LANES {
-            // SAFETY: The call to the `unsafe` function `is_occupied` is safe here
-            // because we are iterating from `0` to the total number of slots,
-            // which is the exact size of the tags array. This ensures that the
-            // index `idx` is always within bounds.
-            if unsafe { self.is_occupied(idx) } {
-                // SAFETY: We have validated `idx` through `is_occupied`, and the bucket
-                // is confirmed to be initialized by an occupied tag.
-                let value = unsafe {
-                    self.buckets_ptr()
-                        .as_mut()
-                        .get_unchecked_mut(idx)
-                        .assume_init_mut()
-                };
+        let mut slots = OccupiedSlots::new(self.populated);
+        // SAFETY: The scan was started for `self`, and the loop only removes slots
+        // the scan has yielded.
+        while let Some(idx) = unsafe { slots.next(self) } {
+            // SAFETY: `idx` is within bounds, and its tag marks it as occupied, so the
+            // bucket is initialized.
+            unsafe {
+                let value = self.value_ptr(idx).as_mut().assume_init_mut();
                 if !f(value) {
-                    self.populated -= 1;
-                    // SAFETY: We have validated `idx` through `is_occupied`, and the bucket
-                    // is confirmed to be initialized by an occupied tag.
-                    unsafe {
-                        self.clear_occupied(idx);
-                        let hash = rehash(value);
-                        let hop_bucket = self.hopmap_index(hash);
-                        self.hopmap_ptr()
-                            .as_mut()
-                            .get_unchecked_mut(hop_bucket)
-                            .clear((idx - hop_bucket * LANES) / LANES);
-
-                        self.buckets_ptr()
-                            .as_mut()
-                            .get_unchecked_mut(idx)
-                            .assume_init_drop();
-                    }
+                    drop(self.remove_occupied(idx, &rehash));
+                }
+            }
+        }
+    }
+
+    /// Retains only the elements specified by the predicate, passing each
+    /// value's hash to the predicate.
+    ///
+    /// `rehash` is called exactly once per value, before `f`, and the result
+    /// is reused to update the hopmap if the value is removed. This is useful
+    /// when the decision depends on the hash itself, such as splitting a table
+    /// into shards by hash bits. `f` must not modify a value in a way that
+    /// changes its hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - A closure that receives a value's hash and the value, and
+    ///   returns whether to retain it
+    /// * `rehash` - A closure that computes the hash for a value
+    pub fn retain_with_hash(
+        &mut self,
+        mut f: impl FnMut(u64, &mut V) -> bool,
+        rehash: impl Fn(&V) -> u64,
+    ) {
+        if self.populated == 0 {
+            return;
+        }
+
+        let mut slots = OccupiedSlots::new(self.populated);
+        // SAFETY: The scan was started for `self`, and the loop only removes slots
+        // the scan has yielded.
+        while let Some(idx) = unsafe { slots.next(self) } {
+            // SAFETY: `idx` is within bounds, and its tag marks it as occupied, so the
+            // bucket is initialized.
+            unsafe {
+                let value = self.value_ptr(idx).as_mut().assume_init_mut();
+                let hash = rehash(value);
+                if !f(hash, value) {
+                    drop(self.remove_occupied(idx, |_| hash));
                 }
             }
         }
@@ -2285,12 +4278,62 @@ impl<V> HashTable<V> {
         R: Fn(&V) -> u64,
     {
         ExtractIf {
+            slots: OccupiedSlots::new(self.populated),
             table: self,
-            index: 0,
             filter: f,
             rehash,
         }
     }
+
+    /// Returns an iterator over the values for which `pred` returns `false`.
+    ///
+    /// This is a borrowing view of what [`retain`](Self::retain) would keep.
+    /// Unlike `iter().filter(..)`, it skips empty slots a group of `LANES` at
+    /// a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `pred` - A closure that returns `true` for values to leave out
+    pub fn filter_out<F>(
+        &self,
+        pred: F,
+    ) -> FilterOut<'_, V, F>
+    where
+        F: FnMut(&V) -> bool,
+    {
+        FilterOut {
+            table: self,
+            slots: OccupiedSlots::new(self.populated),
+            pred,
+        }
+    }
+
+    /// Returns a cursor positioned at the first occupied slot.
+    ///
+    /// The cursor walks occupied slots in slot order and can remove the value
+    /// under it in place, which suits eviction sweeps such as CLOCK. Removing
+    /// a value never moves the others, so the walk order stays stable for the
+    /// lifetime of the cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `rehash` - A closure that computes the hash for a value, used to
+    ///   update the hopmap when removing entries
+    pub fn cursor<R>(
+        &mut self,
+        rehash: R,
+    ) -> SlotCursor<'_, V, R>
+    where
+        R: Fn(&V) -> u64,
+    {
+        let mut cursor = SlotCursor {
+            table: self,
+            index: 0,
+            rehash,
+        };
+        cursor.seek();
+        cursor
+    }
 }
 
 impl<V> IntoIterator for HashTable<V> {
@@ -2299,12 +4342,31 @@ impl<V> IntoIterator for HashTable<V> {
 
     fn into_iter(self) -> Self::IntoIter {
         IntoIter {
+            end: self.slot_capacity(),
             table: self,
             index: 0,
         }
     }
 }
 
+impl<'a, V> IntoIterator for &'a HashTable<V> {
+    type IntoIter = Iter<'a, V>;
+    type Item = &'a V;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a mut HashTable<V> {
+    type IntoIter = IterMut<'a, V>;
+    type Item = &'a mut V;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 /// A view into a single entry in the hash table, which may be vacant or
 /// occupied.
 ///
@@ -2344,6 +4406,9 @@ impl<'a, V> Entry<'a, V> {
     /// value. If the entry is vacant, calls the provided closure to compute
     /// the value and inserts it.
     ///
+    /// `default` runs before the vacant slot is claimed, so if it panics the
+    /// slot stays empty and the table's length is unchanged.
+    ///
     /// # Arguments
     ///
     /// * `default` - A closure that returns the value to insert if the entry is
@@ -2398,6 +4463,30 @@ impl<'a, V> Entry<'a, V> {
     }
 }
 
+/// A handle to the slot of a value, for removing it later without a lookup.
+///
+/// Handles are returned by [`VacantEntry::insert_with_handle`] and
+/// [`OccupiedEntry::handle`], and consumed by
+/// [`HashTable::remove_by_handle`]. Like slot indexes, a handle is only
+/// valid until the table is next modified. Each handle records which table
+/// created it and that table's generation, a counter bumped by every
+/// insertion, removal, move or resize, so using a stale or foreign handle is
+/// detected rather than removing the wrong value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryHandle {
+    index: usize,
+    root: usize,
+    table_id: usize,
+    generation: usize,
+}
+
+impl EntryHandle {
+    /// Returns the absolute slot index of the value.
+    pub fn slot(&self) -> usize {
+        self.index
+    }
+}
+
 /// A view into a vacant entry in the hash table.
 ///
 /// This struct is created by the [`entry`] method on [`HashTable`] when the
@@ -2414,6 +4503,39 @@ pub struct VacantEntry<'a, V> {
 }
 
 impl<'a, V> VacantEntry<'a, V> {
+    /// Returns the metrics sink of the table this entry belongs to.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn metrics_sink(&self) -> Option<&'static dyn MetricsSink> {
+        self.table.metrics
+    }
+
+    /// Returns the absolute slot index the value will be stored at.
+    ///
+    /// The index is valid until the next structural modification of the
+    /// table (an insertion, removal, clear, or resize), and is always less
+    /// than [`HashTable::slot_capacity`].
+    pub fn slot(&self) -> usize {
+        self.hopmap_root * LANES + self.n_index
+    }
+
+    /// Inserts a value into the vacant entry and returns a mutable reference to
+    /// it, along with a handle for removing it later.
+    ///
+    /// See [`EntryHandle`] for how long the handle stays valid.
+    pub fn insert_with_handle(
+        self,
+        value: V,
+    ) -> (&'a mut V, EntryHandle) {
+        let handle = EntryHandle {
+            index: self.hopmap_root * LANES + self.n_index,
+            root: self.hopmap_root,
+            table_id: self.table.id,
+            // `insert` counts as exactly one modification.
+            generation: self.table.generation.wrapping_add(1),
+        };
+        (self.insert(value), handle)
+    }
+
     /// Inserts a value into the vacant entry and returns a mutable reference to
     /// it.
     ///
@@ -2424,6 +4546,7 @@ impl<'a, V> VacantEntry<'a, V> {
         value: V,
     ) -> &'a mut V {
         self.table.populated += 1;
+        self.table.note_modified();
 
         // SAFETY: A `VacantEntry` is only constructed by `do_vacant_lookup` with:
         // - A valid `hopmap_root` where `hopmap_root <= max_root_mask`, ensuring it
@@ -2448,11 +4571,64 @@ impl<'a, V> VacantEntry<'a, V> {
             let target_index = self.hopmap_root * LANES + self.n_index;
             self.table.set_occupied(target_index, hashtag(self.hash));
 
+            self.table.value_ptr(target_index).as_mut().write(value)
+        }
+    }
+
+    /// Inserts the value returned by `f` into the vacant entry and returns a
+    /// mutable reference to it.
+    ///
+    /// This defers building the value until its slot has been found. The
+    /// value is still moved into the slot once `f` returns, although the
+    /// compiler can often construct it there directly. If `f` panics, the
+    /// table is left unchanged.
+    pub fn insert_with(
+        self,
+        f: impl FnOnce() -> V,
+    ) -> &'a mut V {
+        self.insert(f())
+    }
+
+    /// Initializes the value directly in its slot through a raw pointer and
+    /// returns a mutable reference to it.
+    ///
+    /// `init` receives a pointer to the uninitialized slot, which is valid
+    /// for writes of `V` and properly aligned. The slot is only marked as
+    /// occupied once `init` returns, so if `init` panics the table is left
+    /// unchanged and anything written to the slot is leaked, not dropped.
+    ///
+    /// # Safety
+    ///
+    /// When `init` returns, it must have fully initialized the pointed-to
+    /// `V`. The table treats the slot as a valid value from then on, and
+    /// drops it like any other. The value must also hash to the hash this
+    /// entry was looked up with, as for [`insert`](Self::insert).
+    pub unsafe fn insert_in_place(
+        self,
+        init: impl FnOnce(*mut V),
+    ) -> &'a mut V {
+        let target_index = self.hopmap_root * LANES + self.n_index;
+        // SAFETY: `target_index` is a valid, unoccupied slot within the table's
+        // bounds, as described in `insert`.
+        let slot = unsafe { self.table.value_ptr(target_index).as_mut().as_mut_ptr() };
+        init(slot);
+
+        self.table.populated += 1;
+        self.table.note_modified();
+        // SAFETY: As in `insert`, `hopmap_root` and `neighbor` are in bounds and
+        // `target_index` is the entry's unoccupied slot. The caller guarantees that
+        // `init` initialized the value, so it is valid to mark the slot as occupied
+        // and hand out a reference to it.
+        unsafe {
+            let neighbor = self.n_index / LANES;
+            debug_assert!(neighbor < HOP_RANGE);
             self.table
-                .buckets_ptr()
+                .hopmap_ptr()
                 .as_mut()
-                .get_unchecked_mut(target_index)
-                .write(value)
+                .get_unchecked_mut(self.hopmap_root)
+                .set(neighbor);
+            self.table.set_occupied(target_index, hashtag(self.hash));
+            &mut *slot
         }
     }
 }
@@ -2467,6 +4643,7 @@ impl<'a, V> VacantEntry<'a, V> {
 #[derive(Debug)]
 pub struct OccupiedEntry<'a, V> {
     table: &'a mut HashTable<V>,
+    hash: u64,
     root_index: usize,
     n_index: usize,
 }
@@ -2481,14 +4658,51 @@ pub struct OccupiedEntry<'a, V> {
 // Therefore, `get_unchecked`, `get_unchecked_mut`, `assume_init_ref`, and
 // `assume_init_mut` are all safe operations when accessing in-table entries.
 impl<'a, V> OccupiedEntry<'a, V> {
+    /// Returns the metrics sink of the table this entry belongs to.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn metrics_sink(&self) -> Option<&'static dyn MetricsSink> {
+        self.table.metrics
+    }
+
+    /// Returns the absolute slot index of the value in the entry.
+    ///
+    /// The index is valid until the next structural modification of the
+    /// table (an insertion, removal, clear, or resize), and is always less
+    /// than [`HashTable::slot_capacity`].
+    pub fn slot(&self) -> usize {
+        self.root_index * LANES + self.n_index
+    }
+
+    /// Returns the hash the entry was looked up with.
+    ///
+    /// This is the hash passed to [`HashTable::entry`] (or a related method),
+    /// so it can be reused for later operations on the same value without
+    /// recomputing it.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns a handle for removing this value later with
+    /// [`HashTable::remove_by_handle`].
+    ///
+    /// See [`EntryHandle`] for how long the handle stays valid.
+    pub fn handle(&self) -> EntryHandle {
+        let index = self.root_index * LANES + self.n_index;
+        EntryHandle {
+            index,
+            root: self.root_index,
+            table_id: self.table.id,
+            generation: self.table.generation,
+        }
+    }
+
     /// Gets a reference to the value in the entry.
     pub fn get(&self) -> &V {
         // SAFETY: See safety invariant comment above `impl` block.
         unsafe {
             self.table
-                .buckets_ptr()
+                .value_ptr(self.root_index * LANES + self.n_index)
                 .as_ref()
-                .get_unchecked(self.root_index * LANES + self.n_index)
                 .assume_init_ref()
         }
     }
@@ -2498,9 +4712,8 @@ impl<'a, V> OccupiedEntry<'a, V> {
         // SAFETY: See safety invariant comment above `impl` block.
         unsafe {
             self.table
-                .buckets_ptr()
+                .value_ptr(self.root_index * LANES + self.n_index)
                 .as_mut()
-                .get_unchecked_mut(self.root_index * LANES + self.n_index)
                 .assume_init_mut()
         }
     }
@@ -2511,43 +4724,83 @@ impl<'a, V> OccupiedEntry<'a, V> {
         // SAFETY: See safety invariant comment above `impl` block.
         unsafe {
             self.table
-                .buckets_ptr()
+                .value_ptr(self.root_index * LANES + self.n_index)
                 .as_mut()
-                .get_unchecked_mut(self.root_index * LANES + self.n_index)
                 .assume_init_mut()
         }
     }
 
     /// Removes the entry from the table and returns the value.
     pub fn remove(self) -> V {
-        self.table.populated -= 1;
-
-        // SAFETY: This is safe for the same reasons as `get()`: the entry is
-        // guaranteed to point to a valid, initialized element. We can therefore
-        // safely read the value with `assume_init_read`. The subsequent calls to
-        // `clear` and `clear_occupied` are also safe because the indices are
-        // guaranteed to be valid by the invariants of `OccupiedEntry`.
+        // SAFETY: See safety invariant comment above `impl` block. `n_index` is
+        // within the hop-neighborhood of `root_index`, as found by
+        // `search_neighborhood`.
         unsafe {
-            let bucket_mut = self
-                .table
-                .buckets_ptr()
-                .as_ref()
-                .get_unchecked(self.root_index * LANES + self.n_index);
-            let value = bucket_mut.assume_init_read();
-            let neighbor = self.n_index / LANES;
-            // SAFETY: `self.n_index` is the offset from the root bucket, and is
-            // guaranteed to be within the hop-neighborhood by `search_neighborhood`.
-            // Therefore, `neighbor` will be a valid neighbor index (< HOP_RANGE).
             self.table
-                .hopmap_ptr()
-                .as_mut()
-                .get_unchecked_mut(self.root_index)
-                .clear(neighbor);
+                .remove_in_neighborhood(self.root_index, self.n_index)
+        }
+    }
+}
 
-            self.table
-                .clear_occupied(self.root_index * LANES + self.n_index);
+/// A scan over the occupied slots of a [`HashTable`], one group of `LANES`
+/// tags at a time.
+///
+/// The scan does not borrow the table, so callers can remove the slots it
+/// yields as they go.
+#[derive(Clone, Copy)]
+struct OccupiedSlots {
+    /// Start of the next group of `LANES` slots to scan.
+    index: usize,
+    /// Occupied slots of the group before `index` that have not been yielded.
+    mask: u16,
+    /// Occupied slots at or past `index`.
+    remaining: usize,
+}
 
-            value
+impl OccupiedSlots {
+    /// Starts a scan of a table holding `populated` values.
+    fn new(populated: usize) -> Self {
+        Self {
+            index: 0,
+            mask: 0,
+            remaining: populated,
+        }
+    }
+
+    /// Returns how many occupied slots have not been yielded yet.
+    fn len(&self) -> usize {
+        self.remaining + self.mask.count_ones() as usize
+    }
+
+    /// Returns the index of the next occupied slot, or `None` once every
+    /// occupied slot has been yielded.
+    ///
+    /// # Safety
+    ///
+    /// `table` must be the table this scan was started for, and since then
+    /// its only changes may be removals of slots this scan has yielded.
+    #[inline(always)]
+    unsafe fn next<V>(
+        &mut self,
+        table: &HashTable<V>,
+    ) -> Option<usize> {
+        loop {
+            if self.mask != 0 {
+                let index = self.index - LANES + self.mask.trailing_zeros() as usize;
+                self.mask &= self.mask - 1;
+                return Some(index);
+            }
+
+            if self.remaining == 0 {
+                return None;
+            }
+
+            // SAFETY: Occupied slots remain past `self.index`, as the caller
+            // guarantees, so it is a multiple of `LANES` less than the slot count,
+            // which is itself a multiple of `LANES`. All 16 tags are in bounds.
+            self.mask = unsafe { table.occupied_mask(self.index) };
+            self.remaining -= self.mask.count_ones() as usize;
+            self.index += LANES;
         }
     }
 }
@@ -2557,49 +4810,69 @@ impl<'a, V> OccupiedEntry<'a, V> {
 /// This struct is created by the [`iter`] method on [`HashTable`].
 /// It yields `&V` references in an arbitrary order.
 ///
+/// The `DISTANCE` parameter is how many slots ahead the iterator prefetches.
+/// It only exists so the benchmarks can compare distances; [`iter`] always
+/// uses the default.
+///
 /// [`iter`]: HashTable::iter
-pub struct Iter<'a, V> {
+pub struct Iter<'a, V, const DISTANCE: usize = ITER_PREFETCH_DISTANCE> {
     table: &'a HashTable<V>,
     bucket_index: usize,
+    remaining: usize,
+    #[cfg(debug_assertions)]
+    generation: usize,
+}
+
+impl<V, const DISTANCE: usize> Clone for Iter<'_, V, DISTANCE> {
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table,
+            bucket_index: self.bucket_index,
+            remaining: self.remaining,
+            #[cfg(debug_assertions)]
+            generation: self.generation,
+        }
+    }
 }
 
-impl<'a, V> Iterator for Iter<'a, V> {
+impl<'a, V, const DISTANCE: usize> Iterator for Iter<'a, V, DISTANCE> {
     type Item = &'a V;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.table.is_empty() {
+        // Best effort only: safe code cannot modify the table while `self.table`
+        // borrows it, and unsafe code that does so is already undefined behavior,
+        // which the compiler may assume away.
+        #[cfg(debug_assertions)]
+        assert!(
+            self.generation == self.table.generation,
+            "table modified during iteration"
+        );
+
+        if self.remaining == 0 {
             return None;
         }
 
         // SAFETY: The `unsafe` block is safe because we are iterating through the
         // table's slots within the valid bounds (`0..total_slots`).
-        // - We guarded against an empty table at the start of `next()`.
+        // - A non-zero `remaining` count means the table is non-empty, so it has an
+        //   allocation.
         // - `is_occupied` is safe to call because `self.bucket_index` is always less
         //   than `total_slots`.
         // - `get_unchecked` is safe for the same reason.
         // - `assume_init_ref` is safe because we only call it after `is_occupied`
         //   returns true, which guarantees the slot contains an initialized value.
         unsafe {
-            let total_slots = (self.table.max_root_mask.wrapping_add(1) + HOP_RANGE) * LANES;
+            let total_slots = self.table.slot_capacity();
             while self.bucket_index < total_slots {
-                let prefetch_index = self.bucket_index + 1;
+                let prefetch_index = self.bucket_index + DISTANCE;
                 if prefetch_index < total_slots {
-                    prefetch(
-                        self.table
-                            .buckets_ptr()
-                            .as_ref()
-                            .as_ptr()
-                            .add(prefetch_index),
-                    );
+                    prefetch(self.table.value_ptr(prefetch_index).as_ptr());
                 }
 
                 if self.table.is_occupied(self.bucket_index) {
-                    let bucket = &self
-                        .table
-                        .buckets_ptr()
-                        .as_ref()
-                        .get_unchecked(self.bucket_index);
+                    let bucket = &self.table.value_ptr(self.bucket_index).as_ref();
                     self.bucket_index += 1;
+                    self.remaining -= 1;
                     return Some(bucket.assume_init_ref());
                 }
 
@@ -2609,95 +4882,246 @@ impl<'a, V> Iterator for Iter<'a, V> {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
-/// A mutable iterator over the values in a [`HashTable`].
+impl<V, const DISTANCE: usize> FusedIterator for Iter<'_, V, DISTANCE> {}
+
+impl<V, const DISTANCE: usize> ExactSizeIterator for Iter<'_, V, DISTANCE> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+/// An iterator over the values in a [`HashTable`] grouped by root bucket.
 ///
-/// This struct is created by the [`iter_mut`] method on [`HashTable`].
-/// It yields `&mut V` references in an arbitrary order.
+/// This struct is created by the [`iter_by_bucket`] method on [`HashTable`].
+/// It yields each non-empty root bucket's index together with a
+/// [`BucketEntries`] iterator over its values.
 ///
-/// [`iter_mut`]: HashTable::iter_mut
-pub struct IterMut<'a, V> {
-    tags: &'a [u8],
-    values: &'a mut [MaybeUninit<V>],
+/// [`iter_by_bucket`]: HashTable::iter_by_bucket
+pub struct BucketIter<'a, V> {
+    table: &'a HashTable<V>,
+    rehash: &'a dyn Fn(&V) -> u64,
+    root: usize,
+    remaining: usize,
 }
 
-impl<'a, V> Iterator for IterMut<'a, V> {
-    type Item = &'a mut V;
+impl<'a, V> Iterator for BucketIter<'a, V> {
+    type Item = (usize, BucketEntries<'a, V>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        while !self.tags.is_empty() {
-            let (first_tag, tags) = core::mem::take(&mut self.tags).split_first().unwrap();
-            self.tags = tags;
-            if *first_tag != EMPTY {
-                let (first_value, values) =
-                    core::mem::take(&mut self.values).split_first_mut().unwrap();
-                self.values = values;
-                return Some(unsafe { first_value.assume_init_mut() });
-            } else {
-                let (_first_value, values) =
-                    core::mem::take(&mut self.values).split_first_mut().unwrap();
-                self.values = values;
+        // Once every value has been handed to a bucket, the remaining roots are
+        // empty. This also covers the unallocated table.
+        while self.remaining != 0 && self.root <= self.table.max_root_mask {
+            let root = self.root;
+            self.root += 1;
+            // SAFETY: `root <= max_root_mask`, so it indexes a valid hopmap entry.
+            let info = unsafe { *self.table.hopmap_ptr().as_ref().get_unchecked(root) };
+            let len: usize = info.neighbors.iter().map(|&n| n as usize).sum();
+            if len == 0 {
+                continue;
             }
+
+            self.remaining -= len;
+            return Some((
+                root,
+                BucketEntries {
+                    table: self.table,
+                    rehash: self.rehash,
+                    root,
+                    candidates: info.candidates(),
+                    base: 0,
+                    mask: 0,
+                    remaining: len,
+                },
+            ));
         }
 
         None
     }
 }
 
-/// A draining iterator over the values in a [`HashTable`].
-///
-/// This struct is created by the [`drain`] method on [`HashTable`].
-/// It yields owned `V` values and empties the table as it iterates.
+impl<V> FusedIterator for BucketIter<'_, V> {}
+
+/// An iterator over the values belonging to one root bucket.
 ///
-/// [`drain`]: HashTable::drain
-pub struct Drain<'a, V> {
-    occupied: Box<[u8]>,
-    total_slots: usize,
-    table: &'a mut HashTable<V>,
-    bucket_index: usize,
+/// Returned as part of each item of [`BucketIter`].
+pub struct BucketEntries<'a, V> {
+    table: &'a HashTable<V>,
+    rehash: &'a dyn Fn(&V) -> u64,
+    root: usize,
+    /// Neighbors of `root` holding at least one of its values, not yet
+    /// scanned.
+    candidates: u16,
+    /// First slot of the neighbor currently being scanned.
+    base: usize,
+    /// Occupied slots of the current neighbor that have not been examined.
+    mask: u16,
+    remaining: usize,
 }
 
-impl<V> Drop for Drain<'_, V> {
-    fn drop(&mut self) {
-        if core::mem::needs_drop::<V>() {
-            for _ in &mut *self {}
+impl<'a, V> Iterator for BucketEntries<'a, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining != 0 {
+            while self.mask != 0 {
+                let idx = self.base + self.mask.trailing_zeros() as usize;
+                self.mask &= self.mask - 1;
+                // SAFETY: `idx` lies in a neighbor of `root`, which is within the
+                // table, and its tag marked it as occupied, so the bucket is
+                // initialized.
+                let value = unsafe { self.table.value_ptr(idx).as_ref().assume_init_ref() };
+                if self.table.hopmap_index((self.rehash)(value)) == self.root {
+                    self.remaining -= 1;
+                    return Some(value);
+                }
+            }
+
+            // The hopmap counts guarantee a candidate remains while values do, unless
+            // `rehash` disagrees with the stored hashes. End early in that case rather
+            // than scanning past the neighborhood.
+            if self.candidates == 0 {
+                self.remaining = 0;
+                return None;
+            }
+            let neighbor = self.candidates.trailing_zeros() as usize;
+            self.candidates &= self.candidates - 1;
+            self.base = (self.root + neighbor) * LANES;
+            // SAFETY: `neighbor < HOP_RANGE` and `root <= max_root_mask`, so the 16
+            // tags starting at `base` are within the table.
+            self.mask = unsafe { self.table.occupied_mask(self.base) };
         }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
-impl<V> Iterator for Drain<'_, V> {
-    type Item = V;
+impl<V> FusedIterator for BucketEntries<'_, V> {}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // SAFETY: The `unsafe` block is safe because we are iterating through the
-        // table's slots within the valid bounds (`0..total_slots`).
+impl<V> ExactSizeIterator for BucketEntries<'_, V> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+/// A mutable iterator over the values in a [`HashTable`].
+///
+/// This struct is created by the [`iter_mut`] method on [`HashTable`].
+/// It yields `&mut V` references in an arbitrary order.
+///
+/// [`iter_mut`]: HashTable::iter_mut
+pub struct IterMut<'a, V> {
+    // No `generation` check, unlike `Iter`: `table` borrows the table mutably
+    // for `'a`, so nothing can modify it while the iterator is alive, and
+    // handing out `&mut V` never changes the table's structure.
+    table: &'a mut HashTable<V>,
+    slots: OccupiedSlots,
+}
+
+impl<'a, V> Iterator for IterMut<'a, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // SAFETY: The scan was started for `self.table`, which nothing else can
+        // modify while it is borrowed here.
+        let idx = unsafe { self.slots.next(self.table) }?;
+        // SAFETY: `idx` is within bounds, and its tag marks it as occupied, so the
+        // bucket is initialized. The scan yields each slot once, so the returned
+        // references never alias, and the table stays borrowed for `'a`.
+        Some(unsafe { self.table.value_ptr(idx).as_mut().assume_init_mut() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.slots.len();
+        (len, Some(len))
+    }
+}
+
+impl<V> FusedIterator for IterMut<'_, V> {}
+
+impl<V> ExactSizeIterator for IterMut<'_, V> {
+    fn len(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+/// What a [`Drain`] does with the table's allocation when it is dropped.
+///
+/// Passed to [`HashTable::drain_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DrainPolicy {
+    /// Keep the allocation so the table can be refilled without resizing.
+    #[default]
+    KeepCapacity,
+    /// Free the allocation, leaving the table at zero capacity.
+    ShrinkAfter,
+}
+
+/// A draining iterator over the values in a [`HashTable`].
+///
+/// This struct is created by the [`drain`] and [`drain_with`] methods on
+/// [`HashTable`]. It yields owned `V` values and empties the table as it
+/// iterates.
+///
+/// [`drain`]: HashTable::drain
+/// [`drain_with`]: HashTable::drain_with
+pub struct Drain<'a, V> {
+    // No `generation` check: `drain_with` already noted the modification, and
+    // the exclusive borrow in `table` keeps anything but this iterator from
+    // touching the table until it is dropped.
+    occupied: Box<[u8]>,
+    total_slots: usize,
+    table: &'a mut HashTable<V>,
+    bucket_index: usize,
+    /// Values that have not been yielded yet.
+    remaining: usize,
+    policy: DrainPolicy,
+}
+
+impl<V> Drop for Drain<'_, V> {
+    fn drop(&mut self) {
+        if core::mem::needs_drop::<V>() {
+            for _ in &mut *self {}
+        }
+
+        if self.policy == DrainPolicy::ShrinkAfter {
+            self.table.release_allocation();
+        }
+    }
+}
+
+impl<V> Iterator for Drain<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // SAFETY: The `unsafe` block is safe because we are iterating through the
+        // table's slots within the valid bounds (`0..total_slots`).
         // - total_slots is initialized to zero if the table is empty
         // - occupied.get_unchecked is safe because `self.bucket_index` is always less
         //   than `total_slots`.
-        // - buckets_ptr.`get_unchecked` is safe for the same reason.
+        // - `value_ptr` is safe for the same reason.
         // - `assume_init_read` is safe because we only call it after `is_occupied`
         //   returns true, and we take ownership of the value.
         unsafe {
             while self.bucket_index < self.total_slots {
-                let prefetch_index = self.bucket_index + 1;
+                let prefetch_index = self.bucket_index + ITER_PREFETCH_DISTANCE;
                 if prefetch_index < self.total_slots {
-                    prefetch(
-                        self.table
-                            .buckets_ptr()
-                            .as_ref()
-                            .as_ptr()
-                            .add(prefetch_index),
-                    );
+                    prefetch(self.table.value_ptr(prefetch_index).as_ptr());
                 }
 
                 if *self.occupied.get_unchecked(self.bucket_index) != EMPTY {
-                    let bucket = self
-                        .table
-                        .buckets_ptr()
-                        .as_ref()
-                        .get_unchecked(self.bucket_index);
+                    let bucket = self.table.value_ptr(self.bucket_index).as_ref();
                     self.bucket_index += 1;
+                    self.remaining -= 1;
                     return Some(bucket.assume_init_read());
                 }
 
@@ -2707,43 +5131,161 @@ impl<V> Iterator for Drain<'_, V> {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<V> FusedIterator for Drain<'_, V> {}
+
+impl<V> ExactSizeIterator for Drain<'_, V> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+/// A draining iterator that maps each value removed from a [`HashTable`].
+///
+/// This struct is created by the [`drain_map`] method on [`HashTable`].
+/// It yields `f(value)` for each owned value and empties the table as it
+/// iterates.
+///
+/// [`drain_map`]: HashTable::drain_map
+pub struct DrainMap<'a, V, F> {
+    inner: Drain<'a, V>,
+    f: F,
+}
+
+impl<V, W, F> Iterator for DrainMap<'_, V, F>
+where
+    F: FnMut(V) -> W,
+{
+    type Item = W;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(&mut self.f)
+    }
+}
+
+impl<V, W, F> FusedIterator for DrainMap<'_, V, F> where F: FnMut(V) -> W {}
+
+/// A draining iterator that yields each value removed from a [`HashTable`]
+/// along with its hash.
+///
+/// This struct is created by the [`drain_hashed`] method on [`HashTable`].
+///
+/// [`drain_hashed`]: HashTable::drain_hashed
+pub struct DrainHashed<'a, V, R> {
+    inner: Drain<'a, V>,
+    rehash: R,
+}
+
+impl<V, R> Iterator for DrainHashed<'_, V, R>
+where
+    R: Fn(&V) -> u64,
+{
+    type Item = (u64, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.inner.next()?;
+        Some(((self.rehash)(&value), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V, R> FusedIterator for DrainHashed<'_, V, R> where R: Fn(&V) -> u64 {}
+
+impl<V, R> ExactSizeIterator for DrainHashed<'_, V, R>
+where
+    R: Fn(&V) -> u64,
+{
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
 }
 
 /// An owning iterator over the values in a [`HashTable`].
 ///
 /// This struct is created by the `into_iter` method on [`HashTable`].
 /// It yields owned `V` values and consumes the table as it iterates.
+///
+/// The slot array is scanned from the front by `next` and from the back by
+/// `next_back`, so the iterator is double-ended, and it knows exactly how many
+/// values remain.
 pub struct IntoIter<V> {
+    // No `generation` check: the iterator owns `table`, so only `next` and
+    // `next_back` ever change it.
     table: HashTable<V>,
     index: usize,
+    end: usize,
+}
+
+impl<V> IntoIter<V> {
+    /// Takes the value at `index` out of the table.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be within bounds and occupied.
+    unsafe fn take(
+        &mut self,
+        index: usize,
+    ) -> V {
+        // SAFETY: Caller ensures `index` is in bounds and occupied, so the bucket is
+        // initialized. Clearing the tag and decrementing `populated` ensures the
+        // table's `Drop` will not drop it again.
+        unsafe {
+            self.table.clear_occupied(index);
+            self.table.populated -= 1;
+            self.table.value_ptr(index).as_ref().assume_init_read()
+        }
+    }
 }
 
 impl<V> Iterator for IntoIter<V> {
     type Item = V;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.table.is_empty() {
-            return None;
-        }
-
         // SAFETY: The `unsafe` block is safe because we are iterating through the
-        // table's slots within the valid bounds (`0..total_slots`).
-        // - We guarded against an empty table at the start of `next()`.
+        // table's slots within the valid bounds (`index..end`, where `end` is at most
+        // `total_slots`).
         // - `is_occupied` is safe to call because `self.index` is always less than
-        //   `total_slots`.
-        // - `get_unchecked` is safe for the same reason.
-        // - `assume_init_read` is safe because we only call it after `is_occupied`
-        //   returns true, which guarantees the slot contains an initialized value.
+        //   `self.end`.
+        // - `take` is safe because we only call it after `is_occupied` returns true,
+        //   which guarantees the slot contains an initialized value.
         unsafe {
-            let total_slots = (self.table.max_root_mask.wrapping_add(1) + HOP_RANGE) * LANES;
-            while self.index < total_slots {
-                if self.table.is_occupied(self.index) {
-                    self.table.clear_occupied(self.index);
-                    let bucket = self.table.buckets_ptr().as_ref().get_unchecked(self.index);
-                    self.index += 1;
-                    return Some(bucket.assume_init_read());
-                }
+            while !self.table.is_empty() && self.index < self.end {
+                let index = self.index;
                 self.index += 1;
+                if self.table.is_occupied(index) {
+                    return Some(self.take(index));
+                }
+            }
+
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.table.len(), Some(self.table.len()))
+    }
+}
+
+impl<V> FusedIterator for IntoIter<V> {}
+
+impl<V> DoubleEndedIterator for IntoIter<V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // SAFETY: As in `next`, but scanning down from `end`, which is always greater
+        // than `index` inside the loop.
+        unsafe {
+            while !self.table.is_empty() && self.end > self.index {
+                self.end -= 1;
+                if self.table.is_occupied(self.end) {
+                    return Some(self.take(self.end));
+                }
             }
 
             None
@@ -2751,10 +5293,16 @@ impl<V> Iterator for IntoIter<V> {
     }
 }
 
+impl<V> ExactSizeIterator for IntoIter<V> {
+    fn len(&self) -> usize {
+        self.table.len()
+    }
+}
+
 /// An iterator that removes and yields all elements matching a predicate.
 pub struct ExtractIf<'a, V, F, R> {
     table: &'a mut HashTable<V>,
-    index: usize,
+    slots: OccupiedSlots,
     filter: F,
     rehash: R,
 }
@@ -2767,49 +5315,15 @@ where
     type Item = V;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.table.is_empty() {
-            return None;
-        }
-
-        while self.index < (self.table.max_root_mask.wrapping_add(1) + HOP_RANGE) * LANES {
-            let idx = self.index;
-            self.index += 1;
-            // SAFETY: The call to the `unsafe` function `is_occupied` is safe here
-            // because we are iterating from `0` to the total number of slots,
-            // which is the exact size of the tags array. This ensures that the
-            // index `idx` is always within bounds.
-            if unsafe { self.table.is_occupied(idx) } {
-                // SAFETY: We have validated `idx` through `is_occupied`, and the bucket
-                // is confirmed to be initialized by an occupied tag.
-                let value = unsafe {
-                    self.table
-                        .buckets_ptr()
-                        .as_mut()
-                        .get_unchecked_mut(idx)
-                        .assume_init_mut()
-                };
+        // SAFETY: The scan was started for `self.table`, and the iterator only
+        // removes slots the scan has yielded.
+        while let Some(idx) = unsafe { self.slots.next(self.table) } {
+            // SAFETY: `idx` is within bounds, and its tag marks it as occupied, so the
+            // bucket is initialized.
+            unsafe {
+                let value = self.table.value_ptr(idx).as_mut().assume_init_mut();
                 if (self.filter)(value) {
-                    self.table.populated -= 1;
-                    // SAFETY: We have validated `idx` through `is_occupied`, and the bucket
-                    // is confirmed to be initialized by an occupied tag.
-                    unsafe {
-                        self.table.clear_occupied(idx);
-                        let hash = (self.rehash)(value);
-                        let hop_bucket = self.table.hopmap_index(hash);
-                        self.table
-                            .hopmap_ptr()
-                            .as_mut()
-                            .get_unchecked_mut(hop_bucket)
-                            .clear((idx - hop_bucket * LANES) / LANES);
-
-                        return Some(
-                            self.table
-                                .buckets_ptr()
-                                .as_mut()
-                                .get_unchecked_mut(idx)
-                                .assume_init_read(),
-                        );
-                    };
+                    return Some(self.table.remove_occupied(idx, &self.rehash));
                 }
             }
         }
@@ -2818,8 +5332,180 @@ where
     }
 }
 
+impl<V, F, R> FusedIterator for ExtractIf<'_, V, F, R>
+where
+    F: FnMut(&mut V) -> bool,
+    R: Fn(&V) -> u64,
+{
+}
+
+impl<V, F, R> ExtractIf<'_, V, F, R>
+where
+    R: Fn(&V) -> u64,
+{
+    /// Shrinks the underlying table if its load has dropped below `min_load`.
+    pub(crate) fn shrink_if_below(
+        &mut self,
+        min_load: f64,
+    ) {
+        self.table.shrink_if_below(min_load, &self.rehash);
+    }
+}
+
+/// An iterator over the values of a [`HashTable`] that do not match a
+/// predicate.
+///
+/// This struct is created by the [`filter_out`] method on [`HashTable`].
+///
+/// [`filter_out`]: HashTable::filter_out
+pub struct FilterOut<'a, V, F> {
+    table: &'a HashTable<V>,
+    slots: OccupiedSlots,
+    pred: F,
+}
+
+impl<'a, V, F> Iterator for FilterOut<'a, V, F>
+where
+    F: FnMut(&V) -> bool,
+{
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // SAFETY: The scan was started for `self.table`, which the shared borrow
+        // keeps unmodified.
+        while let Some(idx) = unsafe { self.slots.next(self.table) } {
+            // SAFETY: `idx` is within bounds, and its tag marks it as occupied, so the
+            // bucket is initialized.
+            let value = unsafe { self.table.value_ptr(idx).as_ref().assume_init_ref() };
+            if !(self.pred)(value) {
+                return Some(value);
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.slots.len()))
+    }
+}
+
+impl<V, F> FusedIterator for FilterOut<'_, V, F> where F: FnMut(&V) -> bool {}
+
+/// A lazy batch lookup over a [`HashTable`].
+///
+/// This struct is created by the [`find_stream`] method on [`HashTable`].
+///
+/// [`find_stream`]: HashTable::find_stream
+pub struct FindStream<'a, V, I, F> {
+    table: &'a HashTable<V>,
+    queries: I,
+    /// Queries that have been pulled and prefetched but not resolved yet.
+    pending: VecDeque<(u64, F)>,
+}
+
+impl<'a, V, I, F> Iterator for FindStream<'a, V, I, F>
+where
+    I: Iterator<Item = (u64, F)>,
+    F: Fn(&V) -> bool,
+{
+    type Item = Option<&'a V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.len() <= FIND_STREAM_PREFETCH_DISTANCE {
+            match self.queries.next() {
+                Some((hash, eq)) => {
+                    self.table.prefetch_hash(hash);
+                    self.pending.push_back((hash, eq));
+                }
+                None => break,
+            }
+        }
+
+        let (hash, eq) = self.pending.pop_front()?;
+        Some(self.table.find(hash, eq))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.queries.size_hint();
+        let pending = self.pending.len();
+        (
+            lower.saturating_add(pending),
+            upper.and_then(|upper| upper.checked_add(pending)),
+        )
+    }
+}
+
+/// A cursor over the occupied slots of a [`HashTable`].
+///
+/// This struct is created by the [`cursor`] method on [`HashTable`]. It is
+/// always positioned either at an occupied slot or past the end of the table.
+///
+/// [`cursor`]: HashTable::cursor
+pub struct SlotCursor<'a, V, R> {
+    table: &'a mut HashTable<V>,
+    index: usize,
+    rehash: R,
+}
+
+impl<V, R> SlotCursor<'_, V, R>
+where
+    R: Fn(&V) -> u64,
+{
+    /// Moves forward from `index` to the next occupied slot, or to the end.
+    fn seek(&mut self) {
+        let total_slots = self.table.slot_capacity();
+        // SAFETY: `self.index` is checked against `total_slots` before each access.
+        while self.index < total_slots && !unsafe { self.table.is_occupied(self.index) } {
+            self.index += 1;
+        }
+    }
+
+    /// Returns the slot index the cursor is positioned at, or `None` if it is
+    /// past the end.
+    pub fn slot(&self) -> Option<usize> {
+        (self.index < self.table.slot_capacity()).then_some(self.index)
+    }
+
+    /// Returns a reference to the value under the cursor.
+    pub fn current(&self) -> Option<&V> {
+        let index = self.slot()?;
+        // SAFETY: The cursor is only ever positioned at an occupied slot or past the
+        // end, and `slot` ruled out the latter.
+        Some(unsafe { self.table.value_ptr(index).as_ref().assume_init_ref() })
+    }
+
+    /// Returns a mutable reference to the value under the cursor.
+    pub fn current_mut(&mut self) -> Option<&mut V> {
+        let index = self.slot()?;
+        // SAFETY: The cursor is only ever positioned at an occupied slot or past the
+        // end, and `slot` ruled out the latter. We hold the table mutably.
+        Some(unsafe { self.table.value_ptr(index).as_mut().assume_init_mut() })
+    }
+
+    /// Moves the cursor to the next occupied slot.
+    pub fn advance(&mut self) {
+        if self.slot().is_some() {
+            self.index += 1;
+            self.seek();
+        }
+    }
+
+    /// Removes and returns the value under the cursor, then moves to the next
+    /// occupied slot.
+    pub fn remove_current(&mut self) -> Option<V> {
+        let idx = self.slot()?;
+        // SAFETY: The cursor is positioned at an occupied slot.
+        let value = unsafe { self.table.remove_occupied(idx, &self.rehash) };
+
+        self.index += 1;
+        self.seek();
+        Some(value)
+    }
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use alloc::string::String;
     use alloc::string::ToString;
     use alloc::vec;
@@ -3296,36 +5982,169 @@ mod tests {
         assert_eq!(cloned_hello.value, 1);
     }
 
-    #[test]
-    fn test_clone_empty_table() {
-        let original: HashTable<Item> = HashTable::with_capacity(10);
-        let cloned = original.clone();
-
-        assert_eq!(original.len(), 0);
-        assert_eq!(cloned.len(), 0);
-        assert!(original.is_empty());
-        assert!(cloned.is_empty());
-    }
-
-    #[test]
-    fn test_shrink_to_fit_empty_table() {
-        let mut table: HashTable<Item> = HashTable::with_capacity(100);
-        let initial_capacity = table.capacity();
-
-        assert!(initial_capacity > 0);
-        assert_eq!(table.len(), 0);
+    #[cfg(feature = "std")]
+    pub(crate) mod alloc_hooks {
+        use core::alloc::GlobalAlloc;
+        use core::alloc::Layout;
+        use core::cell::Cell;
+        use std::alloc::System;
+
+        std::thread_local! {
+            static FAIL: Cell<bool> = const { Cell::new(false) };
+            static ALLOCS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        /// Runs `f` with every allocation made on this thread failing.
+        pub fn with_failing_alloc<R>(f: impl FnOnce() -> R) -> R {
+            FAIL.with(|fail| fail.set(true));
+            let result = f();
+            FAIL.with(|fail| fail.set(false));
+            result
+        }
+
+        /// Runs `f`, returning its result and the number of allocations it
+        /// made on this thread.
+        pub fn count_allocs<R>(f: impl FnOnce() -> R) -> (R, usize) {
+            let before = ALLOCS.with(|allocs| allocs.get());
+            let result = f();
+            let after = ALLOCS.with(|allocs| allocs.get());
+            (result, after - before)
+        }
+
+        struct HookedAlloc;
+
+        // SAFETY: Forwards to the system allocator, or reports failure with a
+        // null pointer which is always allowed.
+        unsafe impl GlobalAlloc for HookedAlloc {
+            unsafe fn alloc(
+                &self,
+                layout: Layout,
+            ) -> *mut u8 {
+                if FAIL.with(|fail| fail.get()) {
+                    return core::ptr::null_mut();
+                }
+                ALLOCS.with(|allocs| allocs.set(allocs.get() + 1));
+                unsafe { System.alloc(layout) }
+            }
 
-        table.shrink_to_fit(|_| panic!("should not be called"));
+            unsafe fn dealloc(
+                &self,
+                ptr: *mut u8,
+                layout: Layout,
+            ) {
+                unsafe { System.dealloc(ptr, layout) }
+            }
+        }
 
-        assert_eq!(table.len(), 0);
-        assert_eq!(table.capacity(), 0);
+        #[global_allocator]
+        static GLOBAL: HookedAlloc = HookedAlloc;
     }
 
     #[test]
-    fn test_shrink_to_fit_with_items() {
+    #[cfg(feature = "std")]
+    fn try_reserve_reports_alloc_failure() {
         let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(1000);
-
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: k,
+                    value: k as i32,
+                });
+        }
+        let capacity = table.capacity();
+
+        let result = alloc_hooks::with_failing_alloc(|| {
+            table.try_reserve(10_000, |v| hash_key(&state, v.key))
+        });
+        assert!(matches!(result, Err(TryReserveError::AllocError { .. })));
+        assert_eq!(table.capacity(), capacity);
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            assert_eq!(table.find(hash, |v| v.key == k).unwrap().value, k as i32);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn with_capacity_overflow_panics() {
+        let _table: HashTable<u64> = HashTable::with_capacity(usize::MAX);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[should_panic(expected = "requested capacity 72057594037927936 for V of size 256 overflows")]
+    fn with_capacity_layout_overflow_names_request() {
+        let _table: HashTable<[u8; 256]> = HashTable::with_capacity(1 << 56);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn try_clone_reports_alloc_failure() {
+        let state = HashState::default();
+        let mut original: HashTable<StringItem> = HashTable::with_capacity(0);
+        for k in 0..100u64 {
+            let key = k.to_string();
+            let hash = hash_string_key(&state, &key);
+            original
+                .entry(hash, |v| v.key == key, |v| hash_string_key(&state, &v.key))
+                .or_insert(StringItem {
+                    key: key.clone(),
+                    value: k as i32,
+                });
+        }
+
+        let result = alloc_hooks::with_failing_alloc(|| original.try_clone());
+        match result {
+            Err(TryReserveError::AllocError { layout }) => {
+                assert_eq!(layout, original.layout.layout)
+            }
+            Err(e) => panic!("wrong error: {:?}", e),
+            Ok(_) => panic!("clone should fail"),
+        }
+
+        assert_eq!(original.len(), 100);
+        let cloned = original.try_clone().expect("allocation should succeed");
+        assert_eq!(cloned.len(), 100);
+        for k in 0..100u64 {
+            let key = k.to_string();
+            let hash = hash_string_key(&state, &key);
+            assert_eq!(cloned.find(hash, |v| v.key == key).unwrap().value, k as i32);
+        }
+    }
+
+    #[test]
+    fn test_clone_empty_table() {
+        let original: HashTable<Item> = HashTable::with_capacity(10);
+        let cloned = original.clone();
+
+        assert_eq!(original.len(), 0);
+        assert_eq!(cloned.len(), 0);
+        assert!(original.is_empty());
+        assert!(cloned.is_empty());
+    }
+
+    #[test]
+    fn test_shrink_to_fit_empty_table() {
+        let mut table: HashTable<Item> = HashTable::with_capacity(100);
+        let initial_capacity = table.capacity();
+
+        assert!(initial_capacity > 0);
+        assert_eq!(table.len(), 0);
+
+        table.shrink_to_fit(|_| panic!("should not be called"));
+
+        assert_eq!(table.len(), 0);
+        assert_eq!(table.capacity(), 0);
+    }
+
+    #[test]
+    fn test_shrink_to_fit_with_items() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(1000);
+
         for i in 0..50 {
             let hash = hash_key(&state, i);
             table
@@ -3454,6 +6273,79 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn resize_allocates_exactly_once() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for i in 0..100 {
+            let hash = hash_key(&state, i);
+            table
+                .entry(hash, |v| v.key == i, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: i,
+                    value: i as i32,
+                });
+        }
+
+        let (resized, allocs) = alloc_hooks::count_allocs(|| {
+            table.reserve_reporting(10_000, |v| hash_key(&state, v.key))
+        });
+        assert!(resized);
+        assert_eq!(allocs, 1);
+
+        for i in 0..100 {
+            let hash = hash_key(&state, i);
+            assert_eq!(table.find(hash, |v| v.key == i).unwrap().value, i as i32);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_shrink_to_fit_redundant_does_not_allocate() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for i in 0..50 {
+            let hash = hash_key(&state, i);
+            table
+                .entry(hash, |v| v.key == i, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: i,
+                    value: i as i32,
+                });
+        }
+        table.shrink_to_fit(|k| hash_key(&state, k.key));
+        let capacity = table.capacity();
+
+        let ((), allocs) = alloc_hooks::count_allocs(|| {
+            table.shrink_to_fit(|_| panic!("should not rehash"));
+        });
+        assert_eq!(allocs, 0);
+        assert_eq!(table.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_shrink_to_fit_minimal_table_is_noop() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(1);
+        for i in 0..4 {
+            let hash = hash_key(&state, i);
+            table
+                .entry(hash, |v| v.key == i, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: i,
+                    value: i as i32,
+                });
+        }
+
+        let capacity = table.capacity();
+        let alloc = table.alloc;
+        table.shrink_to_fit(|_| panic!("minimal table should not rehash"));
+        assert_eq!(table.capacity(), capacity);
+        assert_eq!(table.alloc, alloc);
+        assert_eq!(table.len(), 4);
+    }
+
     #[test]
     fn test_shrink_to_fit_preserves_functionality() {
         let state = HashState::default();
@@ -3603,44 +6495,107 @@ mod tests {
     }
 
     #[test]
-    fn retain_empty_table() {
-        let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(0);
-        table.retain(|_| true, |v| hash_key(&state, v.key));
-        assert_eq!(table.len(), 0);
-    }
-
-    #[test]
-    fn retain_all() {
+    fn drain_map_transforms_and_empties() {
         let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(0);
-
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
         for k in 0..100u64 {
             let hash = hash_key(&state, k);
             table
-                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
-                .or_insert(Item {
-                    key: k,
-                    value: k as i32,
-                });
+                .entry(hash, |v| *v == k, |v| hash_key(&state, *v))
+                .or_insert(k);
         }
 
-        assert_eq!(table.len(), 100);
-        table.retain(|_| true, |v| hash_key(&state, v.key));
-        assert_eq!(table.len(), 100);
+        let mut drained: Vec<String> = table.drain_map(|v| v.to_string()).collect();
+        drained.sort_by_key(|s| s.parse::<u64>().unwrap());
+        assert_eq!(
+            drained,
+            (0..100u64).map(|k| k.to_string()).collect::<Vec<_>>()
+        );
+        assert!(table.is_empty());
+        assert!(table.iter().next().is_none());
 
-        for k in 0..100u64 {
+        for k in 0..10u64 {
             let hash = hash_key(&state, k);
-            assert!(table.find(hash, |v| v.key == k).is_some());
+            table
+                .entry(hash, |v| *v == k, |v| hash_key(&state, *v))
+                .or_insert(k);
         }
+        let mut partial = table.drain_map(|v| v * 2);
+        assert!(partial.next().is_some());
+        drop(partial);
+        assert!(table.is_empty());
     }
 
     #[test]
-    fn retain_none() {
-        let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+    fn once_variants_accept_stateful_predicates() {
+        let mut table: HashTable<u64> = HashTable::with_capacity(16);
+        let hash = 0x1234_5678_u64;
+        for v in 0..3u64 {
+            table.entry(hash, |x| *x == v, |_| hash).or_insert(v);
+        }
+        // Same root bucket, different tag.
+        let other = (1 << 60) | hash;
+        table.entry(other, |x| *x == 99, |_| other).or_insert(99);
+
+        let mut examined = 0;
+        let found = table.find_once(hash, |x| {
+            examined += 1;
+            *x == 2
+        });
+        assert_eq!(found, Some(&2));
+        assert!((1..=3).contains(&examined));
+
+        // Only candidates whose tags match are examined.
+        let mut examined = Vec::new();
+        let found = table.find_mut_once(hash, |x| {
+            examined.push(*x);
+            false
+        });
+        assert!(found.is_none());
+        examined.sort_unstable();
+        assert_eq!(examined, vec![0, 1, 2]);
+
+        let needle = 7u64;
+        let mut examined = 0;
+        match table.entry_once(
+            hash,
+            move |x| {
+                examined += 1;
+                assert!(examined <= 3);
+                *x == needle
+            },
+            |_| hash,
+        ) {
+            Entry::Vacant(entry) => {
+                entry.insert(needle);
+            }
+            Entry::Occupied(_) => panic!("7 was never inserted"),
+        }
+        assert_eq!(table.len(), 5);
 
-        for k in 0..100u64 {
+        match table.entry_once(hash, |x| *x == 2, |_| hash) {
+            Entry::Occupied(mut entry) => *entry.get_mut() = 20,
+            Entry::Vacant(_) => panic!("2 is present"),
+        }
+        assert!(table.find(hash, |x| *x == 20).is_some());
+
+        let mut examined = 0;
+        assert_eq!(
+            table.find_once(hash, |_| {
+                examined += 1;
+                false
+            }),
+            None
+        );
+        assert_eq!(examined, 4);
+    }
+
+    #[test]
+    fn for_each_visits_every_value() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(1000);
+        let mut expected = 0i64;
+        for k in 0..300u64 {
             let hash = hash_key(&state, k);
             table
                 .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
@@ -3648,107 +6603,356 @@ mod tests {
                     key: k,
                     value: k as i32,
                 });
-        }
+            expected += k as i64;
+        }
+
+        let mut sum = 0i64;
+        let mut count = 0;
+        table.for_each(|v| {
+            sum += v.value as i64;
+            count += 1;
+        });
+        assert_eq!(sum, expected);
+        assert_eq!(count, 300);
+
+        table.for_each_mut(|v| v.value *= 2);
+        let mut doubled = 0i64;
+        table.for_each(|v| doubled += v.value as i64);
+        assert_eq!(doubled, expected * 2);
+        assert_eq!(
+            table.iter().map(|v| v.value as i64).sum::<i64>(),
+            expected * 2
+        );
 
-        assert_eq!(table.len(), 100);
-        table.retain(|_| false, |v| hash_key(&state, v.key));
-        assert_eq!(table.len(), 0);
-        assert!(table.is_empty());
+        let empty: HashTable<Item> = HashTable::with_capacity(0);
+        empty.for_each(|_| panic!("empty table has no values"));
+    }
 
-        for k in 0..100u64 {
-            let hash = hash_key(&state, k);
-            assert!(table.find(hash, |v| v.key == k).is_none());
+    #[test]
+    fn find_where_skips_tag_matches_rejected_by_predicate() {
+        #[derive(Debug, PartialEq)]
+        enum Tagged {
+            A(u32),
+            B(u32),
         }
+
+        let mut table: HashTable<Tagged> = HashTable::with_capacity(16);
+        let hash = 0xDEAD_BEEF_u64;
+        table
+            .entry(hash, |v| *v == Tagged::A(1), |_| hash)
+            .or_insert(Tagged::A(1));
+        table
+            .entry(hash, |v| *v == Tagged::B(2), |_| hash)
+            .or_insert(Tagged::B(2));
+
+        assert_eq!(
+            table.find_where(hash, |v| matches!(v, Tagged::B(_))),
+            Some(&Tagged::B(2))
+        );
+        assert_eq!(
+            table.find_where(hash, |v| matches!(v, Tagged::A(_))),
+            Some(&Tagged::A(1))
+        );
+        assert_eq!(table.find_where(hash, |v| matches!(v, Tagged::B(3))), None);
     }
 
     #[test]
-    fn retain_even_keys() {
+    fn merge_resolves_overlapping_values() {
         let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        let mut left: HashTable<Item> = HashTable::with_capacity(0);
+        let mut right: HashTable<Item> = HashTable::with_capacity(0);
 
         for k in 0..100u64 {
             let hash = hash_key(&state, k);
-            table
+            left.entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item { key: k, value: 1 });
+        }
+        for k in 50..150u64 {
+            let hash = hash_key(&state, k);
+            right
                 .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
-                .or_insert(Item {
-                    key: k,
-                    value: k as i32,
-                });
+                .or_insert(Item { key: k, value: 10 });
         }
 
-        assert_eq!(table.len(), 100);
-        table.retain(|item| item.key % 2 == 0, |v| hash_key(&state, v.key));
-        assert_eq!(table.len(), 50);
+        left.merge(
+            right,
+            |a, b| a.key == b.key,
+            |v| hash_key(&state, v.key),
+            |existing, incoming| existing.value += incoming.value,
+        );
 
-        for k in 0..100u64 {
+        assert_eq!(left.len(), 150);
+        for k in 0..150u64 {
+            let expected = match k {
+                0..50 => 1,
+                50..100 => 11,
+                _ => 10,
+            };
             let hash = hash_key(&state, k);
-            if k % 2 == 0 {
-                assert!(
-                    table.find(hash, |v| v.key == k).is_some(),
-                    "even key {} should be present",
-                    k
-                );
-            } else {
-                assert!(
-                    table.find(hash, |v| v.key == k).is_none(),
-                    "odd key {} should be absent",
-                    k
-                );
-            }
+            assert_eq!(left.find(hash, |v| v.key == k).unwrap().value, expected);
         }
     }
 
     #[test]
-    fn retain_by_value() {
+    fn capacity_fits_flips_at_resize_boundary() {
         let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        let mut table: HashTable<Item> = HashTable::with_capacity(64);
+        let capacity = table.capacity();
 
-        for k in 0..50u64 {
+        assert!(table.capacity_fits(capacity));
+        assert!(!table.capacity_fits(capacity + 1));
+        assert!(!table.capacity_fits(usize::MAX));
+
+        // At the higher densities a full neighborhood can grow the table before
+        // it reaches `capacity`, so fill whatever capacity the table ends up with.
+        let mut k = 0;
+        while table.len() < table.capacity() {
             let hash = hash_key(&state, k);
             table
                 .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
-                .or_insert(Item {
-                    key: k,
-                    value: (k as i32) * 10,
-                });
+                .or_insert(Item { key: k, value: 0 });
+            k += 1;
         }
 
-        table.retain(|item| item.value < 250, |v| hash_key(&state, v.key));
-        assert_eq!(table.len(), 25);
-
-        for k in 0..50u64 {
-            let hash = hash_key(&state, k);
-            let expected_value = (k as i32) * 10;
-            if expected_value < 250 {
-                assert!(table.find(hash, |v| v.key == k).is_some());
-            } else {
-                assert!(table.find(hash, |v| v.key == k).is_none());
-            }
-        }
+        assert!(table.capacity_fits(0));
+        assert!(!table.capacity_fits(1));
+        assert!(!table.reserve_reporting(0, |v| hash_key(&state, v.key)));
+        assert!(table.reserve_reporting(1, |v| hash_key(&state, v.key)));
     }
 
     #[test]
-    fn retain_mut_empty_table() {
+    fn reserve_reporting_only_reports_real_resizes() {
         let state = HashState::default();
         let mut table: HashTable<Item> = HashTable::with_capacity(0);
-        table.retain_mut(|_| true, |v| hash_key(&state, v.key));
-        assert_eq!(table.len(), 0);
+
+        assert!(table.reserve_reporting(100, |v| hash_key(&state, v.key)));
+        let capacity = table.capacity();
+        assert!(!table.reserve_reporting(100, |v| hash_key(&state, v.key)));
+        assert_eq!(table.capacity(), capacity);
+        assert!(!table.reserve_reporting(0, |v| hash_key(&state, v.key)));
     }
 
     #[test]
-    fn retain_mut_modify_and_keep() {
+    #[cfg(feature = "std")]
+    fn reserve_small_amounts_from_empty() {
         let state = HashState::default();
         let mut table: HashTable<Item> = HashTable::with_capacity(0);
 
-        for k in 0..50u64 {
-            let hash = hash_key(&state, k);
-            table
-                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
-                .or_insert(Item {
-                    key: k,
-                    value: k as i32,
-                });
-        }
+        let (resized, allocs) =
+            alloc_hooks::count_allocs(|| table.reserve_reporting(0, |v| hash_key(&state, v.key)));
+        assert!(!resized);
+        assert_eq!(allocs, 0);
+        assert_eq!(table.capacity(), 0);
+
+        let (resized, allocs) =
+            alloc_hooks::count_allocs(|| table.reserve_reporting(1, |v| hash_key(&state, v.key)));
+        assert!(resized);
+        assert_eq!(allocs, 1);
+        let capacity = table.capacity();
+        assert!(capacity >= 1);
+
+        let (resized, allocs) = alloc_hooks::count_allocs(|| {
+            table.reserve_reporting(1, |v| hash_key(&state, v.key))
+                | table.reserve_reporting(0, |v| hash_key(&state, v.key))
+        });
+        assert!(!resized);
+        assert_eq!(allocs, 0);
+        assert_eq!(table.capacity(), capacity);
+    }
+
+    #[test]
+    fn small_capacities_never_have_zero_max_pop() {
+        for requested in 1..=1024 {
+            let table: HashTable<u64> = HashTable::with_capacity(requested);
+            assert!(table.capacity() >= requested);
+        }
+
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(1);
+        let hash = hash_key(&state, 7);
+        table
+            .entry(hash, |v| v.key == 7, |v| hash_key(&state, v.key))
+            .or_insert(Item { key: 7, value: 1 });
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.find(hash, |v| v.key == 7).unwrap().value, 1);
+    }
+
+    #[test]
+    fn entry_slot_indexes_sidecar() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(100);
+        let mut sidecar = vec![0u64; table.slot_capacity()];
+
+        for k in 0..50u64 {
+            let hash = hash_key(&state, k);
+            match table.entry(hash, |v| v.key == k, |v| hash_key(&state, v.key)) {
+                Entry::Vacant(v) => {
+                    let slot = v.slot();
+                    assert!(slot < sidecar.len());
+                    sidecar[slot] = k * 3;
+                    v.insert(Item { key: k, value: 0 });
+                }
+                Entry::Occupied(_) => unreachable!(),
+            }
+        }
+
+        assert_eq!(table.slot_capacity(), sidecar.len());
+        for k in 0..50u64 {
+            let hash = hash_key(&state, k);
+            match table.entry(hash, |v| v.key == k, |v| hash_key(&state, v.key)) {
+                Entry::Occupied(o) => assert_eq!(sidecar[o.slot()], k * 3),
+                Entry::Vacant(_) => panic!("key {} should be present", k),
+            }
+        }
+    }
+
+    #[test]
+    fn retain_empty_table() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        table.retain(|_| true, |v| hash_key(&state, v.key));
+        assert_eq!(table.len(), 0);
+    }
+
+    #[test]
+    fn retain_all() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: k,
+                    value: k as i32,
+                });
+        }
+
+        assert_eq!(table.len(), 100);
+        table.retain(|_| true, |v| hash_key(&state, v.key));
+        assert_eq!(table.len(), 100);
+
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            assert!(table.find(hash, |v| v.key == k).is_some());
+        }
+    }
+
+    #[test]
+    fn retain_none() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: k,
+                    value: k as i32,
+                });
+        }
+
+        assert_eq!(table.len(), 100);
+        table.retain(|_| false, |v| hash_key(&state, v.key));
+        assert_eq!(table.len(), 0);
+        assert!(table.is_empty());
+
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            assert!(table.find(hash, |v| v.key == k).is_none());
+        }
+    }
+
+    #[test]
+    fn retain_even_keys() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: k,
+                    value: k as i32,
+                });
+        }
+
+        assert_eq!(table.len(), 100);
+        table.retain(|item| item.key % 2 == 0, |v| hash_key(&state, v.key));
+        assert_eq!(table.len(), 50);
+
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            if k % 2 == 0 {
+                assert!(
+                    table.find(hash, |v| v.key == k).is_some(),
+                    "even key {} should be present",
+                    k
+                );
+            } else {
+                assert!(
+                    table.find(hash, |v| v.key == k).is_none(),
+                    "odd key {} should be absent",
+                    k
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn retain_by_value() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+
+        for k in 0..50u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: k,
+                    value: (k as i32) * 10,
+                });
+        }
+
+        table.retain(|item| item.value < 250, |v| hash_key(&state, v.key));
+        assert_eq!(table.len(), 25);
+
+        for k in 0..50u64 {
+            let hash = hash_key(&state, k);
+            let expected_value = (k as i32) * 10;
+            if expected_value < 250 {
+                assert!(table.find(hash, |v| v.key == k).is_some());
+            } else {
+                assert!(table.find(hash, |v| v.key == k).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn retain_mut_empty_table() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        table.retain_mut(|_| true, |v| hash_key(&state, v.key));
+        assert_eq!(table.len(), 0);
+    }
+
+    #[test]
+    fn retain_mut_modify_and_keep() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+
+        for k in 0..50u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: k,
+                    value: k as i32,
+                });
+        }
 
         table.retain_mut(
             |item| {
@@ -4082,288 +7286,1960 @@ mod tests {
     }
 
     #[test]
-    fn extract_if_maintains_table_integrity() {
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "hash/eq inconsistency")]
+    fn inconsistent_rehash_detected_on_resize() {
+        let hash_of = |k: u64| k.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        for k in 0..100u64 {
+            table
+                .entry(hash_of(k), |v| *v == k, |v| hash_of(*v))
+                .or_insert(k);
+        }
+
+        // Inverting the hash flips every tag bit.
+        table.reserve(10_000, |v| !hash_of(*v));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "hash/eq inconsistency")]
+    fn inconsistent_rehash_detected_on_retain() {
+        let hash_of = |k: u64| k.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        for k in 0..100u64 {
+            table
+                .entry(hash_of(k), |v| *v == k, |v| hash_of(*v))
+                .or_insert(k);
+        }
+
+        table.retain(|v| v % 2 == 0, |v| !hash_of(*v));
+    }
+
+    #[test]
+    fn find_index_then_remove_index() {
         let state = HashState::default();
         let mut table: HashTable<Item> = HashTable::with_capacity(0);
-
-        for k in 0..500u64 {
+        for k in 0..200u64 {
             let hash = hash_key(&state, k);
             table
                 .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
                 .or_insert(Item {
                     key: k,
-                    value: k as i32,
+                    value: k as i32 * 3,
                 });
         }
 
-        let _extracted: Vec<Item> = table
-            .extract_if(|item| item.key % 3 == 0, |v| hash_key(&state, v.key))
-            .collect();
+        let hash = hash_key(&state, 42);
+        let index = table.find_index(hash, |v| v.key == 42).unwrap();
+        assert!(
+            table
+                .find_index(hash_key(&state, 500), |v| v.key == 500)
+                .is_none()
+        );
 
-        for k in 0..500u64 {
+        let removed = table.remove_index(index, |v| hash_key(&state, v.key));
+        assert_eq!(
+            removed,
+            Some(Item {
+                key: 42,
+                value: 126
+            })
+        );
+        assert_eq!(table.len(), 199);
+        assert!(table.find(hash, |v| v.key == 42).is_none());
+
+        assert!(
+            table
+                .remove_index(index, |v| hash_key(&state, v.key))
+                .is_none()
+        );
+        assert!(
+            table
+                .remove_index(table.slot_capacity(), |v| hash_key(&state, v.key))
+                .is_none()
+        );
+
+        for k in (0..200u64).filter(|&k| k != 42) {
             let hash = hash_key(&state, k);
-            let found = table.find(hash, |v| v.key == k);
-            if k % 3 == 0 {
-                assert!(found.is_none(), "key {} should be extracted", k);
-            } else {
-                assert!(found.is_some(), "key {} should remain", k);
-                assert_eq!(found.unwrap().value, k as i32);
-            }
+            assert_eq!(
+                table.find(hash, |v| v.key == k).unwrap().value,
+                k as i32 * 3
+            );
         }
     }
 
     #[test]
-    fn try_entry_vacant_success() {
-        let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(100);
-
-        let k = 42u64;
-        let hash = hash_key(&state, k);
+    #[cfg(feature = "stats")]
+    fn bubble_count_tracks_displaced_inserts() {
+        let mut table: HashTable<u64> = HashTable::with_capacity(1000);
+        let insert = |table: &mut HashTable<u64>, hash: u64| {
+            table.entry(hash, |&v| v == hash, |&v| v).or_insert(hash);
+        };
 
-        match table.try_entry(hash, |v| v.key == k) {
-            Ok(Entry::Vacant(v)) => {
-                v.insert(Item { key: k, value: 100 });
+        // Fill every bucket of bucket 0's neighborhood with values rooted at
+        // that bucket, so nothing has to move yet.
+        for bucket in 0..HOP_RANGE as u64 {
+            for lane in 0..LANES as u64 {
+                insert(&mut table, (lane << 57) | (lane << 32) | bucket);
             }
-            Ok(Entry::Occupied(_)) => panic!("should be vacant"),
-            Err(e) => panic!("should succeed: {:?}", e),
         }
+        assert_eq!(table.bubble_count(), 0);
 
-        assert_eq!(table.len(), 1);
-        assert_eq!(
-            table.find(hash, |v| v.key == k),
-            Some(&Item { key: k, value: 100 })
-        );
+        // The nearest empty slot for another bucket 0 value is one bucket past
+        // its neighborhood, so a value from bucket 1 has to make room.
+        insert(&mut table, 1 << 40);
+        assert_eq!(table.bubble_count(), 1);
+        assert_eq!(table.len(), HOP_RANGE * LANES + 1);
+
+        table.reset_bubble_count();
+        assert_eq!(table.bubble_count(), 0);
     }
 
     #[test]
-    fn try_entry_occupied_success() {
-        let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(100);
+    fn debug_output_is_truncated_for_large_tables() {
+        use alloc::format;
 
-        let k = 42u64;
-        let hash = hash_key(&state, k);
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        for i in 0..100_000u64 {
+            let hash = i.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            table
+                .entry(
+                    hash,
+                    |&v| v == i,
+                    |&v| v.wrapping_mul(0x9e37_79b9_7f4a_7c15),
+                )
+                .or_insert(i);
+        }
 
-        match table.try_entry(hash, |v| v.key == k) {
-            Ok(Entry::Vacant(v)) => {
-                v.insert(Item { key: k, value: 100 });
-            }
-            _ => panic!("first should be vacant"),
-        }
+        let short = format!("{table:?}");
+        assert!(short.len() < 32 * 1024, "{} bytes", short.len());
+        assert!(short.contains("more buckets"));
 
-        match table.try_entry(hash, |v| v.key == k) {
-            Ok(Entry::Occupied(mut occ)) => {
-                assert_eq!(occ.get().value, 100);
-                occ.get_mut().value = 200;
-            }
-            Ok(Entry::Vacant(_)) => panic!("should be occupied"),
-            Err(e) => panic!("should succeed: {:?}", e),
-        }
+        let long = format!("{table:#?}");
+        assert!(long.len() > short.len());
+        assert!(long.len() < 512 * 1024, "{} bytes", long.len());
+        assert!(long.contains("more buckets"));
 
-        assert_eq!(table.len(), 1);
-        assert_eq!(
-            table.find(hash, |v| v.key == k),
-            Some(&Item { key: k, value: 200 })
-        );
+        let mut small: HashTable<u64> = HashTable::with_capacity(0);
+        small.entry(1, |&v| v == 1, |&v| v).or_insert(1);
+        assert!(!format!("{small:?}").contains("more buckets"));
     }
 
     #[test]
-    fn try_entry_capacity_too_small() {
+    fn drain_with_applies_capacity_policy() {
         let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(10);
-
-        let capacity = table.capacity();
-        for k in 0..capacity as u64 {
-            let hash = hash_key(&state, k);
-            match table.try_entry(hash, |v| v.key == k) {
-                Ok(Entry::Vacant(v)) => {
-                    v.insert(Item {
-                        key: k,
+        let fill = |table: &mut HashTable<StringItem>| {
+            for k in 0..500u64 {
+                let key = k.to_string();
+                let hash = hash_string_key(&state, &key);
+                table
+                    .entry(hash, |v| v.key == key, |v| hash_string_key(&state, &v.key))
+                    .or_insert(StringItem {
+                        key: key.clone(),
                         value: k as i32,
                     });
-                }
-                Err(e) => panic!("should succeed before capacity: {:?}", e),
-                _ => panic!("should be vacant"),
             }
-        }
+        };
 
-        assert_eq!(table.len(), capacity);
+        let mut table: HashTable<StringItem> = HashTable::with_capacity(0);
+        fill(&mut table);
+        let capacity = table.capacity();
 
-        let k = capacity as u64;
-        let hash = hash_key(&state, k);
-        match table.try_entry(hash, |v| v.key == k) {
-            Err(TryEntryError::CapacityTooSmall) => {}
-            Ok(_) => panic!("should fail with CapacityTooSmall"),
-            Err(e) => panic!("wrong error: {:?}", e),
-        }
+        assert_eq!(table.drain_with(DrainPolicy::KeepCapacity).count(), 500);
+        assert!(table.is_empty());
+        assert_eq!(table.capacity(), capacity);
+
+        fill(&mut table);
+        assert_eq!(table.capacity(), capacity);
+        assert_eq!(table.drain_with(DrainPolicy::ShrinkAfter).count(), 500);
+        assert!(table.is_empty());
+        assert_eq!(table.capacity(), 0);
+        assert_eq!(table.slot_capacity(), 0);
+
+        // Dropping a partially consumed drain still drops the rest before
+        // freeing the allocation.
+        fill(&mut table);
+        let mut drain = table.drain_with(DrainPolicy::ShrinkAfter);
+        assert!(drain.next().is_some());
+        drop(drain);
+        assert!(table.is_empty());
+        assert_eq!(table.capacity(), 0);
+
+        fill(&mut table);
+        assert_eq!(table.len(), 500);
+        let key = 42.to_string();
+        let hash = hash_string_key(&state, &key);
+        assert_eq!(table.find(hash, |v| v.key == key).unwrap().value, 42);
     }
 
     #[test]
-    fn try_entry_no_free_slot_in_range() {
-        let mut table: HashTable<Item> = HashTable::with_capacity(100);
+    fn cursor_sweep_removes_every_third() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in 0..300u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item { key: k, value: 0 });
+        }
+
+        let mut removed = Vec::new();
+        let mut visited = 0;
+        let mut last_slot = None;
+        let mut cursor = table.cursor(|v| hash_key(&state, v.key));
+        while let Some(slot) = cursor.slot() {
+            assert!(last_slot.is_none_or(|last| slot > last));
+            last_slot = Some(slot);
+            if visited % 3 == 0 {
+                removed.push(cursor.remove_current().unwrap().key);
+            } else {
+                cursor.current_mut().unwrap().value += 1;
+                cursor.advance();
+            }
+            visited += 1;
+        }
+        assert!(cursor.current().is_none());
+        assert!(cursor.remove_current().is_none());
 
-        let capacity = table.capacity();
-        let target_fill = (capacity * 9) / 10;
+        assert_eq!(visited, 300);
+        assert_eq!(removed.len(), 100);
+        assert_eq!(table.len(), 200);
+        for k in 0..300u64 {
+            let hash = hash_key(&state, k);
+            match table.find(hash, |v| v.key == k) {
+                Some(item) => {
+                    assert!(!removed.contains(&k));
+                    assert_eq!(item.value, 1);
+                }
+                None => assert!(removed.contains(&k)),
+            }
+        }
+    }
 
-        let mut inserted = 0;
-        let hash = 0u64;
+    #[test]
+    fn capacity_info_is_consistent() {
+        let empty: HashTable<u64> = HashTable::with_capacity(0);
+        let info = empty.capacity_info();
+        assert_eq!(info.live, 0);
+        assert_eq!(info.slots, 0);
+        assert_eq!(info.allocation_bytes, 0);
+        assert_eq!(info.load_factor, 0.0);
+
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        for k in 0..500u64 {
+            table.entry(k, |v| *v == k, |v| *v).or_insert(k);
+            let info = table.capacity_info();
+            assert!(info.live <= info.slots);
+            assert_eq!(info.live, table.capacity());
+            assert_eq!(info.slots, table.slot_capacity());
+            assert_eq!(info.allocation_bytes, table.layout.layout.size());
+            assert!(info.load_factor > 0.0 && info.load_factor <= 1.0);
+        }
+    }
 
-        while inserted < target_fill {
-            match table.try_entry(hash, |_| false) {
-                Ok(Entry::Vacant(v)) => {
-                    v.insert(Item {
-                        key: inserted as u64,
-                        value: inserted as i32,
-                    });
-                    inserted += 1;
-                }
-                Ok(Entry::Occupied(_)) => {}
-                Err(TryEntryError::NoFreeSlot) => break,
-                Err(TryEntryError::CapacityTooSmall) => break,
+    #[test]
+    fn iterators_stay_exhausted() {
+        fn assert_fused<I: FusedIterator>(mut iter: I) {
+            for _ in iter.by_ref() {}
+            for _ in 0..3 {
+                assert!(iter.next().is_none());
             }
         }
 
-        assert_eq!(
-            table.try_entry(hash, |_| false).err(),
-            Some(TryEntryError::NoFreeSlot)
-        );
-        assert!(!table.is_empty());
-        assert!(table.len() <= capacity);
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        for k in 0..50u64 {
+            table.entry(k, |v| *v == k, |v| *v).or_insert(k);
+        }
+
+        assert_fused(table.iter());
+        assert_fused(table.iter_mut());
+        assert_fused(table.clone().drain());
+        assert_fused(table.clone().drain_map(|v| v + 1));
+        assert_fused(table.clone().extract_if(|v| *v % 2 == 0, |v| *v));
+        assert_fused(table.into_iter());
     }
 
     #[test]
-    fn try_entry_multiple_insertions() {
+    fn entry_with_bucket_matches_entry() {
         let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(50);
+        let mut hinted: HashTable<Item> = HashTable::with_capacity(0);
+        let mut plain: HashTable<Item> = HashTable::with_capacity(0);
 
-        let keys = [1u64, 5, 10, 15, 20, 25, 30];
-
-        for &k in &keys {
+        for k in 0..1000u64 {
             let hash = hash_key(&state, k);
-            match table.try_entry(hash, |v| v.key == k) {
-                Ok(Entry::Vacant(v)) => {
-                    v.insert(Item {
-                        key: k,
-                        value: (k * 10) as i32,
-                    });
+            let bucket = hinted.bucket_index(hash);
+            let hinted_slot = match hinted.entry_with_bucket(
+                hash,
+                bucket,
+                |v| v.key == k,
+                |v| hash_key(&state, v.key),
+            ) {
+                Entry::Vacant(entry) => {
+                    let slot = entry.slot();
+                    entry.insert(Item { key: k, value: 0 });
+                    slot
                 }
-                Err(e) => panic!("insertion failed for key {}: {:?}", k, e),
-                _ => panic!("should be vacant for key {}", k),
-            }
+                Entry::Occupied(_) => unreachable!(),
+            };
+            let plain_slot = match plain.entry(hash, |v| v.key == k, |v| hash_key(&state, v.key)) {
+                Entry::Vacant(entry) => {
+                    let slot = entry.slot();
+                    entry.insert(Item { key: k, value: 0 });
+                    slot
+                }
+                Entry::Occupied(_) => unreachable!(),
+            };
+            assert_eq!(hinted_slot, plain_slot);
         }
 
-        assert_eq!(table.len(), keys.len());
-
-        for &k in &keys {
+        for k in 0..1000u64 {
             let hash = hash_key(&state, k);
-            assert_eq!(
-                table.find(hash, |v| v.key == k),
-                Some(&Item {
-                    key: k,
-                    value: (k * 10) as i32
-                })
-            );
+            let bucket = hinted.bucket_index(hash);
+            assert!(matches!(
+                hinted.entry_with_bucket(hash, bucket, |v| v.key == k, |v| hash_key(&state, v.key)),
+                Entry::Occupied(_)
+            ));
         }
     }
 
     #[test]
-    fn try_entry_does_not_resize() {
-        let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(10);
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "bucket hint does not match hash")]
+    fn entry_with_bucket_rejects_wrong_hint() {
+        let mut table: HashTable<u64> = HashTable::with_capacity(100);
+        let hash = 5u64;
+        let wrong = table.bucket_index(hash) + 1;
+        table.entry_with_bucket(hash, wrong, |v| *v == 5, |v| *v);
+    }
 
-        let initial_capacity = table.capacity();
+    #[test]
+    fn send_sync_follow_value_type() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
 
-        for k in 0..initial_capacity as u64 {
+        assert_send::<HashTable<u64>>();
+        assert_sync::<HashTable<u64>>();
+        assert_send::<crate::hash_map::HashMap<u64, String, HashState>>();
+        assert_sync::<crate::hash_map::HashMap<u64, String, HashState>>();
+        assert_send::<crate::hash_set::HashSet<u64, HashState>>();
+        assert_sync::<crate::hash_set::HashSet<u64, HashState>>();
+        assert_send::<IntoIter<u64>>();
+        assert_sync::<Iter<'_, u64>>();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn table_moves_to_another_thread() {
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        for k in 0..1000u64 {
+            table.entry(k, |v| *v == k, |v| *v).or_insert(k);
+        }
+
+        let sum = std::thread::spawn(move || {
+            assert_eq!(table.len(), 1000);
+            table.iter().sum::<u64>()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(sum, (0..1000).sum());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn table_shared_across_threads() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in 0..1000u64 {
             let hash = hash_key(&state, k);
-            let _ = table.try_entry(hash, |v| v.key == k).map(|e| {
-                e.or_insert(Item {
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item {
                     key: k,
                     value: k as i32,
-                })
-            });
+                });
         }
 
-        assert_eq!(table.capacity(), initial_capacity);
+        let table = &table;
+        let state = &state;
+        std::thread::scope(|scope| {
+            for t in 0..4u64 {
+                scope.spawn(move || {
+                    for k in (t..1000).step_by(4) {
+                        let hash = hash_key(state, k);
+                        let found = table.find(hash, |v| v.key == k).unwrap();
+                        assert_eq!(found.value, k as i32);
+                    }
+                });
+            }
+        });
+    }
 
-        let k = initial_capacity as u64;
-        let hash = hash_key(&state, k);
-        assert!(table.try_entry(hash, |v| v.key == k).is_err());
+    #[test]
+    fn into_iter_consumes_from_both_ends() {
+        use alloc::rc::Rc;
 
-        assert_eq!(table.capacity(), initial_capacity);
+        let state = HashState::default();
+        let shared = Rc::new(());
+        let mut table: HashTable<(u64, Rc<()>)> = HashTable::with_capacity(0);
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.0 == k, |v| hash_key(&state, v.0))
+                .or_insert((k, shared.clone()));
+        }
+
+        let mut iter = table.into_iter();
+        assert_eq!(iter.len(), 100);
+        let mut seen = [false; 100];
+        for _ in 0..30 {
+            let (k, _) = iter.next().unwrap();
+            assert!(!core::mem::replace(&mut seen[k as usize], true));
+            let (k, _) = iter.next_back().unwrap();
+            assert!(!core::mem::replace(&mut seen[k as usize], true));
+        }
+        assert_eq!(iter.len(), 40);
+        assert_eq!(iter.size_hint(), (40, Some(40)));
+
+        // Dropping the iterator must drop exactly the values not yet yielded.
+        drop(iter);
+        assert_eq!(seen.iter().filter(|s| **s).count(), 60);
+        assert_eq!(Rc::strong_count(&shared), 1);
     }
 
     #[test]
-    fn try_entry_vacant_or_insert() {
+    fn clear_drops_every_value_once() {
+        use alloc::rc::Rc;
+
         let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(50);
+        let shared = Rc::new(());
+        let mut table: HashTable<(u64, Rc<()>)> = HashTable::with_capacity(0);
+        for k in 0..200u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.0 == k, |v| hash_key(&state, v.0))
+                .or_insert((k, shared.clone()));
+        }
+        assert_eq!(Rc::strong_count(&shared), 201);
 
-        let k = 100u64;
-        let hash = hash_key(&state, k);
+        let capacity = table.capacity();
+        table.clear();
+        assert_eq!(Rc::strong_count(&shared), 1);
+        assert!(table.is_empty());
+        assert_eq!(table.capacity(), capacity);
+        assert_eq!(table.iter().count(), 0);
 
-        let result = table
-            .try_entry(hash, |v| v.key == k)
-            .map(|e| e.or_insert(Item { key: k, value: 500 }));
+        let mut plain: HashTable<u64> = HashTable::with_capacity(100);
+        for k in 0..100u64 {
+            plain.entry(k, |v| *v == k, |v| *v).or_insert(k);
+        }
+        plain.clear();
+        assert!(plain.is_empty());
+        assert_eq!(plain.find(5, |v| *v == 5), None);
+    }
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap().value, 500);
-        assert_eq!(table.len(), 1);
+    #[test]
+    fn clear_on_empty_table_is_a_no_op() {
+        let mut table: HashTable<u64> = HashTable::with_capacity(1 << 16);
+        table.entry(7, |&v| v == 7, |&v| v).or_insert(7);
+        table.remove(7, |&v| v == 7);
+        let info = table.capacity_info();
 
-        let result = table
-            .try_entry(hash, |v| v.key == k)
-            .map(|e| e.or_insert(Item { key: k, value: 999 }));
+        let generation = table.generation;
+        table.clear();
+        // Any write to the metadata bumps the generation, so an unchanged
+        // generation means the metadata was not rewritten.
+        assert_eq!(table.generation, generation);
+        assert_eq!(table.capacity_info(), info);
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap().value, 500);
-        assert_eq!(table.len(), 1);
+        table.entry(7, |&v| v == 7, |&v| v).or_insert(7);
+        assert_eq!(table.find(7, |&v| v == 7), Some(&7));
     }
 
     #[test]
-    fn try_entry_with_collision() {
+    fn extract_if_maintains_table_integrity() {
         let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(50);
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
 
-        for k in 0..20u64 {
+        for k in 0..500u64 {
             let hash = hash_key(&state, k);
-            match table.try_entry(hash, |v| v.key == k) {
-                Ok(Entry::Vacant(v)) => {
-                    v.insert(Item {
-                        key: k,
-                        value: k as i32,
-                    });
-                }
-                Err(e) => panic!("should succeed: {:?}", e),
-                _ => panic!("should be vacant"),
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: k,
+                    value: k as i32,
+                });
+        }
+
+        let _extracted: Vec<Item> = table
+            .extract_if(|item| item.key % 3 == 0, |v| hash_key(&state, v.key))
+            .collect();
+
+        for k in 0..500u64 {
+            let hash = hash_key(&state, k);
+            let found = table.find(hash, |v| v.key == k);
+            if k % 3 == 0 {
+                assert!(found.is_none(), "key {} should be extracted", k);
+            } else {
+                assert!(found.is_some(), "key {} should remain", k);
+                assert_eq!(found.unwrap().value, k as i32);
             }
         }
+    }
 
-        for k in 0..20u64 {
+    #[test]
+    fn entry_tracked_flags_the_resize_boundary() {
+        let state = HashState::default();
+        let mut table: HashTable<u64> = HashTable::with_capacity(100);
+
+        // At the higher densities a full neighborhood can also grow the table
+        // before it reaches `capacity`, so check the flag against the
+        // allocation rather than assuming only the boundary insert resizes.
+        let mut k = 0;
+        loop {
+            let slots = table.slot_capacity();
+            let at_capacity = table.len() == table.capacity();
             let hash = hash_key(&state, k);
-            match table.try_entry(hash, |v| v.key == k) {
-                Ok(Entry::Occupied(occ)) => {
-                    assert_eq!(occ.get().key, k);
-                    assert_eq!(occ.get().value, k as i32);
-                }
-                _ => panic!("should be occupied for key {}", k),
+            let (entry, resized) = table.entry_tracked(hash, |&v| v == k, |&v| hash_key(&state, v));
+            entry.or_insert(k);
+            assert_eq!(resized, table.slot_capacity() != slots, "insert {k}");
+            k += 1;
+            if at_capacity {
+                assert!(resized);
+                break;
             }
         }
+
+        let hash = hash_key(&state, 0);
+        let (entry, resized) = table.entry_tracked(hash, |&v| v == 0, |&v| hash_key(&state, v));
+        assert!(matches!(entry, Entry::Occupied(_)));
+        assert!(!resized);
     }
 
     #[test]
-    fn try_entry_empty_table() {
+    #[cfg(feature = "std")]
+    fn panicking_or_insert_with_leaves_table_unchanged() {
+        use std::panic::AssertUnwindSafe;
+        use std::panic::catch_unwind;
+
         let state = HashState::default();
-        let mut table: HashTable<Item> = HashTable::with_capacity(10);
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        for k in 0..50u64 {
+            let hash = hash_key(&state, k);
+            table.insert_unique_unchecked(hash, k, |a, b| a == b, |&v| hash_key(&state, v));
+        }
 
-        let k = 1u64;
+        let k = 1_000u64;
         let hash = hash_key(&state, k);
+        let slot = match table.entry(hash, |&v| v == k, |&v| hash_key(&state, v)) {
+            Entry::Vacant(entry) => entry.slot(),
+            Entry::Occupied(_) => unreachable!(),
+        };
 
-        assert_eq!(table.len(), 0);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            table
+                .entry(hash, |&v| v == k, |&v| hash_key(&state, v))
+                .or_insert_with(|| panic!("constructor failed"));
+        }));
+        assert!(result.is_err());
+
+        assert_eq!(table.len(), 50);
+        assert!(table.find(hash, |&v| v == k).is_none());
+        // SAFETY: `slot` came from a vacant entry, so it is within the table.
+        assert!(!unsafe { table.is_occupied(slot) });
+        match table.entry(hash, |&v| v == k, |&v| hash_key(&state, v)) {
+            Entry::Vacant(entry) => assert_eq!(entry.slot(), slot),
+            Entry::Occupied(_) => unreachable!(),
+        }
+        for k in 0..50u64 {
+            let hash = hash_key(&state, k);
+            assert_eq!(table.find(hash, |&v| v == k), Some(&k));
+        }
+    }
+
+    #[test]
+    fn iter_by_bucket_partitions_values_by_root() {
+        let state = HashState::default();
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        let rehash = |v: &u64| hash_key(&state, *v);
+        assert!(table.iter_by_bucket(&rehash).next().is_none());
+
+        for k in 0..3_000u64 {
+            table.insert_unique_unchecked(hash_key(&state, k), k, |a, b| a == b, rehash);
+        }
+        for k in (0..3_000u64).step_by(3) {
+            table.remove(hash_key(&state, k), |&v| v == k);
+        }
+
+        let mut grouped = Vec::new();
+        let mut last_root = None;
+        for (root, entries) in table.iter_by_bucket(&rehash) {
+            assert!(last_root < Some(root));
+            last_root = Some(root);
+            let len = entries.len();
+            assert!(len > 0);
+            let values: Vec<u64> = entries.copied().collect();
+            assert_eq!(values.len(), len);
+            for &v in &values {
+                assert_eq!(table.bucket_index(hash_key(&state, v)), root);
+            }
+            grouped.extend(values);
+        }
+
+        let mut expected: Vec<u64> = table.iter().copied().collect();
+        expected.sort_unstable();
+        grouped.sort_unstable();
+        assert_eq!(grouped, expected);
+    }
+
+    #[test]
+    fn modify_matching_visits_every_duplicate() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        for k in 0..200u64 {
+            table.insert_unique_unchecked(
+                hash_key(&state, k),
+                Item { key: k, value: 0 },
+                |a, b| a == b,
+                rehash,
+            );
+        }
+        let hash = hash_key(&state, 7);
+        table.insert_unique_unchecked(hash, Item { key: 7, value: 1 }, |a, b| a == b, rehash);
+
+        let modified = table.modify_matching(hash, |v| v.key == 7, |v| v.value += 10);
+        assert_eq!(modified, 2);
+
+        let mut values: Vec<i32> = table
+            .iter()
+            .filter(|v| v.key == 7)
+            .map(|v| v.value)
+            .collect();
+        values.sort_unstable();
+        assert_eq!(values, [10, 11]);
+        assert!(table.iter().filter(|v| v.key != 7).all(|v| v.value == 0));
+
+        assert_eq!(
+            table.modify_matching(hash_key(&state, 500), |v| v.key == 500, |_| ()),
+            0
+        );
+    }
+
+    /// Calls the value-moving operations with an unbounded `V`, so this fails
+    /// to compile if any of them picks up a `Clone` bound.
+    fn exercise_without_clone<V>(
+        table: &mut HashTable<V>,
+        rehash: impl Fn(&V) -> u64 + Copy,
+        keep: impl Fn(&V) -> bool + Copy,
+    ) -> Vec<V> {
+        table.reserve(1_000, rehash);
+        let mut removed: Vec<V> = table.extract_if(|v| !keep(v), rehash).collect();
+        // Everything left passes `keep`, so this only exercises the bound.
+        table.retain(keep, rehash);
+        table.shrink_to_fit(rehash);
+        removed.extend(table.drain());
+        removed
+    }
+
+    #[test]
+    fn value_moving_operations_do_not_require_clone() {
+        struct NotClone {
+            key: u64,
+            _owned: Box<u64>,
+        }
+
+        let state = HashState::default();
+        let rehash = |v: &NotClone| hash_key(&state, v.key);
+        let mut table: HashTable<NotClone> = HashTable::with_capacity(0);
+        for k in 0..300u64 {
+            table.insert_unique_unchecked(
+                hash_key(&state, k),
+                NotClone {
+                    key: k,
+                    _owned: Box::new(k),
+                },
+                |a, b| a.key == b.key,
+                rehash,
+            );
+        }
+
+        table.reserve(5_000, rehash);
+        let grown = table.capacity();
+        table.shrink_to_fit(rehash);
+        assert!(table.capacity() < grown);
+        assert_eq!(table.len(), 300);
+
+        let drained = exercise_without_clone(&mut table, rehash, |v| v.key < 250);
+        assert!(table.is_empty());
+        let mut keys: Vec<u64> = drained.iter().map(|v| v.key).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, (0..300).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn insert_returns_replaced_value() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        let rehash = |v: &Item| hash_key(&state, v.key);
+
+        for k in 0..100u64 {
+            let item = Item {
+                key: k,
+                value: k as i32,
+            };
+            assert_eq!(
+                table.insert(hash_key(&state, k), item, |v| v.key == k, rehash),
+                None
+            );
+        }
+        assert_eq!(table.len(), 100);
+
+        let previous = table.insert(
+            hash_key(&state, 42),
+            Item { key: 42, value: -1 },
+            |v| v.key == 42,
+            rehash,
+        );
+        assert_eq!(previous, Some(Item { key: 42, value: 42 }));
+        assert_eq!(table.len(), 100);
+        assert_eq!(
+            table.find(hash_key(&state, 42), |v| v.key == 42),
+            Some(&Item { key: 42, value: -1 })
+        );
+    }
+
+    #[test]
+    fn last_root_bucket_fills_into_the_pad() {
+        let mut table: HashTable<u64> = HashTable::with_capacity(256);
+        let last_root = table.max_root_mask;
+        let slots = table.slot_capacity();
+        // Every key maps to the last root bucket. The keys also share a tag, so
+        // lookups have to compare every value in the neighborhood.
+        let hash_of = |k: u64| (k << 40) | last_root as u64;
+        let neighborhood = HOP_RANGE * LANES;
+        assert!(table.capacity() > neighborhood);
+
+        for k in 0..neighborhood as u64 {
+            match table.entry(hash_of(k), |&v| v == k, |&v| hash_of(v)) {
+                Entry::Vacant(entry) => {
+                    let slot = entry.slot();
+                    assert!(slot >= last_root * LANES && slot < slots);
+                    entry.insert(k);
+                }
+                Entry::Occupied(_) => unreachable!(),
+            }
+        }
+        assert_eq!(
+            table.max_root_mask, last_root,
+            "the neighborhood fit without resizing"
+        );
+        for k in 0..neighborhood as u64 {
+            assert_eq!(table.find(hash_of(k), |&v| v == k), Some(&k));
+        }
+
+        // Free a slot at the very end of the neighborhood and fill it again.
+        let last = (0..neighborhood as u64)
+            .max_by_key(|&k| table.find_index(hash_of(k), |&v| v == k))
+            .unwrap();
+        assert_eq!(
+            table.find_index(hash_of(last), |&v| v == last),
+            Some((last_root + HOP_RANGE) * LANES - 1)
+        );
+        table.remove(hash_of(last), |&v| v == last);
+        table
+            .entry(hash_of(last), |&v| v == last, |&v| hash_of(v))
+            .or_insert(last);
+        assert_eq!(table.max_root_mask, last_root);
+        for k in 0..neighborhood as u64 {
+            assert_eq!(table.find(hash_of(k), |&v| v == k), Some(&k));
+        }
+    }
+
+    #[test]
+    fn masked_scans_match_scalar_reference() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+
+        for k in 0..2_000u64 {
+            let hash = hash_key(&state, k);
+            table
+                .entry(hash, |v| v.key == k, |v| hash_key(&state, v.key))
+                .or_insert(Item {
+                    key: k,
+                    value: k as i32,
+                });
+        }
+        // Leave gaps so some groups are empty and others are partially full.
+        table.retain(
+            |item| !item.key.is_multiple_of(7),
+            |v| hash_key(&state, v.key),
+        );
+
+        let predicate = |item: &Item| item.key.is_multiple_of(5) || item.key % 11 == 3;
+        let mut expected_removed: Vec<u64> = table
+            .iter()
+            .filter(|item| predicate(item))
+            .map(|item| item.key)
+            .collect();
+        let mut expected_kept: Vec<u64> = table
+            .iter()
+            .filter(|item| !predicate(item))
+            .map(|item| item.key)
+            .collect();
+        expected_removed.sort_unstable();
+        expected_kept.sort_unstable();
+
+        let mut retained = table.clone();
+        retained.retain(|item| !predicate(item), |v| hash_key(&state, v.key));
+        let mut kept: Vec<u64> = retained.iter().map(|item| item.key).collect();
+        kept.sort_unstable();
+        assert_eq!(kept, expected_kept);
+
+        let mut removed: Vec<u64> = table
+            .extract_if(|item| predicate(item), |v| hash_key(&state, v.key))
+            .map(|item| item.key)
+            .collect();
+        removed.sort_unstable();
+        assert_eq!(removed, expected_removed);
+
+        let mut kept: Vec<u64> = table.iter().map(|item| item.key).collect();
+        kept.sort_unstable();
+        assert_eq!(kept, expected_kept);
+        for &k in &expected_kept {
+            let hash = hash_key(&state, k);
+            assert!(table.find(hash, |v| v.key == k).is_some());
+        }
+    }
+
+    #[test]
+    fn try_entry_vacant_success() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(100);
+
+        let k = 42u64;
+        let hash = hash_key(&state, k);
 
         match table.try_entry(hash, |v| v.key == k) {
             Ok(Entry::Vacant(v)) => {
-                v.insert(Item { key: k, value: 42 });
+                v.insert(Item { key: k, value: 100 });
             }
-            _ => panic!("should be vacant in empty table"),
+            Ok(Entry::Occupied(_)) => panic!("should be vacant"),
+            Err(e) => panic!("should succeed: {:?}", e),
+        }
+
+        assert_eq!(table.len(), 1);
+        assert_eq!(
+            table.find(hash, |v| v.key == k),
+            Some(&Item { key: k, value: 100 })
+        );
+    }
+
+    #[test]
+    fn try_entry_occupied_success() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(100);
+
+        let k = 42u64;
+        let hash = hash_key(&state, k);
+
+        match table.try_entry(hash, |v| v.key == k) {
+            Ok(Entry::Vacant(v)) => {
+                v.insert(Item { key: k, value: 100 });
+            }
+            _ => panic!("first should be vacant"),
+        }
+
+        match table.try_entry(hash, |v| v.key == k) {
+            Ok(Entry::Occupied(mut occ)) => {
+                assert_eq!(occ.get().value, 100);
+                occ.get_mut().value = 200;
+            }
+            Ok(Entry::Vacant(_)) => panic!("should be occupied"),
+            Err(e) => panic!("should succeed: {:?}", e),
         }
 
         assert_eq!(table.len(), 1);
+        assert_eq!(
+            table.find(hash, |v| v.key == k),
+            Some(&Item { key: k, value: 200 })
+        );
+    }
+
+    #[test]
+    fn try_entry_capacity_too_small() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(10);
+
+        let capacity = table.capacity();
+        for k in 0..capacity as u64 {
+            let hash = hash_key(&state, k);
+            match table.try_entry(hash, |v| v.key == k) {
+                Ok(Entry::Vacant(v)) => {
+                    v.insert(Item {
+                        key: k,
+                        value: k as i32,
+                    });
+                }
+                Err(e) => panic!("should succeed before capacity: {:?}", e),
+                _ => panic!("should be vacant"),
+            }
+        }
+
+        assert_eq!(table.len(), capacity);
+
+        let k = capacity as u64;
+        let hash = hash_key(&state, k);
+        match table.try_entry(hash, |v| v.key == k) {
+            Err(TryEntryError::CapacityTooSmall) => {}
+            Ok(_) => panic!("should fail with CapacityTooSmall"),
+            Err(e) => panic!("wrong error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn try_entry_no_free_slot_in_range() {
+        let mut table: HashTable<Item> = HashTable::with_capacity(100);
+
+        let capacity = table.capacity();
+        let target_fill = (capacity * 9) / 10;
+
+        let mut inserted = 0;
+        let hash = 0u64;
+
+        while inserted < target_fill {
+            match table.try_entry(hash, |_| false) {
+                Ok(Entry::Vacant(v)) => {
+                    v.insert(Item {
+                        key: inserted as u64,
+                        value: inserted as i32,
+                    });
+                    inserted += 1;
+                }
+                Ok(Entry::Occupied(_)) => {}
+                Err(TryEntryError::NoFreeSlot) => break,
+                Err(TryEntryError::CapacityTooSmall) => break,
+            }
+        }
+
+        assert_eq!(
+            table.try_entry(hash, |_| false).err(),
+            Some(TryEntryError::NoFreeSlot)
+        );
+        assert!(!table.is_empty());
+        assert!(table.len() <= capacity);
+    }
+
+    #[test]
+    fn try_entry_multiple_insertions() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(50);
+
+        let keys = [1u64, 5, 10, 15, 20, 25, 30];
+
+        for &k in &keys {
+            let hash = hash_key(&state, k);
+            match table.try_entry(hash, |v| v.key == k) {
+                Ok(Entry::Vacant(v)) => {
+                    v.insert(Item {
+                        key: k,
+                        value: (k * 10) as i32,
+                    });
+                }
+                Err(e) => panic!("insertion failed for key {}: {:?}", k, e),
+                _ => panic!("should be vacant for key {}", k),
+            }
+        }
+
+        assert_eq!(table.len(), keys.len());
+
+        for &k in &keys {
+            let hash = hash_key(&state, k);
+            assert_eq!(
+                table.find(hash, |v| v.key == k),
+                Some(&Item {
+                    key: k,
+                    value: (k * 10) as i32
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn try_entry_does_not_resize() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(10);
+
+        let initial_capacity = table.capacity();
+
+        for k in 0..initial_capacity as u64 {
+            let hash = hash_key(&state, k);
+            let _ = table.try_entry(hash, |v| v.key == k).map(|e| {
+                e.or_insert(Item {
+                    key: k,
+                    value: k as i32,
+                })
+            });
+        }
+
+        assert_eq!(table.capacity(), initial_capacity);
+
+        let k = initial_capacity as u64;
+        let hash = hash_key(&state, k);
+        assert!(table.try_entry(hash, |v| v.key == k).is_err());
+
+        assert_eq!(table.capacity(), initial_capacity);
+    }
+
+    #[test]
+    fn try_entry_vacant_or_insert() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(50);
+
+        let k = 100u64;
+        let hash = hash_key(&state, k);
+
+        let result = table
+            .try_entry(hash, |v| v.key == k)
+            .map(|e| e.or_insert(Item { key: k, value: 500 }));
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().value, 500);
+        assert_eq!(table.len(), 1);
+
+        let result = table
+            .try_entry(hash, |v| v.key == k)
+            .map(|e| e.or_insert(Item { key: k, value: 999 }));
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().value, 500);
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn try_entry_with_collision() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(50);
+
+        for k in 0..20u64 {
+            let hash = hash_key(&state, k);
+            match table.try_entry(hash, |v| v.key == k) {
+                Ok(Entry::Vacant(v)) => {
+                    v.insert(Item {
+                        key: k,
+                        value: k as i32,
+                    });
+                }
+                Err(e) => panic!("should succeed: {:?}", e),
+                _ => panic!("should be vacant"),
+            }
+        }
+
+        for k in 0..20u64 {
+            let hash = hash_key(&state, k);
+            match table.try_entry(hash, |v| v.key == k) {
+                Ok(Entry::Occupied(occ)) => {
+                    assert_eq!(occ.get().key, k);
+                    assert_eq!(occ.get().value, k as i32);
+                }
+                _ => panic!("should be occupied for key {}", k),
+            }
+        }
+    }
+
+    #[test]
+    fn try_entry_empty_table() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(10);
+
+        let k = 1u64;
+        let hash = hash_key(&state, k);
+
+        assert_eq!(table.len(), 0);
+
+        match table.try_entry(hash, |v| v.key == k) {
+            Ok(Entry::Vacant(v)) => {
+                v.insert(Item { key: k, value: 42 });
+            }
+            _ => panic!("should be vacant in empty table"),
+        }
+
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn failed_rehash_leaves_table_usable() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(10);
+        let dead = 3u64;
+        let rehash = |v: &Item| (v.key != dead).then(|| hash_key(&state, v.key));
+
+        // Insert until the table has to grow. That is usually the insert past
+        // `capacity`, but at the higher densities a full neighborhood can force
+        // it earlier.
+        let capacity = table.capacity();
+        let mut k = 0;
+        loop {
+            let hash = hash_key(&state, k);
+            match table.try_entry_with_rehash(hash, |v| v.key == k, rehash) {
+                Ok(Entry::Vacant(entry)) => {
+                    entry.insert(Item {
+                        key: k,
+                        value: k as i32,
+                    });
+                }
+                Err(TryRehashError::HashUnavailable) => break,
+                _ => panic!("keys are distinct, and growing fails"),
+            }
+            k += 1;
+        }
+        assert!(k > dead);
+
+        let len = table.len();
+        let hash = hash_key(&state, k);
+        assert_eq!(
+            table
+                .try_entry_with_rehash(hash, |v| v.key == k, rehash)
+                .err(),
+            Some(TryRehashError::HashUnavailable)
+        );
+        assert_eq!(
+            table.try_reserve_with_rehash(capacity - len + 1, rehash),
+            Err(TryRehashError::HashUnavailable)
+        );
+        assert_eq!(table.capacity(), capacity);
+        assert_eq!(table.len(), len);
+        for key in 0..k {
+            let hash = hash_key(&state, key);
+            assert_eq!(
+                table.find(hash, |v| v.key == key).unwrap().value,
+                key as i32
+            );
+        }
+
+        // Lookups of present values never need to resize.
+        let hash = hash_key(&state, dead);
+        assert!(matches!(
+            table.try_entry_with_rehash(hash, |v| v.key == dead, rehash),
+            Ok(Entry::Occupied(_))
+        ));
+
+        let rehash = |v: &Item| Some(hash_key(&state, v.key));
+        match table.try_entry_with_rehash(hash_key(&state, k), |v| v.key == k, rehash) {
+            Ok(Entry::Vacant(entry)) => {
+                entry.insert(Item {
+                    key: k,
+                    value: k as i32,
+                });
+            }
+            _ => panic!("should be vacant after growing"),
+        }
+        assert!(table.capacity() > capacity);
+        for key in 0..=k {
+            let hash = hash_key(&state, key);
+            assert_eq!(
+                table.find(hash, |v| v.key == key).unwrap().value,
+                key as i32
+            );
+        }
+    }
+
+    #[test]
+    fn insert_all_unique_rebuilds_snapshot() {
+        let state = HashState::default();
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        let snapshot: Vec<_> = (0..5000u64)
+            .map(|k| {
+                let item = Item {
+                    key: k,
+                    value: k as i32,
+                };
+                (hash_key(&state, k), item)
+            })
+            .collect();
+
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        table.insert_all_unique(snapshot.iter().cloned(), rehash);
+        assert_eq!(table.len(), snapshot.len());
+        for (hash, item) in &snapshot {
+            assert_eq!(table.find(*hash, |v| v.key == item.key), Some(item));
+        }
+
+        // An iterator that under-reports its length still has every item
+        // inserted.
+        struct Undercount<I>(I);
+        impl<I: Iterator> Iterator for Undercount<I> {
+            type Item = I::Item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.next()
+            }
+        }
+        impl<I: Iterator> ExactSizeIterator for Undercount<I> {
+            fn len(&self) -> usize {
+                1
+            }
+        }
+
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        table.insert_all_unique(Undercount(snapshot.iter().cloned()), rehash);
+        assert_eq!(table.len(), snapshot.len());
+        for (hash, item) in &snapshot {
+            assert_eq!(table.find(*hash, |v| v.key == item.key), Some(item));
+        }
+    }
+
+    #[test]
+    fn for_loops_over_table_references() {
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in 0..200u64 {
+            let item = Item {
+                key: k,
+                value: k as i32,
+            };
+            table.insert_unique_unchecked(
+                hash_key(&state, k),
+                item,
+                |a, b| a.key == b.key,
+                |v| hash_key(&state, v.key),
+            );
+        }
+
+        let mut sum = 0;
+        for item in &table {
+            sum += item.value;
+        }
+        assert_eq!(sum, (0..200).sum());
+
+        for item in &mut table {
+            item.value = -item.value;
+        }
+        for item in &table {
+            assert_eq!(item.value, -(item.key as i32));
+        }
+    }
+
+    #[test]
+    fn bubble_limit_resizes_instead_of_bubbling() {
+        let fill = |limit: usize| {
+            let mut table: HashTable<u64> = HashTable::with_capacity(1000);
+            table.set_max_bubble_moves(limit);
+            let mut inserted = Vec::new();
+            let mut insert = |table: &mut HashTable<u64>, hash: u64| {
+                table.entry(hash, |&v| v == hash, |&v| v).or_insert(hash);
+                inserted.push(hash);
+            };
+
+            // Fill bucket 0's neighborhood so that the next bucket 0 value can
+            // only be placed by bubbling a bucket 1 value out of the way.
+            for bucket in 0..HOP_RANGE as u64 {
+                for lane in 0..LANES as u64 {
+                    insert(&mut table, (lane << 57) | (lane << 32) | bucket);
+                }
+            }
+            let capacity = table.capacity();
+            insert(&mut table, 1 << 40);
+
+            for &hash in &inserted {
+                assert_eq!(table.find(hash, |&v| v == hash), Some(&hash));
+            }
+            assert_eq!(table.len(), inserted.len());
+            (capacity, table.capacity())
+        };
+
+        let (before, after) = fill(usize::MAX);
+        assert_eq!(before, after);
+
+        let (before, after) = fill(0);
+        assert!(after > before);
+    }
+
+    #[test]
+    fn soft_shrink_defers_reallocation() {
+        let state = HashState::default();
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        let mut table: HashTable<Item> = HashTable::with_capacity(10_000);
+        let insert = |table: &mut HashTable<Item>, range: core::ops::Range<u64>| {
+            for k in range {
+                let item = Item {
+                    key: k,
+                    value: k as i32,
+                };
+                table.insert_unique_unchecked(
+                    hash_key(&state, k),
+                    item,
+                    |a, b| a.key == b.key,
+                    rehash,
+                );
+            }
+        };
+        insert(&mut table, 0..100);
+        let capacity = table.capacity();
+        let bytes = table.capacity_info().allocation_bytes;
+
+        table.soft_shrink(200);
+        assert_eq!(table.capacity(), 200);
+        assert_eq!(table.capacity_info().allocation_bytes, bytes);
+        table.soft_shrink(50);
+        assert_eq!(table.capacity(), 100);
+
+        // Growing past the lowered capacity reuses the allocation.
+        insert(&mut table, 100..250);
+        assert_eq!(table.capacity(), capacity);
+        assert_eq!(table.capacity_info().allocation_bytes, bytes);
+
+        table.soft_shrink(300);
+        table.commit_shrink(rehash);
+        assert!(table.capacity() >= 300);
+        assert!(table.capacity() < capacity);
+        assert!(table.capacity_info().allocation_bytes < bytes);
+        assert_eq!(table.len(), 250);
+        for k in 0..250 {
+            let hash = hash_key(&state, k);
+            assert_eq!(table.find(hash, |v| v.key == k).unwrap().value, k as i32);
+        }
+
+        // A capacity that needs the current geometry keeps the allocation.
+        let bytes = table.capacity_info().allocation_bytes;
+        let capacity = table.capacity();
+        table.soft_shrink(capacity - 1);
+        table.commit_shrink(rehash);
+        assert_eq!(table.capacity(), capacity);
+        assert_eq!(table.capacity_info().allocation_bytes, bytes);
+    }
+
+    #[test]
+    fn occupied_entry_reports_lookup_hash() {
+        let state = HashState::default();
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in 0..100u64 {
+            table
+                .entry(hash_key(&state, k), |v| v.key == k, rehash)
+                .or_insert(Item {
+                    key: k,
+                    value: k as i32,
+                });
+        }
+
+        for k in 0..100u64 {
+            let hash = hash_key(&state, k);
+            match table.entry(hash, |v| v.key == k, rehash) {
+                Entry::Occupied(entry) => assert_eq!(entry.hash(), hash),
+                Entry::Vacant(_) => panic!("key {k} should be present"),
+            }
+            match table.try_entry(hash, |v| v.key == k) {
+                Ok(Entry::Occupied(mut entry)) => {
+                    entry.get_mut().value += 1;
+                    assert_eq!(entry.hash(), hash);
+                    assert_eq!(entry.remove().value, k as i32 + 1);
+                }
+                _ => panic!("key {k} should be present"),
+            }
+        }
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn extract_if_rehashes_only_extracted_values() {
+        use core::cell::RefCell;
+
+        let state = HashState::default();
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in 0..2000u64 {
+            let item = Item {
+                key: k,
+                value: k as i32,
+            };
+            table.insert_unique_unchecked(
+                hash_key(&state, k),
+                item,
+                |a, b| a.key == b.key,
+                |v| hash_key(&state, v.key),
+            );
+        }
+
+        let rehashed = RefCell::new(Vec::new());
+        let extracted: Vec<u64> = table
+            .extract_if(
+                |v| v.key.is_multiple_of(3),
+                |v| {
+                    rehashed.borrow_mut().push(v.key);
+                    hash_key(&state, v.key)
+                },
+            )
+            .map(|v| v.key)
+            .collect();
+
+        let mut rehashed = rehashed.into_inner();
+        let mut extracted_sorted = extracted.clone();
+        rehashed.sort_unstable();
+        extracted_sorted.sort_unstable();
+        assert_eq!(extracted.len(), 667);
+        assert_eq!(rehashed, extracted_sorted);
+        assert_eq!(table.len(), 2000 - 667);
+        for k in (0..2000u64).filter(|k| !k.is_multiple_of(3)) {
+            assert!(table.find(hash_key(&state, k), |v| v.key == k).is_some());
+        }
+    }
+
+    #[test]
+    fn migration_across_multi_step_growth() {
+        let state = HashState::default();
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        let roots = |table: &HashTable<Item>| table.slot_capacity() / LANES - HOP_RANGE;
+
+        for growth in [2, 4, 8] {
+            let mut table: HashTable<Item> = HashTable::with_capacity(1000);
+            let count = table.capacity() as u64;
+            for k in 0..count {
+                let item = Item {
+                    key: k,
+                    value: k as i32,
+                };
+                table.insert_unique_unchecked(
+                    hash_key(&state, k),
+                    item,
+                    |a, b| a.key == b.key,
+                    rehash,
+                );
+            }
+
+            let old_roots = roots(&table);
+            // Ask for just over `growth / 2` times the current capacity so the root
+            // count has to grow by exactly `growth`.
+            let target = table.capacity() * growth / 2 + 1;
+            table.reserve(target - table.len(), rehash);
+            assert_eq!(roots(&table), old_roots * growth);
+
+            assert_eq!(table.len(), count as usize);
+            for k in 0..count {
+                let found = table.find(hash_key(&state, k), |v| v.key == k);
+                assert_eq!(found.map(|v| v.value), Some(k as i32));
+            }
+        }
+    }
+
+    #[test]
+    fn find_stream_matches_find() {
+        let state = HashState::default();
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in (0..20_000u64).step_by(2) {
+            let item = Item {
+                key: k,
+                value: k as i32,
+            };
+            table.insert_unique_unchecked(hash_key(&state, k), item, |a, b| a.key == b.key, rehash);
+        }
+
+        let keys: Vec<u64> = (0..5_000u64).map(|k| (k * 7) % 20_001).collect();
+        let queries = keys.iter().map(|&k| {
+            let eq: Box<dyn Fn(&Item) -> bool> = Box::new(move |v: &Item| v.key == k);
+            (hash_key(&state, k), eq)
+        });
+        let stream = table.find_stream(queries);
+        assert_eq!(stream.size_hint(), (keys.len(), Some(keys.len())));
+
+        let streamed: Vec<Option<&Item>> = stream.collect();
+        assert_eq!(streamed.len(), keys.len());
+        for (&k, result) in keys.iter().zip(streamed) {
+            let expected = table.find(hash_key(&state, k), |v| v.key == k);
+            assert_eq!(result.map(|v| v.key), expected.map(|v| v.key));
+            assert_eq!(result.is_some(), k % 2 == 0 && k < 20_000);
+        }
+
+        let empty: HashTable<Item> = HashTable::with_capacity(0);
+        let mut stream = empty.find_stream([(0, |_: &Item| true)]);
+        assert_eq!(stream.next(), Some(None));
+        assert_eq!(stream.next(), None);
+    }
+
+    #[test]
+    fn drain_hashed_partitions_by_hash() {
+        const SHARDS: usize = 4;
+        let state = HashState::default();
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in 0..5_000u64 {
+            let item = Item {
+                key: k,
+                value: k as i32,
+            };
+            table.insert_unique_unchecked(hash_key(&state, k), item, |a, b| a.key == b.key, rehash);
+        }
+
+        let mut shards: Vec<HashTable<Item>> =
+            (0..SHARDS).map(|_| HashTable::with_capacity(0)).collect();
+        let mut drain = table.drain_hashed(rehash);
+        assert_eq!(drain.len(), 5_000);
+        while let Some((hash, item)) = drain.next() {
+            assert_eq!(drain.size_hint(), (drain.len(), Some(drain.len())));
+            assert_eq!(hash, hash_key(&state, item.key));
+            shards[hash as usize % SHARDS].insert_unique_unchecked(
+                hash,
+                item,
+                |a, b| a.key == b.key,
+                rehash,
+            );
+        }
+        assert_eq!(drain.len(), 0);
+        drop(drain);
+
+        assert!(table.is_empty());
+        assert_eq!(shards.iter().map(HashTable::len).sum::<usize>(), 5_000);
+        for k in 0..5_000u64 {
+            let hash = hash_key(&state, k);
+            for (index, shard) in shards.iter().enumerate() {
+                let found = shard.find(hash, |v| v.key == k).is_some();
+                assert_eq!(found, hash as usize % SHARDS == index);
+            }
+        }
+    }
+
+    #[test]
+    fn vacant_entry_deferred_and_in_place_inserts() {
+        let state = HashState::default();
+        let rehash = |v: &StringItem| hash_string_key(&state, &v.key);
+        let mut table: HashTable<StringItem> = HashTable::with_capacity(0);
+
+        for k in 0..200u64 {
+            let key = k.to_string();
+            let hash = hash_string_key(&state, &key);
+            let inserted = match table.entry(hash, |v| v.key == key, rehash) {
+                Entry::Vacant(entry) if k % 2 == 0 => entry.insert_with(|| StringItem {
+                    key: key.clone(),
+                    value: k as i32,
+                }),
+                // SAFETY: Both fields are written before `init` returns, and the
+                // key is the one the entry was looked up with.
+                Entry::Vacant(entry) => unsafe {
+                    entry.insert_in_place(|slot| {
+                        core::ptr::addr_of_mut!((*slot).key).write(key.clone());
+                        core::ptr::addr_of_mut!((*slot).value).write(k as i32);
+                    })
+                },
+                Entry::Occupied(_) => unreachable!(),
+            };
+            inserted.value += 1;
+        }
+
+        assert_eq!(table.len(), 200);
+        for k in 0..200u64 {
+            let key = k.to_string();
+            let found = table.find(hash_string_key(&state, &key), |v| v.key == key);
+            assert_eq!(found.map(|v| v.value), Some(k as i32 + 1));
+        }
+        table.clear();
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn panicking_insert_in_place_leaves_table_unchanged() {
+        use std::panic::AssertUnwindSafe;
+        use std::panic::catch_unwind;
+
+        let state = HashState::default();
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        for k in 0..50u64 {
+            table.insert_unique_unchecked(
+                hash_key(&state, k),
+                k,
+                |a, b| a == b,
+                |&v| hash_key(&state, v),
+            );
+        }
+
+        let k = 1_000u64;
+        let hash = hash_key(&state, k);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            if let Entry::Vacant(entry) = table.entry(hash, |&v| v == k, |&v| hash_key(&state, v)) {
+                // SAFETY: `init` panics before returning, so the slot is never
+                // treated as initialized.
+                unsafe { entry.insert_in_place(|_| panic!("initializer failed")) };
+            }
+        }));
+        assert!(result.is_err());
+
+        assert_eq!(table.len(), 50);
+        assert!(table.find(hash, |&v| v == k).is_none());
+        for k in 0..50u64 {
+            assert_eq!(table.find(hash_key(&state, k), |&v| v == k), Some(&k));
+        }
+    }
+
+    /// Every public method called on a table created with `with_capacity(0)`,
+    /// which has no allocation and a root mask of `usize::MAX`.
+    mod zero_capacity {
+        use super::*;
+
+        fn empty() -> HashTable<u64> {
+            let table = HashTable::with_capacity(0);
+            assert_eq!(table.slot_capacity(), 0);
+            assert_eq!(table.capacity_info().allocation_bytes, 0);
+            table
+        }
+
+        #[test]
+        fn read_only_methods() {
+            let state = HashState::default();
+            let hash = hash_key(&state, 7);
+            let table = empty();
+
+            assert!(table.is_empty());
+            assert_eq!(table.len(), 0);
+            assert_eq!(table.capacity(), 0);
+            assert!(table.capacity_fits(0));
+            assert!(!table.capacity_fits(1));
+            let info = table.capacity_info();
+            assert_eq!((info.live, info.slots, info.load_factor), (0, 0, 0.0));
+            assert_eq!(table.max_bubble_moves(), usize::MAX);
+
+            assert_eq!(table.iter().count(), 0);
+            assert_eq!((&table).into_iter().count(), 0);
+            let rehash = |&v: &u64| hash_key(&state, v);
+            assert_eq!(table.iter_by_bucket(&rehash).count(), 0);
+            table.for_each(|_| unreachable!());
+            assert_eq!(table.filter_out(|_| false).count(), 0);
+            assert_eq!(
+                table
+                    .find_stream([(hash, |_: &u64| true)])
+                    .collect::<Vec<_>>(),
+                [None]
+            );
+
+            assert_eq!(table.find(hash, |_| true), None);
+            assert_eq!(table.find_once(hash, |_| true), None);
+            assert_eq!(table.find_index(hash, |_| true), None);
+            let _ = table.bucket_index(hash);
+            assert!(!alloc::format!("{table:?}").is_empty());
+
+            let clone = table.clone();
+            assert_eq!(clone.slot_capacity(), 0);
+            let clone = table.try_clone().unwrap();
+            assert_eq!(clone.slot_capacity(), 0);
+            assert_eq!(clone.into_iter().count(), 0);
+
+            #[cfg(feature = "stats")]
+            {
+                let histogram = table.probe_histogram();
+                assert_eq!(histogram.probe_length_by_bucket, [0; HOP_RANGE]);
+                let stats = table.debug_stats();
+                assert_eq!((stats.total_slots, stats.occupied_slots), (0, 0));
+                assert_eq!(table.bubble_count(), 0);
+            }
+        }
+
+        #[test]
+        fn non_growing_mutations() {
+            let state = HashState::default();
+            let hash = hash_key(&state, 7);
+            let rehash = |&v: &u64| hash_key(&state, v);
+            let mut table = empty();
+
+            assert_eq!(table.iter_mut().count(), 0);
+            assert_eq!((&mut table).into_iter().count(), 0);
+            table.for_each_mut(|_| unreachable!());
+            assert_eq!(table.find_mut(hash, |_| true), None);
+            assert_eq!(table.find_mut_once(hash, |_| true), None);
+            assert_eq!(table.modify_matching(hash, |_| true, |_| unreachable!()), 0);
+            assert_eq!(table.remove(hash, |_| true), None);
+            assert_eq!(table.remove_index(0, rehash), None);
+            assert!(matches!(
+                table.try_entry(hash, |_| true),
+                Err(TryEntryError::CapacityTooSmall)
+            ));
+
+            assert_eq!(table.drain().count(), 0);
+            assert_eq!(table.drain_with(DrainPolicy::ShrinkAfter).count(), 0);
+            assert_eq!(table.drain_map(|v| v).count(), 0);
+            assert_eq!(table.drain_hashed(rehash).count(), 0);
+            table.retain(|_| unreachable!(), rehash);
+            table.retain_mut(|_| unreachable!(), rehash);
+            table.retain_with_hash(|_, _| unreachable!(), rehash);
+            assert_eq!(table.extract_if(|_| true, rehash).count(), 0);
+
+            let mut cursor = table.cursor(rehash);
+            assert_eq!(cursor.slot(), None);
+            assert_eq!(cursor.current(), None);
+            assert_eq!(cursor.current_mut(), None);
+            assert_eq!(cursor.remove_current(), None);
+            cursor.advance();
+            assert_eq!(cursor.slot(), None);
+
+            #[cfg(feature = "rayon")]
+            {
+                use rayon::iter::ParallelIterator;
+                assert_eq!(table.par_iter_mut().count(), 0);
+            }
+
+            table.clear();
+            table.clear_to_capacity(0);
+            table.clear_to_capacity(16);
+            table.shrink_to_fit(rehash);
+            table.soft_shrink(0);
+            table.commit_shrink(rehash);
+            table.set_max_bubble_moves(4);
+            #[cfg(feature = "stats")]
+            table.reset_bubble_count();
+            table.merge(empty(), |a, b| a == b, rehash, |_, _| {});
+
+            assert_eq!(table.slot_capacity(), 0);
+            assert!(table.is_empty());
+        }
+
+        #[test]
+        fn growing_mutations() {
+            let state = HashState::default();
+            let hash = hash_key(&state, 7);
+            let rehash = |&v: &u64| hash_key(&state, v);
+            let try_rehash = |&v: &u64| Some(hash_key(&state, v));
+            let check = |table: HashTable<u64>| {
+                assert_eq!(table.len(), 1);
+                assert_eq!(table.find(hash, |&v| v == 7), Some(&7));
+            };
+
+            let mut table = empty();
+            table.reserve(1, rehash);
+            assert!(table.capacity() >= 1);
+            let mut table = empty();
+            assert!(table.reserve_reporting(1, rehash));
+            let mut table = empty();
+            table.try_reserve(1, rehash).unwrap();
+            let mut table = empty();
+            table.try_reserve_with_rehash(1, try_rehash).unwrap();
+            assert!(table.capacity() >= 1);
+
+            let mut table = empty();
+            table.entry(hash, |&v| v == 7, rehash).or_insert(7);
+            check(table);
+            let mut table = empty();
+            table.entry_once(hash, |&v| v == 7, rehash).or_insert(7);
+            check(table);
+            let mut table = empty();
+            let (entry, resized) = table.entry_tracked(hash, |&v| v == 7, rehash);
+            entry.or_insert(7);
+            assert!(resized);
+            check(table);
+            let mut table = empty();
+            let bucket = table.bucket_index(hash);
+            table
+                .entry_with_bucket(hash, bucket, |&v| v == 7, rehash)
+                .or_insert(7);
+            check(table);
+            let mut table = empty();
+            table
+                .try_entry_with_rehash(hash, |&v| v == 7, try_rehash)
+                .unwrap()
+                .or_insert(7);
+            check(table);
+            let mut table = empty();
+            assert_eq!(table.insert(hash, 7, |&v| v == 7, rehash), None);
+            check(table);
+            let mut table = empty();
+            table.insert_unique_unchecked(hash, 7, |a, b| a == b, rehash);
+            check(table);
+            let mut table = empty();
+            table.insert_all_unique([(hash, 7)].into_iter(), rehash);
+            check(table);
+
+            let mut other = empty();
+            other.insert_unique_unchecked(hash, 7, |a, b| a == b, rehash);
+            let mut table = empty();
+            table.merge(other, |a, b| a == b, rehash, |_, _| unreachable!());
+            check(table);
+        }
+    }
+
+    #[test]
+    fn remove_by_foreign_or_stale_handle_returns_none() {
+        let state = HashState::default();
+        let rehash = |&v: &u64| hash_key(&state, v);
+        let mut a: HashTable<u64> = HashTable::with_capacity(0);
+        let mut b: HashTable<u64> = HashTable::with_capacity(0);
+        for k in 0..1000u64 {
+            a.insert_unique_unchecked(hash_key(&state, k), k, |a, b| a == b, rehash);
+            b.insert_unique_unchecked(
+                hash_key(&state, k + 1_000_000),
+                k + 1_000_000,
+                |a, b| a == b,
+                rehash,
+            );
+        }
+        assert_eq!(a.slot_capacity(), b.slot_capacity());
+
+        let handle_for = |table: &mut HashTable<u64>, k: u64| match table.entry(
+            hash_key(&state, k),
+            |&v| v == k,
+            rehash,
+        ) {
+            Entry::Occupied(entry) => entry.handle(),
+            Entry::Vacant(_) => unreachable!(),
+        };
+
+        for k in 0..1000u64 {
+            let handle = handle_for(&mut a, k);
+            assert_eq!(b.remove_by_handle(handle), None);
+        }
+        assert_eq!(b.len(), 1000);
+
+        for k in 0..1000u64 {
+            let handle = handle_for(&mut a, k);
+            a.remove(hash_key(&state, (k + 1) % 1000), |&v| v == (k + 1) % 1000);
+            assert_eq!(a.remove_by_handle(handle), None);
+            a.insert_unique_unchecked(
+                hash_key(&state, (k + 1) % 1000),
+                (k + 1) % 1000,
+                |a, b| a == b,
+                rehash,
+            );
+        }
+        assert_eq!(a.len(), 1000);
+
+        for k in 0..1000u64 {
+            let handle = handle_for(&mut a, k);
+            assert_eq!(a.remove_by_handle(handle), Some(k));
+        }
+        assert!(a.is_empty());
+        for k in 1_000_000..1_001_000u64 {
+            assert!(b.find(hash_key(&state, k), |&v| v == k).is_some());
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "debug-checks", debug_assertions))]
+    #[should_panic(expected = "insert_unique_unchecked: an equal value is already present")]
+    fn insert_unique_unchecked_panics_on_duplicate() {
+        let state = HashState::default();
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in 0..100u64 {
+            table.insert_unique_unchecked(
+                hash_key(&state, k),
+                Item { key: k, value: 0 },
+                |a, b| a.key == b.key,
+                rehash,
+            );
+        }
+        table.insert_unique_unchecked(
+            hash_key(&state, 42),
+            Item { key: 42, value: 1 },
+            |a, b| a.key == b.key,
+            rehash,
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "hash/eq inconsistency")]
+    fn inconsistent_eq_detected_on_insert() {
+        let state = HashState::default();
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        let mut table: HashTable<Item> = HashTable::with_capacity(0);
+        for k in 0..100u64 {
+            table.insert(
+                hash_key(&state, k),
+                Item { key: k, value: 0 },
+                |v| v.key == k,
+                rehash,
+            );
+        }
+
+        // The hash and `eq` are for key 1000, but the value stores key 1001.
+        table.insert(
+            hash_key(&state, 1000),
+            Item {
+                key: 1001,
+                value: 0,
+            },
+            |v| v.key == 1000,
+            rehash,
+        );
+    }
+
+    #[test]
+    fn iter_by_bucket_ends_on_inconsistent_rehash() {
+        let state = HashState::default();
+        let mut table: HashTable<u64> = HashTable::with_capacity(0);
+        let rehash = |v: &u64| hash_key(&state, *v);
+        for k in 0..3_000u64 {
+            table.insert_unique_unchecked(hash_key(&state, k), k, |a, b| a == b, rehash);
+        }
+
+        let wrong = |v: &u64| !hash_key(&state, *v);
+        let mut yielded = 0;
+        for (_, mut entries) in table.iter_by_bucket(&wrong) {
+            yielded += entries.by_ref().count();
+            assert_eq!(entries.len(), 0);
+            assert!(entries.next().is_none());
+        }
+        assert!(yielded <= table.len());
+    }
+
+    #[test]
+    fn compact_table_is_one_bucket_until_it_grows() {
+        let state = HashState::default();
+        let rehash = |v: &Item| hash_key(&state, v.key);
+        let mut table: HashTable<Item> = HashTable::with_capacity_compact(8);
+        assert_eq!(table.slot_capacity(), LANES);
+        assert!(table.capacity() >= 8);
+        assert!(table.capacity_info().allocation_bytes < 1024);
+
+        let fits = table.capacity() as u64;
+        for k in 0..fits {
+            table.insert(
+                hash_key(&state, k),
+                Item { key: k, value: 0 },
+                |v| v.key == k,
+                rehash,
+            );
+        }
+        assert_eq!(table.slot_capacity(), LANES);
+        assert!(matches!(
+            table.try_entry(hash_key(&state, fits), |v| v.key == fits),
+            Err(TryEntryError::CapacityTooSmall)
+        ));
+
+        assert!(table.remove(hash_key(&state, 0), |v| v.key == 0).is_some());
+        assert!(matches!(
+            table.try_entry(hash_key(&state, fits), |v| v.key == fits),
+            Ok(Entry::Vacant(_))
+        ));
+        table.insert(
+            hash_key(&state, 0),
+            Item { key: 0, value: 0 },
+            |v| v.key == 0,
+            rehash,
+        );
+
+        // One past the capacity moves the table to the regular layout.
+        table.insert(
+            hash_key(&state, fits),
+            Item {
+                key: fits,
+                value: 0,
+            },
+            |v| v.key == fits,
+            rehash,
+        );
+        assert!(table.slot_capacity() > LANES);
+        for k in 0..=fits {
+            assert_eq!(
+                table
+                    .find(hash_key(&state, k), |v| v.key == k)
+                    .map(|v| v.key),
+                Some(k)
+            );
+        }
+
+        let compact: HashTable<Item> = HashTable::with_capacity_compact(100);
+        let regular: HashTable<Item> = HashTable::with_capacity(100);
+        assert_eq!(compact.slot_capacity(), regular.slot_capacity());
+        assert_eq!(
+            HashTable::<Item>::with_capacity_compact(0).slot_capacity(),
+            0
+        );
     }
 }
@@ -0,0 +1,264 @@
+use core::fmt::Debug;
+
+use crate::hash_table::Entry;
+use crate::hash_table::HashTable;
+use crate::hash_table::Iter;
+
+/// A [`HashTable`] that stores its hash and equality functions.
+///
+/// Every `HashTable` operation takes a `hash`, an `eq` predicate and,
+/// where the table may resize, a `rehash` closure. These must agree with
+/// each other on every call, or lookups will silently miss. `KeyedTable`
+/// takes both functions once at construction and supplies them itself, so
+/// lookups only need a probe value of the same type as the stored values.
+///
+/// This is intended for values whose key is part of the value itself. When
+/// the key is a separate type, use [`HashMap`](crate::hash_map::HashMap)
+/// instead.
+///
+/// # Examples
+///
+/// ```
+/// use hop_hash::keyed_table::KeyedTable;
+///
+/// struct User {
+///     id: u64,
+///     name: &'static str,
+/// }
+///
+/// let mut users = KeyedTable::new(
+///     |user: &User| user.id.wrapping_mul(0x9e37_79b9_7f4a_7c15),
+///     |a: &User, b: &User| a.id == b.id,
+/// );
+///
+/// users.insert(User { id: 1, name: "ada" });
+/// users.insert(User {
+///     id: 2,
+///     name: "grace",
+/// });
+///
+/// let probe = User { id: 2, name: "" };
+/// assert_eq!(users.find(&probe).map(|u| u.name), Some("grace"));
+/// ```
+pub struct KeyedTable<V, H, E> {
+    table: HashTable<V>,
+    hash: H,
+    eq: E,
+}
+
+impl<V, H, E> KeyedTable<V, H, E>
+where
+    H: Fn(&V) -> u64,
+    E: Fn(&V, &V) -> bool,
+{
+    /// Creates an empty table using `hash` and `eq` for every operation.
+    ///
+    /// `eq(a, b)` returning `true` must imply `hash(a) == hash(b)`.
+    pub fn new(
+        hash: H,
+        eq: E,
+    ) -> Self {
+        Self::with_capacity(0, hash, eq)
+    }
+
+    /// Creates an empty table with space for at least `capacity` values,
+    /// using `hash` and `eq` for every operation.
+    pub fn with_capacity(
+        capacity: usize,
+        hash: H,
+        eq: E,
+    ) -> Self {
+        Self {
+            table: HashTable::with_capacity(capacity),
+            hash,
+            eq,
+        }
+    }
+
+    /// Inserts a value, returning the equal value it replaced, if any.
+    pub fn insert(
+        &mut self,
+        value: V,
+    ) -> Option<V> {
+        let hash = (self.hash)(&value);
+        match self.table.entry(hash, |v| (self.eq)(v, &value), &self.hash) {
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+            Entry::Occupied(mut entry) => Some(core::mem::replace(entry.get_mut(), value)),
+        }
+    }
+
+    /// Returns a reference to the stored value equal to `probe`, if any.
+    pub fn find(
+        &self,
+        probe: &V,
+    ) -> Option<&V> {
+        self.table.find((self.hash)(probe), |v| (self.eq)(v, probe))
+    }
+
+    /// Returns a mutable reference to the stored value equal to `probe`, if
+    /// any.
+    ///
+    /// The value must not be modified in a way that changes its hash or
+    /// equality.
+    pub fn find_mut(
+        &mut self,
+        probe: &V,
+    ) -> Option<&mut V> {
+        let hash = (self.hash)(probe);
+        self.table.find_mut(hash, |v| (self.eq)(v, probe))
+    }
+
+    /// Returns `true` if the table holds a value equal to `probe`.
+    pub fn contains(
+        &self,
+        probe: &V,
+    ) -> bool {
+        self.find(probe).is_some()
+    }
+
+    /// Removes and returns the stored value equal to `probe`, if any.
+    pub fn remove(
+        &mut self,
+        probe: &V,
+    ) -> Option<V> {
+        let hash = (self.hash)(probe);
+        self.table.remove(hash, |v| (self.eq)(v, probe))
+    }
+}
+
+impl<V, H, E> KeyedTable<V, H, E> {
+    /// Returns the number of values in the table.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns `true` if the table holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Removes all values, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// Returns an iterator over the values in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, V> {
+        self.table.iter()
+    }
+
+    /// Returns the underlying table.
+    pub fn as_table(&self) -> &HashTable<V> {
+        &self.table
+    }
+}
+
+impl<V: Debug, H, E> Debug for KeyedTable<V, H, E> {
+    fn fmt(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, V, H, E> IntoIterator for &'a KeyedTable<V, H, E> {
+    type IntoIter = Iter<'a, V>;
+    type Item = &'a V;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::hash::Hasher;
+
+    use siphasher::sip::SipHasher;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Account {
+        id: u32,
+        region: u8,
+        balance: i64,
+    }
+
+    fn account_hash(account: &Account) -> u64 {
+        let mut h = SipHasher::new();
+        h.write_u32(account.id);
+        h.write_u8(account.region);
+        h.finish()
+    }
+
+    fn same_account(
+        a: &Account,
+        b: &Account,
+    ) -> bool {
+        (a.id, a.region) == (b.id, b.region)
+    }
+
+    fn probe(
+        id: u32,
+        region: u8,
+    ) -> Account {
+        Account {
+            id,
+            region,
+            balance: 0,
+        }
+    }
+
+    #[test]
+    fn keyed_table_uses_derived_key() {
+        let mut accounts = KeyedTable::new(account_hash, same_account);
+        for id in 0..500 {
+            for region in 0..2 {
+                let account = Account {
+                    id,
+                    region,
+                    balance: id as i64 * 10 + region as i64,
+                };
+                assert!(accounts.insert(account).is_none());
+            }
+        }
+        assert_eq!(accounts.len(), 1000);
+
+        assert_eq!(accounts.find(&probe(42, 1)).unwrap().balance, 421);
+        assert!(accounts.find(&probe(42, 2)).is_none());
+
+        accounts.find_mut(&probe(7, 0)).unwrap().balance = -1;
+        assert_eq!(accounts.find(&probe(7, 0)).unwrap().balance, -1);
+
+        let replaced = accounts.insert(Account {
+            id: 7,
+            region: 0,
+            balance: 99,
+        });
+        assert_eq!(replaced.unwrap().balance, -1);
+        assert_eq!(accounts.len(), 1000);
+
+        for id in (0..500).step_by(2) {
+            let removed = accounts.remove(&probe(id, 0)).unwrap();
+            assert_eq!((removed.id, removed.region), (id, 0));
+        }
+        assert_eq!(accounts.len(), 750);
+        assert!(!accounts.contains(&probe(0, 0)));
+        assert!(accounts.contains(&probe(0, 1)));
+        assert!(accounts.contains(&probe(1, 0)));
+
+        let mut ids: Vec<_> = accounts
+            .iter()
+            .filter(|a| a.region == 0)
+            .map(|a| a.id)
+            .collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (1..500).step_by(2).collect::<Vec<_>>());
+    }
+}
@@ -14,6 +14,29 @@ cfg_if! {
     }
 }
 
+cfg_if! {
+    if #[cfg(feature = "foldhash")] {
+        /// A deterministic hasher builder for reproducible iteration order.
+        ///
+        /// Every instance hashes the same input to the same value, in every
+        /// process, so maps built with it iterate in the same order given the
+        /// same operations. This is meant for snapshot tests and reproducible
+        /// output. It provides no protection against hash-flooding attacks, so
+        /// do not use it for maps keyed by untrusted input.
+        pub type FixedState = foldhash::fast::FixedState;
+    } else if #[cfg(feature = "std")] {
+        /// A deterministic hasher builder for reproducible iteration order.
+        ///
+        /// Every instance hashes the same input to the same value, so maps
+        /// built with it iterate in the same order given the same operations.
+        /// This is meant for snapshot tests and reproducible output. It
+        /// provides no protection against hash-flooding attacks, so do not use
+        /// it for maps keyed by untrusted input.
+        pub type FixedState =
+            core::hash::BuildHasherDefault<std::collections::hash_map::DefaultHasher>;
+    }
+}
+
 /// A HashMap implementation using hopscotch hashing.
 ///
 /// This module provides a `HashMap` that wraps the `HashTable` and provides
@@ -28,6 +51,19 @@ pub mod hash_table;
 /// a standard set interface with configurable hashers.
 pub mod hash_set;
 
+/// A `HashTable` wrapper that stores its hash and equality functions.
+///
+/// This module provides a `KeyedTable` for values whose key is derived from
+/// the value itself, so callers pass a probe value instead of closures.
+pub mod keyed_table;
+
+/// Operation counters for maps and sets.
+///
+/// This module provides the `MetricsSink` trait, which a `HashMap` or
+/// `HashSet` can report gets, inserts, removes and resizes to.
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
 cfg_if! {
     if #[cfg(any(feature = "std", feature = "foldhash"))] {
         /// The default `HashMap` type using `RandomState` as the hasher.
@@ -49,5 +85,12 @@ cfg_if! {
 }
 
 pub use hash_map::Entry;
+pub use hash_table::EntryHandle;
 pub use hash_table::HashTable;
 pub use hash_table::TryEntryError;
+pub use hash_table::TryRehashError;
+pub use hash_table::TryReserveError;
+#[cfg(feature = "metrics")]
+pub use metrics::MetricsEvent;
+#[cfg(feature = "metrics")]
+pub use metrics::MetricsSink;
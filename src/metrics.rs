@@ -0,0 +1,84 @@
+/// An operation reported to a [`MetricsSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MetricsEvent {
+    /// A lookup by key, such as `get` or `contains`.
+    Get,
+    /// An `insert`, whether it added a new entry or replaced one.
+    ///
+    /// The other insertion methods, such as `try_insert`,
+    /// `insert_unique_unchecked`, and the entry API, report this too. Methods
+    /// that may only look the key up, like `entry(..).or_insert(..)` or
+    /// `get_or_insert_with`, report it only when they store a value.
+    Insert,
+    /// A `remove` or `take`, whether or not the key was present.
+    Remove,
+    /// The table was reallocated to grow, by any operation that can add
+    /// entries or reserve capacity.
+    Resize,
+}
+
+/// Receives operation events from a [`HashMap`] or [`HashSet`] it is
+/// registered with.
+///
+/// Events are delivered synchronously, on the thread performing the
+/// operation, so `record` should be cheap, e.g. an atomic increment.
+///
+/// [`HashMap`]: crate::hash_map::HashMap
+/// [`HashSet`]: crate::hash_set::HashSet
+pub trait MetricsSink: Sync {
+    /// Records a single operation.
+    fn record(
+        &self,
+        event: MetricsEvent,
+    );
+}
+
+/// Reports `event` to `sink`, if one is registered.
+#[inline(always)]
+pub(crate) fn record(
+    sink: Option<&'static dyn MetricsSink>,
+    event: MetricsEvent,
+) {
+    if let Some(sink) = sink {
+        sink.record(event);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering;
+
+    use super::MetricsEvent;
+    use super::MetricsSink;
+
+    /// Counts the events it receives, indexed as `[Get, Insert, Remove,
+    /// Resize]`.
+    pub(crate) struct CountingSink([AtomicUsize; 4]);
+
+    impl CountingSink {
+        pub(crate) const fn new() -> Self {
+            Self([const { AtomicUsize::new(0) }; 4])
+        }
+
+        pub(crate) fn counts(&self) -> [usize; 4] {
+            self.0.each_ref().map(|count| count.load(Ordering::Relaxed))
+        }
+    }
+
+    impl MetricsSink for CountingSink {
+        fn record(
+            &self,
+            event: MetricsEvent,
+        ) {
+            let index = match event {
+                MetricsEvent::Get => 0,
+                MetricsEvent::Insert => 1,
+                MetricsEvent::Remove => 2,
+                MetricsEvent::Resize => 3,
+            };
+            self.0[index].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}